@@ -4,10 +4,9 @@
 //! They are usually emitted as asm instructions operating on variables.
 
 use lex::{Token, TokenType, TokenData, Span, Location};
-use ast::{ScopedId, Identifier, UnaryOperator, BinaryOperator};
+use ast::{ScopedId, IdCell, Identifier, UnaryOperator, BinaryOperator};
 use parse::{ParseResult, ParseError, ExpectedNextType};
 
-use std::cell::Ref;
 
 /// Expression types
 #[derive(Debug, PartialEq, Clone)]
@@ -45,10 +44,24 @@ impl Expression {
             _ => true
         }
     }
+    /// A short description of the kind of expression this is, for use in
+    /// error messages.
+    pub fn description(&self) -> &'static str {
+        use self::Expression::*;
+        match *self {
+            Literal(_) => "a literal",
+            VariableRef(_) => "a variable",
+            BinaryOp(_) => "a binary operation",
+            UnaryOp(_) => "a unary operation",
+            IfExpression(_) => "an if expression",
+            FnCall(_) => "a function call",
+            Assignment(_) => "an assignment"
+        }
+    }
     pub fn expect_value(self) -> ParseResult<Expression> {
         if !self.has_value() {
             Err(ParseError::ExpectedExpression {
-                expected: ExpectedNextType::AnyExpression,
+                expected: ExpectedNextType::Rvalue,
                 got: self
             })
         } else {
@@ -83,6 +96,8 @@ pub enum LiteralValue {
     Bool(bool),
     /// Numeric literals
     Float(f64),
+    /// Character literals
+    Char(char),
     /// `()`
     Unit
 }
@@ -132,7 +147,8 @@ impl Literal {
     pub fn new_float(token: Token, value: f64) -> Literal {
         debug_assert!(
             match token.data() {
-                TokenData::NumberLiteral => true, _ => false
+                TokenData::IntLiteral | TokenData::FloatLiteral => true,
+                _ => false
             },
             "Literal f64 called with bad token {:?}", token);
         Literal {
@@ -141,6 +157,19 @@ impl Literal {
         }
     }
 
+    /// Creates a new character literal from the given token and value.
+    pub fn new_char(token: Token, value: char) -> Literal {
+        debug_assert!(
+            match token.data() {
+                TokenData::CharLiteral => true, _ => false
+            },
+            "Literal char created with bad token {:?}", token);
+        Literal {
+            token,
+            value: LiteralValue::Char(value)
+        }
+    }
+
     pub fn text(&self) -> &str {
         self.token.text()
     }
@@ -253,6 +282,7 @@ pub struct IfExpression {
     condition: Box<Expression>,
     true_expr: Box<Expression>,
     else_expr: Box<Expression>,
+    scoped_id: IdCell,
     span: Span
 }
 impl IfExpression {
@@ -264,7 +294,8 @@ impl IfExpression {
             span: Span::from(start ..= else_expr.span().end()),
             condition: condition,
             true_expr: true_expr,
-            else_expr: else_expr
+            else_expr: else_expr,
+            scoped_id: IdCell::default()
         }
     }
     pub fn condition(&self) -> &Expression {
@@ -277,6 +308,23 @@ impl IfExpression {
         &self.else_expr
     }
 
+    /// ID of the type of the expression's value, which both branches share.
+    ///
+    /// The branches' own types are under `branch_ids`.
+    pub fn id(&self) -> ScopedId {
+        self.scoped_id.get()
+    }
+    pub fn set_id(&self, id: ScopedId) {
+        self.scoped_id.set(id);
+    }
+
+    /// IDs of the types of the true and else branches.
+    pub fn branch_ids(&self) -> (ScopedId, ScopedId) {
+        let true_id = self.id().pushed();
+        let else_id = true_id.incremented();
+        (true_id, else_id)
+    }
+
     pub fn span(&self) -> Span {
         self.span
     }
@@ -306,7 +354,17 @@ impl FnCall {
         &self.args
     }
 
-    pub fn id<'a>(&'a self) -> Ref<'a, ScopedId> {
+    /// Gets the name (if given) and value of each argument, in call order.
+    ///
+    /// Every argument is currently named, but passes which go through this
+    /// won't need to change when unnamed arguments are added (see #45).
+    pub fn arg_exprs(&self) -> Vec<(Option<&str>, &Expression)> {
+        self.args.iter()
+            .map(|arg| (Some(arg.name().name()), arg.expression()))
+            .collect()
+    }
+
+    pub fn id(&self) -> ScopedId {
         self.ident().id()
     }
     pub fn set_id(&self, id: ScopedId) {
@@ -1,93 +1,140 @@
 use smallvec::SmallVec;
 
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// The scope path of a `ScopedId`.
+///
+/// Cap on variables defined in one scope is `u16::MAX`,
+/// Cap on number of scopes is `usize::MAX`
+///
+/// On 64-bit machines, the size/align of `SmallVec<[u16; 11]>` is the
+/// same as `SmallVec<[u16; 1]>` so we don't save space by cacing fewer
+/// indices before allocating.
+/// TODO get numbers for this on 32-bit.
+type IdPath = SmallVec<[u16; 11]>;
 
 /// An identifier which recognizes the concept of scopes.
-#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+///
+/// Each ID owns its path of indices, and caches the hash of that path.
+/// Hashing an ID, such as for a `HashMap<ScopedId, _>` lookup, only hashes
+/// the cached value, and IDs with different hashes are unequal without
+/// comparing their paths.
+///
+/// Paths up to 11 scopes deep are stored inline, so an ID doesn't allocate
+/// and nothing outlives it.
+#[derive(Clone)]
 pub struct ScopedId {
-    /// Cap on variables defined in one scope is `u16::MAX`,
-    /// Cap on number of scopes is `usize::MAX`
-    ///
-    /// On 64-bit machines, the size/align of `SmallVec<[u16; 11]>` is the
-    /// same as `SmallVec<[u16; 1]>` so we don't save space by cacing fewer
-    /// indices before allocating.
-    /// TODO get numbers for this on 32-bit.
-    indices: SmallVec<[u16; 11]>
+    indices: IdPath,
+    hash: u64
 }
 
 impl ScopedId {
+    /// Gets the hash to cache for the path of `indices`.
+    fn hash_indices(indices: &[u16]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        indices.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Gets the ID of the path of `indices`.
+    fn from_indices(indices: IdPath) -> ScopedId {
+        let hash = ScopedId::hash_indices(&indices);
+        ScopedId { indices, hash }
+    }
+
+    /// Applies `change` to this ID's path.
+    fn update<F>(&mut self, change: F) where F: FnOnce(&mut IdPath) {
+        change(&mut self.indices);
+        self.hash = ScopedId::hash_indices(&self.indices);
+    }
+
+    /// Gets the ID of this ID's path after applying `change`.
+    fn changed<F>(&self, change: F) -> ScopedId where F: FnOnce(&mut IdPath) {
+        let mut indices = self.indices.clone();
+        change(&mut indices);
+        ScopedId::from_indices(indices)
+    }
+
     /// Increments this ID to the next `ScopedId` within this scope.
     #[inline]
     pub fn increment(&mut self) {
-        let ix = self.indices.len() - 1;
-        self.indices[ix] += 1;
+        self.update(|indices| {
+            let ix = indices.len() - 1;
+            indices[ix] += 1;
+        })
     }
 
     /// Gets the next `ScopedId` within this scope.
     #[inline]
     pub fn incremented(&self) -> ScopedId {
-        let ix = self.indices.len() - 1;
-        let mut new_indices = self.indices.clone();
-        new_indices[ix] += 1;
-        ScopedId { indices: new_indices }
+        self.changed(|indices| {
+            let ix = indices.len() - 1;
+            indices[ix] += 1;
+        })
     }
 
     /// Decrements this ID to the previous `ScopedId` within this scope.
     #[inline]
     pub fn decrement(&mut self) {
-        let ix = self.indices.len() - 1;
-        debug_assert!(self.indices[ix] != 0,
-            "Attempt to decrement {:?}", self);
-        self.indices[ix] -= 1;
+        self.update(|indices| {
+            let ix = indices.len() - 1;
+            debug_assert!(indices[ix] != 0,
+                "Attempt to decrement Id{:?}", indices);
+            indices[ix] -= 1;
+        })
     }
 
     /// Gets the previous `ScopedId` within this scope.
     #[inline]
     pub fn decremented(&self) -> ScopedId {
-        let ix = self.indices.len() - 1;
-        debug_assert!(self.indices[ix] != 0,
-            "Attempt to get decremented {:?}", self);
-        let mut new_indices = self.indices.clone();
-        new_indices[ix] -= 1;
-        ScopedId { indices: new_indices }
+        self.changed(|indices| {
+            let ix = indices.len() - 1;
+            debug_assert!(indices[ix] != 0,
+                "Attempt to get decremented Id{:?}", indices);
+            indices[ix] -= 1;
+        })
     }
 
     /// Pushes this `ScopedId` to the next scope.
     #[inline]
     pub fn push(&mut self) {
-        self.indices.push(0);
+        self.update(|indices| indices.push(0))
     }
 
     /// Gets a `ScopedId` pushed to the next scope.
     #[inline]
     pub fn pushed(&self) -> ScopedId {
-        let mut new_indices = self.indices.clone();
-        new_indices.push(0);
-        ScopedId { indices: new_indices }
+        self.changed(|indices| indices.push(0))
     }
 
     /// Pops this `ScopedId` to the previous scope.
     #[inline]
     pub fn pop(&mut self) {
-        debug_assert!(!self.indices.is_empty(),
-            "Attempt to pop empty ScopedId");
-        self.indices.pop();
+        self.update(|indices| {
+            debug_assert!(!indices.is_empty(),
+                "Attempt to pop empty ScopedId");
+            indices.pop();
+        })
     }
 
     /// Gets a `ScopedId` popped to the previous scope.
     #[inline]
     pub fn popped(&self) -> ScopedId {
-        debug_assert!(!self.indices.is_empty(),
-            "Attempt to get popped empty ScopedId");
-        let mut new_indices = self.indices.clone();
-        new_indices.pop();
-        ScopedId { indices: new_indices }
+        self.changed(|indices| {
+            debug_assert!(!indices.is_empty(),
+                "Attempt to get popped empty ScopedId");
+            indices.pop();
+        })
     }
 
     /// Whether another scopedId has a common prefix with this one.
     pub fn is_subindex_of(&self, other: &ScopedId) -> bool {
         other.indices.len() >= self.indices.len() &&
-            &other.indices[0..self.indices.len() - 1] == &*self.indices
+            other.indices[0..self.indices.len() - 1] == *self.indices
     }
 
     pub fn is_default(&self) -> bool {
@@ -95,6 +142,32 @@ impl ScopedId {
     }
 }
 
+impl PartialEq for ScopedId {
+    fn eq(&self, other: &ScopedId) -> bool {
+        self.hash == other.hash && self.indices == other.indices
+    }
+}
+
+impl Eq for ScopedId { }
+
+impl Hash for ScopedId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash)
+    }
+}
+
+impl PartialOrd for ScopedId {
+    fn partial_cmp(&self, other: &ScopedId) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScopedId {
+    fn cmp(&self, other: &ScopedId) -> Ordering {
+        self.indices.cmp(&other.indices)
+    }
+}
+
 impl fmt::Debug for ScopedId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Id{:?}", self.indices)
@@ -103,8 +176,207 @@ impl fmt::Debug for ScopedId {
 
 impl Default for ScopedId {
     fn default() -> ScopedId {
-        let mut indices = SmallVec::new();
+        let mut indices = IdPath::new();
         indices.push(0);
-        ScopedId { indices: indices }
+        ScopedId::from_indices(indices)
+    }
+}
+
+/// A `ScopedId` which can be set through a shared reference.
+///
+/// The identify pass sets the IDs of AST nodes in place.
+#[derive(Default)]
+pub struct IdCell {
+    id: Cell<ScopedId>
+}
+
+impl IdCell {
+    /// Gets the ID in this cell.
+    pub fn get(&self) -> ScopedId {
+        let id = self.id.take();
+        self.id.set(id.clone());
+        id
+    }
+
+    /// Replaces the ID in this cell.
+    pub fn set(&self, id: ScopedId) {
+        self.id.set(id);
+    }
+}
+
+impl Clone for IdCell {
+    fn clone(&self) -> IdCell {
+        IdCell { id: Cell::new(self.get()) }
+    }
+}
+
+impl PartialEq for IdCell {
+    fn eq(&self, other: &IdCell) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl Eq for IdCell { }
+
+impl fmt::Debug for IdCell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+/// An `IdCell` which may be empty.
+#[derive(Default)]
+pub struct OptionIdCell {
+    id: Cell<Option<ScopedId>>
+}
+
+impl OptionIdCell {
+    /// Gets the ID in this cell, if it has been set.
+    pub fn get(&self) -> Option<ScopedId> {
+        let id = self.id.take();
+        self.id.set(id.clone());
+        id
+    }
+
+    /// Puts an ID in this cell.
+    pub fn set(&self, id: ScopedId) {
+        self.id.set(Some(id));
+    }
+
+    /// Whether an ID has been put in this cell.
+    pub fn is_some(&self) -> bool {
+        self.get().is_some()
+    }
+}
+
+impl Clone for OptionIdCell {
+    fn clone(&self) -> OptionIdCell {
+        OptionIdCell { id: Cell::new(self.get()) }
+    }
+}
+
+impl PartialEq for OptionIdCell {
+    fn eq(&self, other: &OptionIdCell) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl fmt::Debug for OptionIdCell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::hash::{Hash, Hasher};
+    use std::mem;
+    use super::{IdCell, OptionIdCell, ScopedId};
+
+    /// Counts the bytes it is asked to hash.
+    #[derive(Default)]
+    struct CountingHasher {
+        bytes: usize
+    }
+
+    impl Hasher for CountingHasher {
+        fn finish(&self) -> u64 {
+            self.bytes as u64
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.bytes += bytes.len();
+        }
+    }
+
+    fn hashed_bytes(id: &ScopedId) -> usize {
+        let mut hasher = CountingHasher::default();
+        id.hash(&mut hasher);
+        hasher.bytes
+    }
+
+    #[test]
+    fn ids_with_the_same_path_are_equal() {
+        let mut built = ScopedId::default();
+        built.push();
+        built.increment();
+        built.increment();
+        let derived = ScopedId::default().pushed().incremented().incremented();
+        assert_eq!(built, derived);
+        assert_eq!(built.hash, derived.hash);
+        assert_eq!(format!("{:?}", built), "Id[0, 2]");
+
+        let mut popped = derived.popped();
+        assert!(popped.is_default());
+        popped.increment();
+        assert_ne!(popped, ScopedId::default());
+        assert_eq!(popped.decremented(), ScopedId::default());
+    }
+
+    #[test]
+    fn ids_are_ordered_by_path() {
+        let first = ScopedId::default().incremented();
+        let second = first.incremented();
+        let inner = first.pushed().incremented().incremented();
+        let mut ids = vec![inner.clone(), second.clone(), first.clone()];
+        ids.sort();
+        assert_eq!(ids, vec![first, inner, second]);
+    }
+
+    #[test]
+    fn id_lookups_hash_the_same_amount_at_any_depth() {
+        let shallow = ScopedId::default().incremented();
+        let mut deep = ScopedId::default();
+        for _ in 0..20 {
+            deep.push();
+            deep.increment();
+        }
+        assert_eq!(hashed_bytes(&shallow), hashed_bytes(&deep));
+        assert_eq!(hashed_bytes(&deep), mem::size_of::<u64>());
+
+        let mut ids = HashMap::new();
+        let mut id = deep.pushed();
+        for ix in 0..1000 {
+            id.increment();
+            ids.insert(id.clone(), ix);
+        }
+        let mut id = deep.pushed();
+        for ix in 0..1000 {
+            id.increment();
+            assert_eq!(ids.get(&id), Some(&ix));
+        }
+    }
+
+    #[test]
+    fn ids_store_paths_inline_up_to_eleven_scopes() {
+        let mut id = ScopedId::default();
+        for _ in 0..10 {
+            id.push();
+            id.increment();
+        }
+        assert_eq!(id.indices.len(), 11);
+        assert!(!id.indices.spilled());
+        id.push();
+        assert!(id.indices.spilled());
+        // Cells hold the ID itself, with nothing shared between them.
+        assert_eq!(mem::size_of::<IdCell>(), mem::size_of::<ScopedId>());
+        let cell = IdCell::default();
+        cell.set(id.clone());
+        assert!(cell.get().indices.spilled());
+        assert_eq!(cell.get(), id);
+    }
+
+    #[test]
+    fn option_id_cells_tell_empty_from_default() {
+        let cell = OptionIdCell::default();
+        assert!(!cell.is_some());
+        assert_eq!(cell.get(), None);
+        cell.set(ScopedId::default());
+        assert!(cell.is_some());
+        assert_eq!(cell.get(), Some(ScopedId::default()));
+        let id = ScopedId::default().pushed().incremented();
+        cell.set(id.clone());
+        assert_eq!(cell.clone().get(), Some(id));
     }
 }
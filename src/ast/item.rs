@@ -2,23 +2,40 @@
 //!
 //! An `Item` is a declaration made in the root context of a program
 //! -- namely declarations such as `class`, `enum`, `struct`.
-use std::cell::Ref;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use lex::{Location, Span};
-use ast::{Identifier, Block, TypeExpression, ScopedId};
+use ast::{Identifier, Block, Expression, TypeExpression, ScopedId};
 
 /// A single "unit" of parsed code.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug)]
 pub struct Unit {
     items: Vec<Item>,
-    span: Span
+    span: Span,
+    /// Whether the unit's `ScopedId`s have been set by the identify pass.
+    identified: AtomicBool
 }
 
 impl Unit {
     /// Create a new unit with the given block
     pub fn new(span: Span, items: Vec<Item>) -> Unit {
-        Unit { span, items }
+        Unit { span, items, identified: AtomicBool::new(false) }
+    }
+
+    /// Whether the identify pass has been run on this unit.
+    pub fn is_identified(&self) -> bool {
+        self.identified.load(Ordering::Relaxed)
+    }
+
+    /// Marks this unit as having been identified.
+    ///
+    /// Identifying a unit twice would silently give its nodes new IDs, so
+    /// this gives `false` if the unit had already been marked.
+    pub fn mark_identified(&self) -> bool {
+        !self.identified.swap(true, Ordering::Relaxed)
     }
+
     /// Gets the collection of exported items
     pub fn items(&self) -> &[Item] {
         &self.items
@@ -27,6 +44,46 @@ impl Unit {
     pub fn span(&self) -> Span {
         self.span
     }
+
+    /// Adds the items of `other` after this unit's items, such as to make
+    /// a prelude's fns available to a unit before it's identified.
+    ///
+    /// The unit keeps its own span. Items of `other` with the same name as
+    /// one of this unit's items are left out. The identifiers of each such
+    /// pair are given back, this unit's first, so they can be reported.
+    pub fn merge(&mut self, other: Unit) -> Vec<(Identifier, Identifier)> {
+        debug_assert!(!self.is_identified() && !other.is_identified(),
+            "Merging units which have already been identified");
+        let mut clashes = Vec::new();
+        for item in other.items {
+            let clash = self.items.iter()
+                .find(|existing| existing.ident().name() == item.ident().name())
+                .map(|existing| existing.ident().clone());
+            match clash {
+                Some(existing) => clashes.push((existing, item.ident().clone())),
+                None => self.items.push(item)
+            }
+        }
+        clashes
+    }
+}
+
+impl Clone for Unit {
+    fn clone(&self) -> Unit {
+        Unit {
+            items: self.items.clone(),
+            span: self.span,
+            identified: AtomicBool::new(self.is_identified())
+        }
+    }
+}
+
+/// Units are compared by their items, whether or not they have been
+/// identified.
+impl PartialEq for Unit {
+    fn eq(&self, other: &Unit) -> bool {
+        self.items == other.items && self.span == other.span
+    }
 }
 
 /// Items exported from a protosnirk program
@@ -34,15 +91,29 @@ impl Unit {
 pub enum Item {
     /// Declaraion of a function
     BlockFnDeclaration(BlockFnDeclaration),
+    /// Declaration of a function defined outside of protosnirk
+    ExternFnDeclaration(ExternFnDeclaration),
     /// Declaration of a type alias
     Typedef(Typedef)
 }
 
+impl Item {
+    /// Get the identifier of the declared item
+    pub fn ident(&self) -> &Identifier {
+        match *self {
+            Item::BlockFnDeclaration(ref block_fn) => block_fn.ident(),
+            Item::ExternFnDeclaration(ref extern_fn) => extern_fn.ident(),
+            Item::Typedef(ref typedef) => typedef.ident()
+        }
+    }
+}
+
 /// Declaration of a function
 #[derive(Debug, Clone, PartialEq)]
 pub struct BlockFnDeclaration {
     ident: Identifier,
     params: Vec<(Identifier, TypeExpression)>,
+    param_defaults: Vec<Option<Expression>>,
     ret_ty: TypeExpression,
     explicit_ret_ty: bool,
     block: Block,
@@ -54,6 +125,7 @@ impl BlockFnDeclaration {
     pub fn new(start: Location,
                ident: Identifier,
                params: Vec<(Identifier, TypeExpression)>,
+               param_defaults: Vec<Option<Expression>>,
                ret_ty: TypeExpression,
                explicit_ret_ty: bool,
                block: Block)
@@ -62,6 +134,7 @@ impl BlockFnDeclaration {
             span: Span::from(start ..= block.span().end()),
             ident,
             params,
+            param_defaults,
             ret_ty,
             explicit_ret_ty,
             block
@@ -75,13 +148,39 @@ impl BlockFnDeclaration {
     pub fn params(&self) -> &[(Identifier, TypeExpression)] {
         &self.params
     }
+    /// Get the number of params the function takes
+    pub fn arity(&self) -> usize {
+        self.params.len()
+    }
+    /// Get the names of the params, in order
+    pub fn param_names(&self) -> Vec<&str> {
+        self.params.iter().map(|(param, _)| param.name()).collect()
+    }
+    /// Get each param with its type from a mapping of IDs to types, such as
+    /// the checker's `TypeMapping`.
+    ///
+    /// Params which aren't in the mapping are paired with `None`.
+    pub fn typed_params<'a, T>(&'a self, types: &'a HashMap<ScopedId, T>)
+                               -> Vec<(&'a Identifier, Option<&'a T>)> {
+        self.params.iter()
+            .map(|(param, _)| (param, types.get(&param.id())))
+            .collect()
+    }
+    /// Get the default values of the params, in the same order as `params`.
+    pub fn param_defaults(&self) -> &[Option<Expression>] {
+        &self.param_defaults
+    }
+    /// Get the default value of the param at index `ix`, if it has one.
+    pub fn param_default(&self, ix: usize) -> Option<&Expression> {
+        self.param_defaults.get(ix).and_then(|default| default.as_ref())
+    }
     pub fn return_type(&self) -> &TypeExpression {
         &self.ret_ty
     }
     pub fn has_explicit_return_type(&self) -> bool {
         self.explicit_ret_ty
     }
-    pub fn id<'a>(&'a self) -> Ref<'a, ScopedId> {
+    pub fn id(&self) -> ScopedId {
         self.ident.id()
     }
     pub fn set_id(&self, id: ScopedId) {
@@ -89,7 +188,7 @@ impl BlockFnDeclaration {
     }
     /// Get the textual name of the function
     pub fn name(&self) -> &str {
-        &self.ident.name()
+        self.ident.name()
     }
     /// Get the block inside the function
     pub fn block(&self) -> &Block {
@@ -101,6 +200,60 @@ impl BlockFnDeclaration {
     }
 }
 
+/// Declaration of an external function, which is linked in from elsewhere.
+///
+/// ```text
+/// extern fn sqrt(x: float) -> float
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternFnDeclaration {
+    ident: Identifier,
+    params: Vec<(Identifier, TypeExpression)>,
+    ret_ty: TypeExpression,
+    span: Span
+}
+
+impl ExternFnDeclaration {
+    /// Create a new ExternFnDeclaration
+    pub fn new(start: Location,
+               ident: Identifier,
+               params: Vec<(Identifier, TypeExpression)>,
+               ret_ty: TypeExpression)
+               -> ExternFnDeclaration {
+        ExternFnDeclaration {
+            span: Span::from(start ..= ret_ty.span().end()),
+            ident,
+            params,
+            ret_ty
+        }
+    }
+
+    /// Get the identifier of the function
+    pub fn ident(&self) -> &Identifier {
+        &self.ident
+    }
+    pub fn params(&self) -> &[(Identifier, TypeExpression)] {
+        &self.params
+    }
+    pub fn return_type(&self) -> &TypeExpression {
+        &self.ret_ty
+    }
+    pub fn id(&self) -> ScopedId {
+        self.ident.id()
+    }
+    pub fn set_id(&self, id: ScopedId) {
+        self.ident.set_id(id);
+    }
+    /// Get the textual name of the function
+    pub fn name(&self) -> &str {
+        self.ident.name()
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
 /// Declaration of a type alias
 #[derive(Debug, Clone, PartialEq)]
 pub struct Typedef {
@@ -125,7 +278,7 @@ impl Typedef {
         &self.alias_ident
     }
 
-    pub fn id<'a>(&'a self) -> Ref<'a, ScopedId> {
+    pub fn id(&self) -> ScopedId {
         self.alias_ident.id()
     }
 
@@ -145,3 +298,28 @@ impl Typedef {
         self.span
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use identify::{ConcreteType, NamedType};
+    use pipeline::check_source;
+    use super::Item;
+
+    #[test]
+    fn block_fn_params_are_paired_with_their_types() {
+        let checked = check_source("fn f(x: float, flag: bool) -> float\n\
+                                   \x20   if flag => x else 0\n");
+        let block_fn = match checked.unit().items()[0] {
+            Item::BlockFnDeclaration(ref block_fn) => block_fn,
+            ref other => panic!("Expected a fn declaration, got {:?}", other)
+        };
+        assert_eq!(block_fn.arity(), 2);
+        assert_eq!(block_fn.param_names(), ["x", "flag"]);
+        let named = |name: &str| ConcreteType::Named(NamedType::new(name.into()));
+        let typed = block_fn.typed_params(checked.type_map()).into_iter()
+            .map(|(param, concrete)| (param.name(), concrete))
+            .collect::<Vec<_>>();
+        assert_eq!(typed, [("x", Some(&named("float"))),
+                           ("flag", Some(&named("bool")))]);
+    }
+}
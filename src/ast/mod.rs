@@ -25,19 +25,17 @@ pub use self::stmt::*;
 pub use self::operator::*;
 pub use self::types::*;
 
-use std::cell::{RefCell, Ref};
-
 use lex::{Token, Span, Location};
 
 /// Basic identifier type
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Identifier {
     token: Token,
-    id: RefCell<ScopedId>,
+    id: IdCell,
 }
 impl Identifier {
     pub fn new(token: Token) -> Self {
-        Identifier { token, id: RefCell::default() }
+        Identifier { token, id: IdCell::default() }
     }
     pub fn name(&self) -> &str {
         &self.token.text()
@@ -46,14 +44,24 @@ impl Identifier {
         &self.token
     }
 
-    pub fn id<'a>(&'a self) -> Ref<'a, ScopedId> {
-        self.id.borrow()
+    pub fn id(&self) -> ScopedId {
+        self.id.get()
     }
 
+    /// Sets the ID of this identifier.
+    ///
+    /// Passes should never give an identifier the default ID. This panics
+    /// in debug builds if they do, and otherwise logs an error and leaves
+    /// the existing ID alone.
     pub fn set_id(&self, index: ScopedId) {
-        debug_assert!(!index.is_default(),
-            "Attempted to reset the ID of {:?}", self);
-        *self.id.borrow_mut() = index;
+        if index.is_default() {
+            if cfg!(debug_assertions) {
+                panic!("Attempted to reset the ID of {:?}", self);
+            }
+            error!("Attempted to reset the ID of {:?}, ignoring it", self);
+            return
+        }
+        self.id.set(index);
     }
 
     pub fn span(&self) -> Span {
@@ -73,9 +81,9 @@ pub struct Block {
     /// Statements in the block
     statements: Vec<Statement>,
     /// Identifier used for type checking.
-    scope_id: RefCell<ScopedId>,
+    scope_id: IdCell,
     /// What uses the value of this block as an expression?
-    source: RefCell<Option<ScopedId>>,
+    source: OptionIdCell,
     span: Span
 }
 impl Block {
@@ -90,31 +98,93 @@ impl Block {
         Block {
             statements,
             span: Span::from(start ..= end),
-            scope_id: RefCell::default(),
-            source: RefCell::new(None),
+            scope_id: IdCell::default(),
+            source: OptionIdCell::default(),
         }
     }
 
     pub fn stmts(&self) -> &[Statement] {
         &self.statements
     }
-    pub fn id<'a>(&'a self) -> Ref<'a, ScopedId> {
-        self.scope_id.borrow()
+    pub fn id(&self) -> ScopedId {
+        self.scope_id.get()
     }
     pub fn set_id(&self, id: ScopedId) {
-        *self.scope_id.borrow_mut() = id;
+        self.scope_id.set(id);
     }
-    pub fn source<'a>(&'a self) -> Ref<'a, Option<ScopedId>> {
-        self.source.borrow()
+    pub fn source(&self) -> Option<ScopedId> {
+        self.source.get()
     }
     pub fn set_source(&self, source: ScopedId) {
-        *self.source.borrow_mut() = Some(source);
+        self.source.set(source);
     }
     pub fn has_source(&self) -> bool {
-        self.source.borrow().is_some()
+        self.source.is_some()
+    }
+
+    /// Whether this block produces a value: its last statement is a value
+    /// expression, or it diverges on every path.
+    ///
+    /// This only looks at the block's structure, so it doesn't depend on
+    /// the identify pass.
+    pub fn has_value(&self) -> bool {
+        if self.diverges() {
+            return true
+        }
+        let last = match self.statements.last() {
+            Some(last) => last,
+            None => return false
+        };
+        match *last {
+            Statement::Expression(ref expr) => expr.has_value(),
+            Statement::DoBlock(ref do_block) => do_block.block().has_value(),
+            Statement::IfBlock(ref if_block) => match if_block.else_block() {
+                Some(else_block) => else_block.has_value() &&
+                    if_block.conditionals().iter()
+                        .all(|cond| cond.block().has_value()),
+                None => false
+            },
+            Statement::Return(_) | Statement::Assert(_)
+                | Statement::Declaration(_) => false
+        }
+    }
+
+    /// Whether control never reaches the end of this block because one of
+    /// its statements diverges.
+    pub fn diverges(&self) -> bool {
+        self.statements.iter().any(Statement::diverges)
     }
 
     pub fn span(&self) -> Span {
         self.span
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use lex::{Location, Token};
+    use super::{Identifier, ScopedId};
+
+    fn ident_with_id() -> (Identifier, ScopedId) {
+        let ident = Identifier::new(Token::new_ident("x", Location::default()));
+        let id = ScopedId::default().pushed().incremented();
+        ident.set_id(id.clone());
+        (ident, id)
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Attempted to reset the ID")]
+    fn setting_default_id_panics_in_debug() {
+        let (ident, _id) = ident_with_id();
+        ident.set_id(ScopedId::default());
+    }
+
+    #[test]
+    #[cfg(not(debug_assertions))]
+    fn setting_default_id_is_ignored_in_release() {
+        let (ident, id) = ident_with_id();
+        ident.set_id(ScopedId::default());
+        assert_eq!(ident.id(), id);
+    }
+}
@@ -14,6 +14,8 @@ pub enum BinaryOperator {
     Division,
     /// Numeric modulus
     Modulus,
+    /// Numeric exponentiation
+    Power,
     /// Equality test
     Equality,
     /// Non-equality test
@@ -28,6 +30,46 @@ pub enum BinaryOperator {
     GreaterThanEquals,
 }
 
+impl BinaryOperator {
+    /// Whether this operator compares its operands, giving a `bool`.
+    pub fn is_comparison(self) -> bool {
+        use self::BinaryOperator::*;
+        match self {
+            Equality | NonEquality => true,
+            _ => self.is_ordering()
+        }
+    }
+
+    /// Whether this operator compares the order of numbers.
+    pub fn is_ordering(self) -> bool {
+        use self::BinaryOperator::*;
+        matches!(self,
+                 LessThan | GreaterThan | LessThanEquals | GreaterThanEquals)
+    }
+
+    /// Name of the compiler-provided fn implementing this operator, which is
+    /// referenced in code as `(+)`.
+    ///
+    /// Only operators on a single type of operand have a fn, so `==` and
+    /// `!=` don't.
+    pub fn fn_name(self) -> Option<&'static str> {
+        use self::BinaryOperator::*;
+        match self {
+            Addition => Some("(+)"),
+            Subtraction => Some("(-)"),
+            Multiplication => Some("(*)"),
+            Division => Some("(/)"),
+            Modulus => Some("(%)"),
+            Power => Some("(**)"),
+            LessThan => Some("(<)"),
+            GreaterThan => Some("(>)"),
+            LessThanEquals => Some("(<=)"),
+            GreaterThanEquals => Some("(>=)"),
+            Equality | NonEquality => None
+        }
+    }
+}
+
 /// Unary operators
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum UnaryOperator {
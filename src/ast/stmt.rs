@@ -5,15 +5,16 @@
 /// `Expression`s are because of their ability to use indentation.
 
 use lex::{Span, Location};
-use ast::{Expression, Identifier, TypeExpression, Block, ScopedId};
+use ast::{Expression, Identifier, TypeExpression, Block, ScopedId, IdCell, OptionIdCell};
+use ast::{BinaryOperation, BinaryOperator};
 
-use std::cell::{RefCell, Ref};
 
 /// Statement representation
 #[derive(Debug, PartialEq, Clone)]
 pub enum Statement {
     Expression(Expression),
     Return(Return),
+    Assert(Assert),
     Declaration(Declaration),
     DoBlock(DoBlock),
     IfBlock(IfBlock)
@@ -25,17 +26,31 @@ impl Statement {
         match self {
             Expression(ref inner) => inner.has_value(),
             Return(ref return_) => return_.has_value(),
+            Assert(_) => false,
             DoBlock(ref do_block) => do_block.has_source(),
             IfBlock(ref if_block) => if_block.has_source(),
             Declaration(_) => false
         }
     }
 
+    /// Whether control never continues past this statement: it returns,
+    /// or is a block which returns on every path.
+    pub fn diverges(&self) -> bool {
+        use self::Statement::*;
+        match self {
+            Return(_) => true,
+            DoBlock(ref do_block) => do_block.block().diverges(),
+            IfBlock(ref if_block) => if_block.diverges(),
+            Expression(_) | Assert(_) | Declaration(_) => false
+        }
+    }
+
     pub fn span(&self) -> Span {
         use self::Statement::*;
         match self {
             Expression(ref e) => e.span(),
             Return(ref r) => r.span(),
+            Assert(ref a) => a.span(),
             DoBlock(ref d) => d.span(),
             IfBlock(ref i) => i.span(),
             Declaration(ref d) => d.span()
@@ -84,6 +99,71 @@ impl Return {
     }
 }
 
+/// Assertion that a condition holds at runtime
+///
+/// If the condition is false the program calls the compiler's panic
+/// handler, which does not return.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Assert {
+    condition: Box<Expression>,
+    equality: bool,
+    scoped_id: IdCell,
+    span: Span
+}
+
+impl Assert {
+    pub fn new(start: Location, condition: Box<Expression>) -> Assert {
+        Assert {
+            span: Span::from(start ..= condition.span().end()),
+            condition,
+            equality: false,
+            scoped_id: IdCell::default()
+        }
+    }
+
+    /// Creates an `assert_eq(left, right)`, which asserts `left == right`
+    /// and shows both values if they differ.
+    pub fn new_equality(start: Location,
+                        left: Box<Expression>,
+                        right: Box<Expression>,
+                        end: Location) -> Assert {
+        let condition = Expression::BinaryOp(BinaryOperation::new(
+            BinaryOperator::Equality, left, right));
+        Assert {
+            span: Span::from(start ..= end),
+            condition: Box::new(condition),
+            equality: true,
+            scoped_id: IdCell::default()
+        }
+    }
+
+    pub fn condition(&self) -> &Expression {
+        &self.condition
+    }
+
+    /// The compared values of an `assert_eq`, which are the operands of its
+    /// `==` condition.
+    pub fn equality_operands(&self) -> Option<(&Expression, &Expression)> {
+        match *self.condition {
+            Expression::BinaryOp(ref bin_op) if self.equality =>
+                Some((bin_op.left(), bin_op.right())),
+            _ => None
+        }
+    }
+
+    /// ID of the type of an `assert_eq`'s values, which must be the same.
+    pub fn id(&self) -> ScopedId {
+        self.scoped_id.get()
+    }
+    pub fn set_id(&self, id: ScopedId) {
+        self.scoped_id.set(id);
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
 /// Variable declaration
 #[derive(Debug, PartialEq, Clone)]
 pub struct Declaration {
@@ -120,7 +200,7 @@ impl Declaration {
     pub fn ident(&self) -> &Identifier {
         &self.ident
     }
-    pub fn id<'a>(&'a self) -> Ref<'a, ScopedId> {
+    pub fn id(&self) -> ScopedId {
         self.ident().id()
     }
     pub fn set_id(&self, id: ScopedId) {
@@ -153,7 +233,7 @@ impl DoBlock {
         &self.block
     }
 
-    pub fn id<'a>(&'a self) -> Ref<'a, ScopedId> {
+    pub fn id(&self) -> ScopedId {
         self.block.id()
     }
 
@@ -161,7 +241,7 @@ impl DoBlock {
         self.block.set_id(id);
     }
 
-    pub fn source<'a>(&'a self) -> Ref<'a, Option<ScopedId>> {
+    pub fn source(&self) -> Option<ScopedId> {
         self.block.source()
     }
 
@@ -196,8 +276,8 @@ impl DoBlock {
 pub struct IfBlock {
     conditionals: Vec<Conditional>,
     else_block: Option<Block>,
-    scoped_id: RefCell<ScopedId>,
-    source: RefCell<Option<ScopedId>>,
+    scoped_id: IdCell,
+    source: OptionIdCell,
     span: Span
 }
 
@@ -227,8 +307,8 @@ impl IfBlock {
         IfBlock {
             conditionals: conditionals,
             else_block: else_block,
-            scoped_id: RefCell::default(),
-            source: RefCell::new(None),
+            scoped_id: IdCell::default(),
+            source: OptionIdCell::default(),
             span: Span::from(start ..= end)
         }
     }
@@ -238,6 +318,16 @@ impl IfBlock {
     pub fn has_else(&self) -> bool {
         self.else_block.is_some()
     }
+    /// Whether every branch of this if block diverges.
+    ///
+    /// An if block without an `else` can always fall through.
+    pub fn diverges(&self) -> bool {
+        match self.else_block {
+            Some(ref else_block) => else_block.diverges() &&
+                self.conditionals.iter().all(|cond| cond.block().diverges()),
+            None => false
+        }
+    }
     pub fn conditionals(&self) -> &Vec<Conditional> {
         &self.conditionals
     }
@@ -247,21 +337,21 @@ impl IfBlock {
     pub fn else_block(&self) -> Option<&Block> {
         self.else_block.as_ref()
     }
-    pub fn id<'a>(&'a self) -> Ref<'a, ScopedId> {
-        self.scoped_id.borrow()
+    pub fn id(&self) -> ScopedId {
+        self.scoped_id.get()
     }
     pub fn set_id(&self, id: ScopedId) {
-        *self.scoped_id.borrow_mut() = id;
+        self.scoped_id.set(id);
     }
 
-    pub fn source<'a>(&'a self) -> Ref<'a, Option<ScopedId>> {
-        self.source.borrow()
+    pub fn source(&self) -> Option<ScopedId> {
+        self.source.get()
     }
     pub fn set_source(&self, source: ScopedId) {
-        *self.source.borrow_mut() = Some(source);
+        self.source.set(source);
     }
     pub fn has_source(&self) -> bool {
-        self.source.borrow().is_some()
+        self.source.is_some()
     }
 
     pub fn span(&self) -> Span {
@@ -286,7 +376,7 @@ impl Conditional {
         &self.block
     }
 
-    pub fn source<'a>(&'a self) -> Ref<'a, Option<ScopedId>> {
+    pub fn source(&self) -> Option<ScopedId> {
         self.block.source()
     }
 
@@ -6,7 +6,6 @@
 // let anonStruct: struct(x: int, y: int) // anonymous named structs
 // let tuple4: (array: [int], sizedArray: [int: 6] sizedArraySlice: &[int: 5], slice: &[int])
 
-use std::cell::Ref;
 
 use ast::{ScopedId, Identifier, Span};
 
@@ -18,7 +17,7 @@ pub enum TypeExpression {
 }
 
 impl TypeExpression {
-    pub fn id(&self) -> Ref<ScopedId> {
+    pub fn id(&self) -> ScopedId {
         match self {
             &TypeExpression::Named(ref named) => named.id()
         }
@@ -57,7 +56,7 @@ impl NamedTypeExpression {
         self.ident.name()
     }
 
-    pub fn id<'a>(&'a self) -> Ref<'a, ScopedId> {
+    pub fn id(&self) -> ScopedId {
         self.ident.id()
     }
 
@@ -0,0 +1,324 @@
+//! Read-only visitor for analyses which build up their own results.
+
+use ast::*;
+
+/// A visitor which only reads the AST, and accumulates an owned result.
+///
+/// The other visitors are usually handed `&mut` builders and collectors to
+/// fill in. An analysis instead owns whatever it gathers and returns it from
+/// `finish`, so independent analyses (stats, call graphs, lint data) don't
+/// share any state. The AST is `Send`, so they can each be run on their own
+/// thread over a copy of the unit.
+///
+/// Every node has a default method which walks its children, so an analysis
+/// only needs to override the nodes it is interested in. An override of a
+/// node with children can call the matching `walk_*` method to keep walking
+/// into the node.
+pub trait AnalysisVisitor: Sized {
+    /// The result of the analysis.
+    type Output;
+
+    /// Gives the result of the analysis once it has visited the unit.
+    fn finish(self) -> Self::Output;
+
+    /// Runs the analysis over the unit.
+    fn analyze(mut self, unit: &Unit) -> Self::Output {
+        self.visit_unit(unit);
+        self.finish()
+    }
+
+    fn visit_unit(&mut self, unit: &Unit) {
+        for item in unit.items() {
+            self.visit_item(item);
+        }
+    }
+
+    fn visit_item(&mut self, item: &Item) {
+        match *item {
+            Item::BlockFnDeclaration(ref block_fn_decl) => {
+                self.visit_block_fn_decl(block_fn_decl);
+            },
+            Item::ExternFnDeclaration(ref extern_fn_decl) => {
+                self.visit_extern_fn_decl(extern_fn_decl);
+            },
+            Item::Typedef(ref typedef) => {
+                self.visit_typedef(typedef);
+            }
+        }
+    }
+
+    fn visit_block_fn_decl(&mut self, block_fn_decl: &BlockFnDeclaration) {
+        self.walk_block_fn_decl(block_fn_decl);
+    }
+
+    /// Visit the param types and defaults, return type, and block of the fn.
+    fn walk_block_fn_decl(&mut self, block_fn_decl: &BlockFnDeclaration) {
+        for &(_, ref param_ty) in block_fn_decl.params() {
+            self.visit_type_expr(param_ty);
+        }
+        for default in block_fn_decl.param_defaults() {
+            if let Some(ref default) = *default {
+                self.visit_expression(default);
+            }
+        }
+        self.visit_type_expr(block_fn_decl.return_type());
+        self.visit_block(block_fn_decl.block());
+    }
+
+    fn visit_extern_fn_decl(&mut self, extern_fn_decl: &ExternFnDeclaration) {
+        self.walk_extern_fn_decl(extern_fn_decl);
+    }
+
+    /// Visit the param types and return type of the extern fn.
+    fn walk_extern_fn_decl(&mut self, extern_fn_decl: &ExternFnDeclaration) {
+        for &(_, ref param_ty) in extern_fn_decl.params() {
+            self.visit_type_expr(param_ty);
+        }
+        self.visit_type_expr(extern_fn_decl.return_type());
+    }
+
+    fn visit_typedef(&mut self, typedef: &Typedef) {
+        self.walk_typedef(typedef);
+    }
+
+    /// Visit the aliased type.
+    fn walk_typedef(&mut self, typedef: &Typedef) {
+        self.visit_type_expr(typedef.type_expr());
+    }
+
+    fn visit_type_expr(&mut self, _type_expr: &TypeExpression) { }
+
+    fn visit_block(&mut self, block: &Block) {
+        self.walk_block(block);
+    }
+
+    /// Visit each statement of the block.
+    fn walk_block(&mut self, block: &Block) {
+        for stmt in block.stmts() {
+            self.visit_stmt(stmt);
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &Statement) {
+        match *stmt {
+            Statement::Expression(ref expr) => {
+                self.visit_expression(expr);
+            },
+            Statement::Declaration(ref decl) => {
+                self.visit_declaration(decl);
+            },
+            Statement::Return(ref return_) => {
+                self.visit_return_stmt(return_);
+            },
+            Statement::Assert(ref assert) => {
+                self.visit_assert_stmt(assert);
+            },
+            Statement::DoBlock(ref do_block) => {
+                self.visit_do_block(do_block);
+            },
+            Statement::IfBlock(ref if_block) => {
+                self.visit_if_block(if_block);
+            }
+        }
+    }
+
+    fn visit_return_stmt(&mut self, return_: &Return) {
+        self.walk_return_stmt(return_);
+    }
+
+    /// Visit the returned value, if there is one.
+    fn walk_return_stmt(&mut self, return_: &Return) {
+        if let Some(value) = return_.value() {
+            self.visit_expression(value);
+        }
+    }
+
+    fn visit_assert_stmt(&mut self, assert: &Assert) {
+        self.walk_assert_stmt(assert);
+    }
+
+    /// Visit the asserted condition.
+    fn walk_assert_stmt(&mut self, assert: &Assert) {
+        self.visit_expression(assert.condition());
+    }
+
+    fn visit_declaration(&mut self, decl: &Declaration) {
+        self.walk_declaration(decl);
+    }
+
+    /// Visit the declared type, if there is one, and the value.
+    fn walk_declaration(&mut self, decl: &Declaration) {
+        if let Some(type_decl) = decl.type_decl() {
+            self.visit_type_expr(type_decl);
+        }
+        self.visit_expression(decl.value());
+    }
+
+    fn visit_do_block(&mut self, do_block: &DoBlock) {
+        self.walk_do_block(do_block);
+    }
+
+    /// Visit the block.
+    fn walk_do_block(&mut self, do_block: &DoBlock) {
+        self.visit_block(do_block.block());
+    }
+
+    fn visit_if_block(&mut self, if_block: &IfBlock) {
+        self.walk_if_block(if_block);
+    }
+
+    /// Visit each condition and its block, then the else block.
+    fn walk_if_block(&mut self, if_block: &IfBlock) {
+        for cond in if_block.conditionals() {
+            self.visit_expression(cond.condition());
+            self.visit_block(cond.block());
+        }
+        if let Some(else_block) = if_block.else_block() {
+            self.visit_block(else_block);
+        }
+    }
+
+    fn visit_expression(&mut self, expr: &Expression) {
+        match *expr {
+            Expression::Literal(ref literal) => {
+                self.visit_literal_expr(literal);
+            },
+            Expression::VariableRef(ref ident) => {
+                self.visit_var_ref(ident);
+            },
+            Expression::BinaryOp(ref bin_op) => {
+                self.visit_binary_op(bin_op);
+            },
+            Expression::UnaryOp(ref un_op) => {
+                self.visit_unary_op(un_op);
+            },
+            Expression::IfExpression(ref if_expr) => {
+                self.visit_if_expr(if_expr);
+            },
+            Expression::FnCall(ref fn_call) => {
+                self.visit_fn_call(fn_call);
+            },
+            Expression::Assignment(ref assign) => {
+                self.visit_assignment(assign);
+            },
+        }
+    }
+
+    fn visit_literal_expr(&mut self, _literal: &Literal) { }
+
+    fn visit_var_ref(&mut self, _ident: &Identifier) { }
+
+    fn visit_if_expr(&mut self, if_expr: &IfExpression) {
+        self.walk_if_expr(if_expr);
+    }
+
+    /// Visit the condition, then both branches.
+    fn walk_if_expr(&mut self, if_expr: &IfExpression) {
+        self.visit_expression(if_expr.condition());
+        self.visit_expression(if_expr.true_expr());
+        self.visit_expression(if_expr.else_expr());
+    }
+
+    fn visit_unary_op(&mut self, unary_op: &UnaryOperation) {
+        self.walk_unary_op(unary_op);
+    }
+
+    /// Visit the operand.
+    fn walk_unary_op(&mut self, unary_op: &UnaryOperation) {
+        self.visit_expression(unary_op.inner());
+    }
+
+    fn visit_binary_op(&mut self, bin_op: &BinaryOperation) {
+        self.walk_binary_op(bin_op);
+    }
+
+    /// Visit the left operand, then the right.
+    fn walk_binary_op(&mut self, bin_op: &BinaryOperation) {
+        self.visit_expression(bin_op.left());
+        self.visit_expression(bin_op.right());
+    }
+
+    fn visit_fn_call(&mut self, fn_call: &FnCall) {
+        self.walk_fn_call(fn_call);
+    }
+
+    /// Visit each argument of the call.
+    fn walk_fn_call(&mut self, fn_call: &FnCall) {
+        for arg in fn_call.args() {
+            self.visit_expression(arg.expression());
+        }
+    }
+
+    fn visit_assignment(&mut self, assign: &Assignment) {
+        self.walk_assignment(assign);
+    }
+
+    /// Visit the assigned value.
+    fn walk_assignment(&mut self, assign: &Assignment) {
+        self.visit_expression(assign.rvalue());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::thread;
+
+    use ast::*;
+    use ast::visit::tests::CallGraph;
+    use pipeline::check_source;
+    use super::AnalysisVisitor;
+
+    /// Counts the statements in each fn's block, not including nested blocks.
+    #[derive(Default)]
+    struct StatementCounts {
+        counts: BTreeMap<String, usize>
+    }
+
+    impl AnalysisVisitor for StatementCounts {
+        type Output = BTreeMap<String, usize>;
+
+        fn finish(self) -> Self::Output {
+            self.counts
+        }
+
+        fn visit_block_fn_decl(&mut self, block_fn: &BlockFnDeclaration) {
+            self.counts.insert(block_fn.name().to_string(),
+                               block_fn.block().stmts().len());
+        }
+    }
+
+    #[test]
+    fn analyses_run_concurrently_on_copies_of_a_unit() {
+        let source = "fn double(x: float) -> float\n\
+                      \x20   x * 2\n\
+                      fn quad(x: float) -> float\n\
+                      \x20   let y: float = double(x: x)\n\
+                      \x20   double(x: y)\n\
+                      fn main()\n\
+                      \x20   if quad(x: 1) > 2\n\
+                      \x20       double(x: 3)\n";
+        let checked = check_source(source);
+        let unit = checked.unit();
+
+        let counts_unit = unit.clone();
+        let counts = thread::spawn(move ||
+            StatementCounts::default().analyze(&counts_unit));
+        let calls_unit = unit.clone();
+        let calls = thread::spawn(move ||
+            CallGraph::default().analyze(&calls_unit));
+        let counts = counts.join().expect("Statement counts panicked");
+        let calls = calls.join().expect("Call graph panicked");
+
+        let summary = counts.iter()
+            .map(|(name, count)| {
+                format!("{}: {} stmts, calls {:?}", name, count, calls[name])
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(summary, vec![
+            "double: 1 stmts, calls []",
+            "main: 1 stmts, calls [\"quad\", \"double\"]",
+            "quad: 2 stmts, calls [\"double\", \"double\"]",
+        ]);
+    }
+}
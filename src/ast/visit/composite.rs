@@ -0,0 +1,292 @@
+//! Running several analyses in one walk of the AST.
+
+use ast::*;
+use ast::visit::AnalysisVisitor;
+
+/// A visitor which can be run alongside others by a `CompositeVisitor`.
+///
+/// Each method is called when the walk reaches a node, before its children
+/// are visited. Unlike the other visitors it doesn't walk the children
+/// itself, as the `CompositeVisitor` does it once for all of its visitors.
+///
+/// Composed visitors are run in turn on each node, so they must own their
+/// state rather than share it.
+pub trait ComposableVisitor {
+    fn visit_block_fn_decl(&mut self, _block_fn_decl: &BlockFnDeclaration) { }
+    fn visit_extern_fn_decl(&mut self, _extern_fn_decl: &ExternFnDeclaration) { }
+    fn visit_typedef(&mut self, _typedef: &Typedef) { }
+    fn visit_type_expr(&mut self, _type_expr: &TypeExpression) { }
+
+    fn visit_block(&mut self, _block: &Block) { }
+    fn visit_return_stmt(&mut self, _return_: &Return) { }
+    fn visit_assert_stmt(&mut self, _assert: &Assert) { }
+    fn visit_declaration(&mut self, _decl: &Declaration) { }
+    fn visit_do_block(&mut self, _do_block: &DoBlock) { }
+    fn visit_if_block(&mut self, _if_block: &IfBlock) { }
+
+    fn visit_literal_expr(&mut self, _literal: &Literal) { }
+    fn visit_var_ref(&mut self, _ident: &Identifier) { }
+    fn visit_if_expr(&mut self, _if_expr: &IfExpression) { }
+    fn visit_unary_op(&mut self, _unary_op: &UnaryOperation) { }
+    fn visit_binary_op(&mut self, _bin_op: &BinaryOperation) { }
+    fn visit_fn_call(&mut self, _fn_call: &FnCall) { }
+    fn visit_assignment(&mut self, _assign: &Assignment) { }
+}
+
+/// Visitors can be composed by reference, so their results can be read once
+/// the `CompositeVisitor` is done.
+impl<V: ComposableVisitor + ?Sized> ComposableVisitor for &mut V {
+    fn visit_block_fn_decl(&mut self, block_fn_decl: &BlockFnDeclaration) {
+        (**self).visit_block_fn_decl(block_fn_decl)
+    }
+    fn visit_extern_fn_decl(&mut self, extern_fn_decl: &ExternFnDeclaration) {
+        (**self).visit_extern_fn_decl(extern_fn_decl)
+    }
+    fn visit_typedef(&mut self, typedef: &Typedef) {
+        (**self).visit_typedef(typedef)
+    }
+    fn visit_type_expr(&mut self, type_expr: &TypeExpression) {
+        (**self).visit_type_expr(type_expr)
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        (**self).visit_block(block)
+    }
+    fn visit_return_stmt(&mut self, return_: &Return) {
+        (**self).visit_return_stmt(return_)
+    }
+    fn visit_assert_stmt(&mut self, assert: &Assert) {
+        (**self).visit_assert_stmt(assert)
+    }
+    fn visit_declaration(&mut self, decl: &Declaration) {
+        (**self).visit_declaration(decl)
+    }
+    fn visit_do_block(&mut self, do_block: &DoBlock) {
+        (**self).visit_do_block(do_block)
+    }
+    fn visit_if_block(&mut self, if_block: &IfBlock) {
+        (**self).visit_if_block(if_block)
+    }
+
+    fn visit_literal_expr(&mut self, literal: &Literal) {
+        (**self).visit_literal_expr(literal)
+    }
+    fn visit_var_ref(&mut self, ident: &Identifier) {
+        (**self).visit_var_ref(ident)
+    }
+    fn visit_if_expr(&mut self, if_expr: &IfExpression) {
+        (**self).visit_if_expr(if_expr)
+    }
+    fn visit_unary_op(&mut self, unary_op: &UnaryOperation) {
+        (**self).visit_unary_op(unary_op)
+    }
+    fn visit_binary_op(&mut self, bin_op: &BinaryOperation) {
+        (**self).visit_binary_op(bin_op)
+    }
+    fn visit_fn_call(&mut self, fn_call: &FnCall) {
+        (**self).visit_fn_call(fn_call)
+    }
+    fn visit_assignment(&mut self, assign: &Assignment) {
+        (**self).visit_assignment(assign)
+    }
+}
+
+/// Runs several `ComposableVisitor`s in a single walk of a unit.
+///
+/// Each node is given to every visitor, in the order they were added, so
+/// running stats, lints and the like together only walks a large unit once.
+///
+/// ```text
+/// CompositeVisitor::new()
+///     .with_visitor(&mut stats)
+///     .with_visitor(&mut call_graph)
+///     .analyze(&unit);
+/// ```
+#[derive(Default)]
+pub struct CompositeVisitor<'v> {
+    visitors: Vec<Box<dyn ComposableVisitor + 'v>>
+}
+
+impl<'v> CompositeVisitor<'v> {
+    pub fn new() -> CompositeVisitor<'v> {
+        CompositeVisitor { visitors: Vec::new() }
+    }
+
+    /// Adds a visitor to be run after the ones already added.
+    pub fn with_visitor<V: ComposableVisitor + 'v>(mut self, visitor: V)
+                                                  -> CompositeVisitor<'v> {
+        self.visitors.push(Box::new(visitor));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.visitors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.visitors.is_empty()
+    }
+
+    fn each<F>(&mut self, mut visit: F)
+    where F: FnMut(&mut dyn ComposableVisitor) {
+        for visitor in &mut self.visitors {
+            visit(&mut **visitor);
+        }
+    }
+}
+
+/// Only the `visit_*` methods of nodes are overridden, to give the node to
+/// each visitor. The node's children are walked by the usual `walk_*`
+/// methods.
+impl<'v> AnalysisVisitor for CompositeVisitor<'v> {
+    type Output = ();
+
+    fn finish(self) { }
+
+    fn visit_block_fn_decl(&mut self, block_fn_decl: &BlockFnDeclaration) {
+        self.each(|visitor| visitor.visit_block_fn_decl(block_fn_decl));
+        self.walk_block_fn_decl(block_fn_decl);
+    }
+
+    fn visit_extern_fn_decl(&mut self, extern_fn_decl: &ExternFnDeclaration) {
+        self.each(|visitor| visitor.visit_extern_fn_decl(extern_fn_decl));
+        self.walk_extern_fn_decl(extern_fn_decl);
+    }
+
+    fn visit_typedef(&mut self, typedef: &Typedef) {
+        self.each(|visitor| visitor.visit_typedef(typedef));
+        self.walk_typedef(typedef);
+    }
+
+    fn visit_type_expr(&mut self, type_expr: &TypeExpression) {
+        self.each(|visitor| visitor.visit_type_expr(type_expr));
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        self.each(|visitor| visitor.visit_block(block));
+        self.walk_block(block);
+    }
+
+    fn visit_return_stmt(&mut self, return_: &Return) {
+        self.each(|visitor| visitor.visit_return_stmt(return_));
+        self.walk_return_stmt(return_);
+    }
+
+    fn visit_assert_stmt(&mut self, assert: &Assert) {
+        self.each(|visitor| visitor.visit_assert_stmt(assert));
+        self.walk_assert_stmt(assert);
+    }
+
+    fn visit_declaration(&mut self, decl: &Declaration) {
+        self.each(|visitor| visitor.visit_declaration(decl));
+        self.walk_declaration(decl);
+    }
+
+    fn visit_do_block(&mut self, do_block: &DoBlock) {
+        self.each(|visitor| visitor.visit_do_block(do_block));
+        self.walk_do_block(do_block);
+    }
+
+    fn visit_if_block(&mut self, if_block: &IfBlock) {
+        self.each(|visitor| visitor.visit_if_block(if_block));
+        self.walk_if_block(if_block);
+    }
+
+    fn visit_literal_expr(&mut self, literal: &Literal) {
+        self.each(|visitor| visitor.visit_literal_expr(literal));
+    }
+
+    fn visit_var_ref(&mut self, ident: &Identifier) {
+        self.each(|visitor| visitor.visit_var_ref(ident));
+    }
+
+    fn visit_if_expr(&mut self, if_expr: &IfExpression) {
+        self.each(|visitor| visitor.visit_if_expr(if_expr));
+        self.walk_if_expr(if_expr);
+    }
+
+    fn visit_unary_op(&mut self, unary_op: &UnaryOperation) {
+        self.each(|visitor| visitor.visit_unary_op(unary_op));
+        self.walk_unary_op(unary_op);
+    }
+
+    fn visit_binary_op(&mut self, bin_op: &BinaryOperation) {
+        self.each(|visitor| visitor.visit_binary_op(bin_op));
+        self.walk_binary_op(bin_op);
+    }
+
+    fn visit_fn_call(&mut self, fn_call: &FnCall) {
+        self.each(|visitor| visitor.visit_fn_call(fn_call));
+        self.walk_fn_call(fn_call);
+    }
+
+    fn visit_assignment(&mut self, assign: &Assignment) {
+        self.each(|visitor| visitor.visit_assignment(assign));
+        self.walk_assignment(assign);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ast::*;
+    use ast::visit::AnalysisVisitor;
+    use ast::visit::tests::CallGraph;
+    use pipeline::check_source;
+    use super::{ComposableVisitor, CompositeVisitor};
+
+    /// Counts each kind of statement and expression in the unit.
+    #[derive(Default)]
+    struct NodeStats {
+        fns: usize,
+        declarations: usize,
+        calls: usize,
+        literals: usize
+    }
+
+    impl ComposableVisitor for NodeStats {
+        fn visit_block_fn_decl(&mut self, _block_fn: &BlockFnDeclaration) {
+            self.fns += 1;
+        }
+        fn visit_declaration(&mut self, _decl: &Declaration) {
+            self.declarations += 1;
+        }
+        fn visit_fn_call(&mut self, _fn_call: &FnCall) {
+            self.calls += 1;
+        }
+        fn visit_literal_expr(&mut self, _literal: &Literal) {
+            self.literals += 1;
+        }
+    }
+
+    #[test]
+    fn composed_visitors_share_one_walk() {
+        let source = "fn double(x: float) -> float\n\
+                      \x20   x * 2\n\
+                      fn quad(x: float) -> float\n\
+                      \x20   let y: float = double(x: x)\n\
+                      \x20   double(x: y)\n\
+                      fn main()\n\
+                      \x20   if quad(x: 1) > 2\n\
+                      \x20       let z: float = double(x: 3)\n";
+        let checked = check_source(source);
+
+        let mut stats = NodeStats::default();
+        let mut call_graph = CallGraph::default();
+        let composite = CompositeVisitor::new()
+            .with_visitor(&mut stats)
+            .with_visitor(&mut call_graph);
+        assert_eq!(composite.len(), 2);
+        composite.analyze(checked.unit());
+
+        assert_eq!((stats.fns, stats.declarations, stats.calls, stats.literals),
+                   (3, 2, 4, 4));
+        let calls = call_graph.calls.iter()
+            .map(|(name, calls)| format!("{}: {:?}", name, calls))
+            .collect::<Vec<_>>();
+        assert_eq!(calls, vec![
+            "double: []",
+            "main: [\"quad\", \"double\"]",
+            "quad: [\"double\", \"double\"]",
+        ]);
+    }
+}
@@ -1,7 +1,14 @@
 //! Code for AST walking.
 
+mod analysis;
+mod composite;
 mod visitor;
 mod walk;
 
+#[cfg(test)]
+mod tests;
+
+pub use self::analysis::*;
+pub use self::composite::*;
 pub use self::walk::*;
 pub use self::visitor::*;
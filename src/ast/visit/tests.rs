@@ -0,0 +1,45 @@
+//! Visitors shared by the tests of the different kinds of visitor.
+
+use std::collections::BTreeMap;
+
+use ast::*;
+use ast::visit::{AnalysisVisitor, ComposableVisitor};
+
+/// Finds the fns called by each fn.
+///
+/// It can be run on its own as an analysis, or composed with others.
+#[derive(Default)]
+pub struct CallGraph {
+    current_fn: String,
+    pub calls: BTreeMap<String, Vec<String>>
+}
+
+impl ComposableVisitor for CallGraph {
+    fn visit_block_fn_decl(&mut self, block_fn: &BlockFnDeclaration) {
+        self.current_fn = block_fn.name().to_string();
+        self.calls.insert(self.current_fn.clone(), Vec::new());
+    }
+
+    fn visit_fn_call(&mut self, fn_call: &FnCall) {
+        self.calls.get_mut(&self.current_fn).expect("Call outside of fn")
+            .push(fn_call.text().to_string());
+    }
+}
+
+impl AnalysisVisitor for CallGraph {
+    type Output = BTreeMap<String, Vec<String>>;
+
+    fn finish(self) -> Self::Output {
+        self.calls
+    }
+
+    fn visit_block_fn_decl(&mut self, block_fn: &BlockFnDeclaration) {
+        ComposableVisitor::visit_block_fn_decl(self, block_fn);
+        self.walk_block_fn_decl(block_fn);
+    }
+
+    fn visit_fn_call(&mut self, fn_call: &FnCall) {
+        ComposableVisitor::visit_fn_call(self, fn_call);
+        self.walk_fn_call(fn_call);
+    }
+}
@@ -16,6 +16,9 @@ pub trait ItemVisitor {
             Item::BlockFnDeclaration(ref block_fn_decl) => {
                 self.visit_block_fn_decl(block_fn_decl);
             },
+            Item::ExternFnDeclaration(ref extern_fn_decl) => {
+                self.visit_extern_fn_decl(extern_fn_decl);
+            },
             Item::Typedef(ref typedef) => {
                 self.visit_typedef(typedef);
             }
@@ -23,6 +26,7 @@ pub trait ItemVisitor {
     }
 
     fn visit_block_fn_decl(&mut self, block_fn_decl: &BlockFnDeclaration);
+    fn visit_extern_fn_decl(&mut self, extern_fn_decl: &ExternFnDeclaration);
     fn visit_typedef(&mut self, typedef: &Typedef);
 }
 
@@ -63,6 +67,9 @@ pub trait StatementVisitor : ExpressionVisitor {
             Statement::Return(ref return_) => {
                 self.visit_return_stmt(return_);
             },
+            Statement::Assert(ref assert) => {
+                self.visit_assert_stmt(assert);
+            },
             Statement::DoBlock(ref do_block) => {
                 self.visit_do_block(do_block);
             },
@@ -72,6 +79,7 @@ pub trait StatementVisitor : ExpressionVisitor {
         }
     }
     fn visit_return_stmt(&mut self, return_: &Return);
+    fn visit_assert_stmt(&mut self, assert: &Assert);
     fn visit_declaration(&mut self, decl: &Declaration);
     fn visit_if_block(&mut self, if_block: &IfBlock);
     fn visit_do_block(&mut self, do_block: &DoBlock);
@@ -58,6 +58,12 @@ pub fn walk_return<V>(visitor: &mut V, ret: &Return)
     }
 }
 
+#[inline]
+pub fn walk_assert<V>(visitor: &mut V, assert: &Assert)
+                     where V: ExpressionVisitor {
+    visitor.visit_expression(assert.condition());
+}
+
 #[inline]
 pub fn walk_do_block<V>(visitor: &mut V, block: &DoBlock)
                         where V: BlockVisitor {
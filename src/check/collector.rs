@@ -1,6 +1,9 @@
 //! The verifier verifies the initial parse tree, returning a list of errors and warnings
 
+use std::fmt::Write;
+
 use check::CheckerError;
+use lex::Span;
 
 /// Structure to hold compiler errors, warnings, and lints.
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
@@ -35,8 +38,178 @@ impl ErrorCollector {
         &self.lints
     }
 
+    /// Sorts the errors, warnings, and lints by the location of their first
+    /// span, in source order.
+    ///
+    /// Messages at the same location keep the order they were added in, and
+    /// messages without a span go last.
+    pub fn sort_by_location(&mut self) {
+        fn location_key(message: &CheckerError) -> (bool, u32, u32) {
+            match message.offender() {
+                Some(span) => (false, span.start().line(), span.start().column()),
+                None => (true, 0, 0)
+            }
+        }
+        self.errors.sort_by_key(location_key);
+        self.warnings.sort_by_key(location_key);
+        self.lints.sort_by_key(location_key);
+    }
+
+    /// Exports the errors, warnings, and lints as a JSON array of
+    /// diagnostics for editors and other tools.
+    ///
+    /// Each diagnostic has the `source` it was found in, its `message`, a
+    /// `severity` of `"error"`, `"warning"`, or `"lint"`, and the `range` of
+    /// its first span. Its other spans are listed in `related` with the same
+    /// message, followed by its notes with their own ranges. Ranges are
+    /// `null` for messages without a span.
+    pub fn to_json(&self, source_name: &str) -> String {
+        let diagnostics = self.errors.iter().map(|error| ("error", error))
+            .chain(self.warnings.iter().map(|warning| ("warning", warning)))
+            .chain(self.lints.iter().map(|lint| ("lint", lint)));
+        let mut json = String::from("[");
+        for (ix, (severity, message)) in diagnostics.enumerate() {
+            if ix > 0 {
+                json.push(',');
+            }
+            json.push_str("{\"source\":");
+            push_json_string(&mut json, source_name);
+            json.push_str(",\"message\":");
+            push_json_string(&mut json, message.text());
+            write!(json, ",\"severity\":\"{}\",\"range\":", severity)
+                .expect("Could not write to string");
+            push_json_range(&mut json, message.offender());
+            json.push_str(",\"related\":[");
+            let extra_spans = message.spans().iter().skip(1)
+                .map(|span| (message.text(), Some(*span)));
+            let notes = message.notes().iter()
+                .map(|note| (note.text(), note.span()));
+            let related = extra_spans.chain(notes);
+            for (related_ix, (text, span)) in related.enumerate() {
+                if related_ix > 0 {
+                    json.push(',');
+                }
+                json.push_str("{\"message\":");
+                push_json_string(&mut json, text);
+                json.push_str(",\"range\":");
+                push_json_range(&mut json, span);
+                json.push('}');
+            }
+            json.push_str("]}");
+        }
+        json.push(']');
+        json
+    }
+
     pub fn decompose(self)
                 -> (Vec<CheckerError>, Vec<CheckerError>, Vec<CheckerError>) {
         (self.errors, self.warnings, self.lints)
     }
 }
+
+/// Writes the text as a quoted JSON string.
+fn push_json_string(json: &mut String, text: &str) {
+    json.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            ch if (ch as u32) < 0x20 =>
+                write!(json, "\\u{:04x}", ch as u32)
+                    .expect("Could not write to string"),
+            ch => json.push(ch)
+        }
+    }
+    json.push('"');
+}
+
+/// Writes the start and end of the span as a JSON object, or `null`.
+fn push_json_range(json: &mut String, span: Option<Span>) {
+    match span {
+        Some(span) => write!(json,
+            "{{\"start\":{{\"line\":{},\"column\":{}}},\
+             \"end\":{{\"line\":{},\"column\":{}}}}}",
+            span.start().line(), span.start().column(),
+            span.end().line(), span.end().column())
+            .expect("Could not write to string"),
+        None => json.push_str("null")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lex::{Location, Span};
+    use check::CheckerError;
+    use super::ErrorCollector;
+
+    fn error_at(line: u32, column: u32, text: &'static str) -> CheckerError {
+        let start = Location::of().line(line).column(column).build();
+        CheckerError::new(vec![Span::from_location(start, 1)], text)
+    }
+
+    #[test]
+    fn errors_are_sorted_by_location() {
+        let mut errors = ErrorCollector::new();
+        errors.add_error(error_at(3, 1, "third"));
+        errors.add_error(CheckerError::new(vec![], "no span"));
+        errors.add_error(error_at(1, 9, "second"));
+        errors.add_error(error_at(1, 2, "first"));
+        errors.add_error(error_at(3, 1, "also third"));
+        errors.sort_by_location();
+
+        let texts = errors.errors().iter()
+            .map(CheckerError::text)
+            .collect::<Vec<_>>();
+        assert_eq!(texts,
+                   ["first", "second", "third", "also third", "no span"]);
+    }
+
+    #[test]
+    fn errors_are_exported_as_json() {
+        let mut errors = ErrorCollector::new();
+        let start = Location::of().line(2).column(12).build();
+        let literal = Span::from_location(start, 3);
+        errors.add_error(CheckerError::new(vec![literal],
+                                           "Expected \"bool\", found float")
+            .with_note_at(literal, "this literal is a float")
+            .with_note("numeric literals are\nalways floats"));
+
+        assert_eq!(errors.to_json("test.protosnirk"),
+            "[{\"source\":\"test.protosnirk\",\
+               \"message\":\"Expected \\\"bool\\\", found float\",\
+               \"severity\":\"error\",\
+               \"range\":{\"start\":{\"line\":2,\"column\":12},\
+                           \"end\":{\"line\":2,\"column\":15}},\
+               \"related\":[\
+                   {\"message\":\"this literal is a float\",\
+                    \"range\":{\"start\":{\"line\":2,\"column\":12},\
+                                \"end\":{\"line\":2,\"column\":15}}},\
+                   {\"message\":\"numeric literals are\\nalways floats\",\
+                    \"range\":null}]}]");
+        assert_eq!(ErrorCollector::new().to_json("empty"), "[]");
+
+        let mut errors = ErrorCollector::new();
+        let redeclared = Span::from_location(
+            Location::of().line(4).column(3).build(), 1);
+        let declared = Span::from_location(
+            Location::of().line(0).column(3).build(), 1);
+        errors.add_error(CheckerError::new(vec![redeclared, declared],
+                                           "Function f is already declared")
+            .with_note("fns can't be overloaded"));
+        assert_eq!(errors.to_json("test.protosnirk"),
+            "[{\"source\":\"test.protosnirk\",\
+               \"message\":\"Function f is already declared\",\
+               \"severity\":\"error\",\
+               \"range\":{\"start\":{\"line\":4,\"column\":3},\
+                           \"end\":{\"line\":4,\"column\":4}},\
+               \"related\":[\
+                   {\"message\":\"Function f is already declared\",\
+                    \"range\":{\"start\":{\"line\":0,\"column\":3},\
+                                \"end\":{\"line\":0,\"column\":4}}},\
+                   {\"message\":\"fns can't be overloaded\",\
+                    \"range\":null}]}]");
+    }
+}
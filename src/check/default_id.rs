@@ -0,0 +1,149 @@
+//! Check that the identify pass gave every node an id.
+
+use ast::*;
+use ast::visit::AnalysisVisitor;
+use check::CheckerError;
+
+/// Finds identifiers and blocks which still have the default `ScopedId`
+/// after identification.
+///
+/// A unit which was identified without errors should have no default ids
+/// left, so any found here are nodes the identify pass missed. The
+/// pipeline runs this after every successful identify pass, to catch those
+/// bugs before they reach the later passes, which expect every id to be set.
+///
+/// The param names of fn call arguments aren't checked, as they name a
+/// param of the called fn instead of referring to a variable.
+#[derive(Debug, Default)]
+pub struct DefaultIdChecker {
+    errors: Vec<CheckerError>
+}
+
+impl DefaultIdChecker {
+    fn check_ident(&mut self, ident: &Identifier) {
+        if ident.id().is_default() {
+            trace!("Found unidentified name {}", ident.name());
+            self.errors.push(CheckerError::new(vec![ident.span()],
+                format!("Internal error: `{}` was not identified",
+                        ident.name())));
+        }
+    }
+}
+
+impl AnalysisVisitor for DefaultIdChecker {
+    type Output = Vec<CheckerError>;
+
+    fn finish(self) -> Vec<CheckerError> {
+        self.errors
+    }
+
+    fn visit_block_fn_decl(&mut self, block_fn: &BlockFnDeclaration) {
+        self.check_ident(block_fn.ident());
+        for (param, _) in block_fn.params() {
+            self.check_ident(param);
+        }
+        self.walk_block_fn_decl(block_fn);
+    }
+
+    fn visit_extern_fn_decl(&mut self, extern_fn: &ExternFnDeclaration) {
+        self.check_ident(extern_fn.ident());
+        for (param, param_ty) in extern_fn.params() {
+            self.check_ident(param);
+            self.visit_type_expr(param_ty);
+        }
+        self.visit_type_expr(extern_fn.return_type());
+    }
+
+    fn visit_typedef(&mut self, typedef: &Typedef) {
+        self.check_ident(typedef.ident());
+        self.visit_type_expr(typedef.type_expr());
+    }
+
+    fn visit_type_expr(&mut self, type_expr: &TypeExpression) {
+        match *type_expr {
+            TypeExpression::Named(ref named) => self.check_ident(named.ident())
+        }
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        if block.id().is_default() {
+            trace!("Found unidentified block");
+            self.errors.push(CheckerError::new(vec![block.span()],
+                "Internal error: block was not identified"));
+        }
+        for stmt in block.stmts() {
+            self.visit_stmt(stmt);
+        }
+    }
+
+    fn visit_declaration(&mut self, decl: &Declaration) {
+        self.check_ident(decl.ident());
+        if let Some(type_decl) = decl.type_decl() {
+            self.visit_type_expr(type_decl);
+        }
+        self.visit_expression(decl.value());
+    }
+
+    fn visit_var_ref(&mut self, ident: &Identifier) {
+        self.check_ident(ident);
+    }
+
+    fn visit_fn_call(&mut self, fn_call: &FnCall) {
+        self.check_ident(fn_call.ident());
+        self.walk_fn_call(fn_call);
+    }
+
+    fn visit_assignment(&mut self, assign: &Assignment) {
+        self.check_ident(assign.lvalue());
+        self.visit_expression(assign.rvalue());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ast::Unit;
+    use ast::visit::AnalysisVisitor;
+    use lex::IterTokenizer;
+    use parse::Parser;
+    use pipeline::check_source;
+    use super::DefaultIdChecker;
+
+    #[test]
+    fn identified_units_have_no_default_ids() {
+        let source = "typedef Real = float\n\
+                      extern fn sqrt(x: float) -> float\n\
+                      fn root(x: Real, y: float = 2) -> float\n\
+                      \x20   let mut z: float = sqrt(x: x + y)\n\
+                      \x20   if z > 1\n\
+                      \x20       z = z - 1\n\
+                      \x20   do\n\
+                      \x20       z = z + 1\n\
+                      \x20   z\n";
+        let checked = check_source(source);
+        assert_eq!(DefaultIdChecker::default().analyze(checked.unit()), vec![]);
+    }
+
+    #[test]
+    fn unidentified_nodes_are_reported() {
+        let checked = check_source("fn one() -> float\n    1\n");
+        // Simulate the identify pass missing a fn by adding one which was
+        // only parsed.
+        let missed_source = "fn two() -> float\n    let x = 2\n    x\n";
+        let missed = Parser::new(IterTokenizer::new(missed_source.chars()))
+            .parse_unit().expect("Could not parse missed fn");
+        let mut items = checked.unit().items().to_vec();
+        items.extend(missed.items().iter().cloned());
+        let unit = Unit::new(checked.unit().span(), items);
+
+        let errors = DefaultIdChecker::default().analyze(&unit);
+        let texts = errors.iter().map(|error| error.text()).collect::<Vec<_>>();
+        assert_eq!(texts, vec![
+            "Internal error: `two` was not identified",
+            "Internal error: `float` was not identified",
+            "Internal error: block was not identified",
+            "Internal error: `x` was not identified",
+            "Internal error: `x` was not identified",
+        ]);
+        assert!(errors.iter().all(|error| error.offender().is_some()));
+    }
+}
@@ -1,6 +1,8 @@
 //! Result types for Verification
 
-use lex::Span;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use lex::{CowStr, Span};
 
 /// Compiler error returned by an expression verifier.
 ///
@@ -9,13 +11,41 @@ use lex::Span;
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct CheckerError {
     spans: Vec<Span>,
-    text: String,
+    text: CowStr,
+    notes: Vec<ErrorNote>,
 }
 impl CheckerError {
-    pub fn new(spans: Vec<Span>,
-               text: String) -> CheckerError {
-        CheckerError { spans, text }
+    /// Creates a new error with the given text.
+    ///
+    /// Fixed messages can be given as `&'static str`s, which won't be copied.
+    pub fn new<T: Into<CowStr>>(spans: Vec<Span>, text: T) -> CheckerError {
+        CheckerError { spans, text: text.into(), notes: Vec::new() }
+    }
+
+    /// Adds a note explaining the error, shown after its message.
+    pub fn with_note<T: Into<CowStr>>(mut self, text: T) -> CheckerError {
+        self.notes.push(ErrorNote { span: None, text: text.into() });
+        self
+    }
+
+    /// Adds a note about the code at the given span, shown after the error's
+    /// message.
+    pub fn with_note_at<T: Into<CowStr>>(mut self, span: Span, text: T)
+                                         -> CheckerError {
+        self.notes.push(ErrorNote { span: Some(span), text: text.into() });
+        self
+    }
+
+    /// Error for a fn returning a reference to the local declared at
+    /// `declared`, which doesn't live past the end of the fn.
+    pub fn returns_local_reference(returned: Span, declared: Span)
+                                   -> CheckerError {
+        CheckerError::new(vec![returned],
+                          "Returned reference to a local variable")
+            .with_note_at(declared, "The variable is declared here, and \
+                                     does not live past the end of the fn")
     }
+
     pub fn offender(&self) -> Option<Span> {
         self.spans.first().cloned()
     }
@@ -25,4 +55,69 @@ impl CheckerError {
     pub fn text(&self) -> &str {
         &self.text
     }
+    pub fn notes(&self) -> &[ErrorNote] {
+        &self.notes
+    }
+}
+
+impl Display for CheckerError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        try!(f.write_str(&self.text));
+        for note in &self.notes {
+            try!(write!(f, "\n  {}", note));
+        }
+        Ok(())
+    }
+}
+
+/// Extra explanation attached to a `CheckerError`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ErrorNote {
+    span: Option<Span>,
+    text: CowStr,
+}
+impl ErrorNote {
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl Display for ErrorNote {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self.span {
+            Some(ref span) => write!(f, "note: at {}: {}", span, self.text),
+            None => write!(f, "note: {}", self.text)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lex::{Location, Span};
+    use super::CheckerError;
+
+    #[test]
+    fn static_message_is_not_copied() {
+        const MESSAGE: &str = "Something went wrong";
+        let error = CheckerError::new(vec![], MESSAGE);
+        assert_eq!(error.text(), MESSAGE);
+        assert_eq!(error.text().as_ptr(), MESSAGE.as_ptr());
+    }
+
+    #[test]
+    fn notes_are_shown_in_order() {
+        let literal = Span::from(Location::of().line(2).column(12).build() ..=
+                                 Location::of().line(2).column(13).build());
+        let error = CheckerError::new(vec![literal], "Expected bool, found float")
+            .with_note_at(literal, "this literal is a float")
+            .with_note("numeric literals are always floats");
+        assert_eq!(error.notes().len(), 2);
+        assert_eq!(error.to_string(),
+                   "Expected bool, found float\n  \
+                    note: at line 2, column 12 to 13: this literal is a float\n  \
+                    note: numeric literals are always floats");
+    }
 }
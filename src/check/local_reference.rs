@@ -0,0 +1,162 @@
+//! Check for fns returning references to their own locals.
+
+use std::collections::HashMap;
+
+use ast::*;
+use ast::visit::AnalysisVisitor;
+use check::{CheckerError, TypeMapping};
+use identify::ConcreteType;
+use lex::Span;
+
+/// Finds fns which return a reference to one of their `let` variables.
+///
+/// Locals are allocated on the fn's stack, so a reference to one would
+/// dangle once the fn returns:
+///
+/// ```text
+/// fn dangling() -> &float
+///     let x = 0
+///     &x
+///     ^ `x` does not live past the end of the fn
+/// ```
+///
+/// protosnirk doesn't have references yet, so no type is a reference and
+/// this currently never finds anything. It's run anyway so the check is in
+/// place when they're added.
+#[derive(Debug)]
+pub struct LocalReferenceChecker<'a> {
+    types: &'a TypeMapping,
+    /// `let` variables of the current fn, and where they're declared.
+    locals: HashMap<ScopedId, Span>,
+    errors: Vec<CheckerError>
+}
+
+impl<'a> LocalReferenceChecker<'a> {
+    pub fn new(types: &'a TypeMapping) -> LocalReferenceChecker<'a> {
+        LocalReferenceChecker {
+            types,
+            locals: HashMap::new(),
+            errors: Vec::new()
+        }
+    }
+
+    /// Checks a value returned by the current fn.
+    fn check_returned(&mut self, expr: &Expression) {
+        match *expr {
+            Expression::VariableRef(ref ident) => {
+                let is_reference = self.types.get(&ident.id())
+                    .is_some_and(is_reference_type);
+                if !is_reference {
+                    return
+                }
+                if let Some(&declared) = self.locals.get(&ident.id()) {
+                    trace!("Found returned local reference {}", ident.name());
+                    self.errors.push(
+                        CheckerError::returns_local_reference(ident.span(),
+                                                              declared));
+                }
+            },
+            Expression::IfExpression(ref if_expr) => {
+                self.check_returned(if_expr.true_expr());
+                self.check_returned(if_expr.else_expr());
+            },
+            _ => { }
+        }
+    }
+
+    /// Checks the value of a block which is implicitly returned.
+    fn check_returned_block(&mut self, block: &Block) {
+        match block.stmts().last() {
+            Some(Statement::Expression(ref expr)) => self.check_returned(expr),
+            Some(Statement::DoBlock(ref do_block)) =>
+                self.check_returned_block(do_block.block()),
+            Some(Statement::IfBlock(ref if_block)) => {
+                for cond in if_block.conditionals() {
+                    self.check_returned_block(cond.block());
+                }
+                if let Some(else_block) = if_block.else_block() {
+                    self.check_returned_block(else_block);
+                }
+            },
+            _ => { }
+        }
+    }
+}
+
+/// Whether values of the type refer to another value.
+///
+/// This should be updated when pointer or reference types are added.
+fn is_reference_type(concrete: &ConcreteType) -> bool {
+    match *concrete {
+        ConcreteType::Named(_) | ConcreteType::Function(_) => false
+    }
+}
+
+impl<'a> AnalysisVisitor for LocalReferenceChecker<'a> {
+    type Output = Vec<CheckerError>;
+
+    fn finish(self) -> Vec<CheckerError> {
+        self.errors
+    }
+
+    fn visit_block_fn_decl(&mut self, block_fn: &BlockFnDeclaration) {
+        self.locals.clear();
+        self.walk_block_fn_decl(block_fn);
+        self.check_returned_block(block_fn.block());
+    }
+
+    fn visit_declaration(&mut self, decl: &Declaration) {
+        self.locals.insert(decl.id(), decl.ident().span());
+        self.visit_expression(decl.value());
+    }
+
+    fn visit_return_stmt(&mut self, return_: &Return) {
+        if let Some(value) = return_.value() {
+            self.check_returned(value);
+            self.visit_expression(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ast::visit::AnalysisVisitor;
+    use pipeline::check_source;
+    use super::LocalReferenceChecker;
+
+    fn local_references(source: &str) -> usize {
+        let checked = check_source(source);
+        LocalReferenceChecker::new(checked.type_map())
+            .analyze(checked.unit())
+            .len()
+    }
+
+    #[test]
+    fn returned_locals_are_values() {
+        let source = "fn implicit() -> float\n\
+                      \x20   let x = 1\n\
+                      \x20   x\n\
+                      fn explicit() -> float\n\
+                      \x20   let mut y = 1\n\
+                      \x20   y = y + 1\n\
+                      \x20   return y\n\
+                      fn branches(flag: bool) -> float\n\
+                      \x20   let a = 1\n\
+                      \x20   let b = 2\n\
+                      \x20   if flag\n\
+                      \x20       a\n\
+                      \x20   else\n\
+                      \x20       if flag => b else a\n";
+        assert_eq!(local_references(source), 0);
+    }
+
+    #[test]
+    fn returned_params_and_calls_are_values() {
+        let source = "fn id(x: float) -> float\n\
+                      \x20   return x\n\
+                      fn call(x: float) -> float\n\
+                      \x20   let y: float = id(x: x)\n\
+                      \x20   id(x: y)\n";
+        assert_eq!(local_references(source), 0);
+    }
+}
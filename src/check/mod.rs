@@ -3,9 +3,13 @@
 //! This will be moved in the future.
 
 mod collector;
+mod default_id;
 mod errors;
+mod local_reference;
 mod types;
 
 pub use self::collector::ErrorCollector;
-pub use self::errors::CheckerError;
+pub use self::default_id::DefaultIdChecker;
+pub use self::errors::{CheckerError, ErrorNote};
+pub use self::local_reference::LocalReferenceChecker;
 pub use self::types::{TypeConcretifier, TypeMapping};
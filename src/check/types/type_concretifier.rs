@@ -6,7 +6,7 @@ use ast::{*, visit::*};
 use check::{CheckerError, ErrorCollector};
 use identify::{ConcreteType, TypeGraph, TypeScopeBuilder};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Mapping of a `ScopedId` to its concrete type.
 pub type TypeMapping = HashMap<ScopedId, ConcreteType>;
@@ -17,7 +17,9 @@ pub struct TypeConcretifier<'err, 'builder, 'graph> {
     errors: &'err mut ErrorCollector,
     graph: &'graph mut TypeGraph,
     builder: &'builder TypeScopeBuilder,
-    results: HashMap<ScopedId, ConcreteType>
+    results: HashMap<ScopedId, ConcreteType>,
+    /// IDs whose types could not be inferred, which have been reported.
+    unresolved: HashSet<ScopedId>
 }
 
 impl<'err, 'builder, 'graph> TypeConcretifier<'err, 'builder, 'graph> {
@@ -27,7 +29,8 @@ impl<'err, 'builder, 'graph> TypeConcretifier<'err, 'builder, 'graph> {
                -> TypeConcretifier<'err, 'builder, 'graph> {
         TypeConcretifier {
             builder, errors, graph,
-            results: HashMap::new()
+            results: HashMap::new(),
+            unresolved: HashSet::new()
         }
     }
 
@@ -35,12 +38,21 @@ impl<'err, 'builder, 'graph> TypeConcretifier<'err, 'builder, 'graph> {
         self.results
     }
 
+    /// Infers the concrete type of the given ID.
+    ///
+    /// If it can't be inferred, an error is reported at `span` the first
+    /// time. Later uses of the ID aren't reported again, so each unresolved
+    /// variable gets one error at its declaration.
     fn infer_var(&mut self, id: &ScopedId, span: Span, context: String) -> bool {
         trace!("Inferring {:?} in context {:?}", id, context);
         if let Some(_known) = self.results.get(id) {
             trace!("Known type of {:?}", id);
             return true
         }
+        if self.unresolved.contains(id) {
+            trace!("Already reported unresolved {:?}", id);
+            return false
+        }
         let inferred = self.graph.infer_type_of_var(id);
         match inferred {
             Ok((_ix, ty)) => {
@@ -57,6 +69,7 @@ impl<'err, 'builder, 'graph> TypeConcretifier<'err, 'builder, 'graph> {
             },
             Err(possibles) => {
                 debug!("Encountered an error in type inferring");
+                self.unresolved.insert(id.clone());
                 if !possibles.is_empty() {
                     debug!("Conflicts in determining a type");
                     self.errors.add_error(CheckerError::new(
@@ -78,6 +91,14 @@ impl<'err, 'builder, 'graph> TypeConcretifier<'err, 'builder, 'graph> {
         }
     }
 
+    /// Gets the concrete type of an ID without reporting errors.
+    fn concrete_type_of(&mut self, id: &ScopedId) -> Option<ConcreteType> {
+        match self.graph.infer_type_of_var(id) {
+            Ok((_ix, ty)) => self.builder.get_type(&ty).cloned(),
+            Err(_) => None
+        }
+    }
+
     fn _infer_type(&mut self, _id: &ScopedId) -> bool {
         unimplemented!("Type expression and function types are known during
             identification and need no further resolution");
@@ -101,12 +122,16 @@ impl<'err, 'builder, 'graph> ItemVisitor
         self.infer_var(&block_fn.id(), block_fn.span(),
             format!("fn {}", block_fn.name()));
 
-        for &(ref param, ref _param_ty) in block_fn.params() {
+        for (ix, &(ref param, ref _param_ty)) in
+                block_fn.params().iter().enumerate() {
             trace!("Inferring the type of {} param {}",
                 block_fn.name(), param.name());
             self.infer_var(&param.id(), param.span(),
                 format!("fn {} param {}",
                     block_fn.name(), param.name()));
+            if let Some(default) = block_fn.param_default(ix) {
+                self.visit_expression(default);
+            }
         }
 
         // We can't attempt to infer the type of fn params right now because
@@ -114,6 +139,20 @@ impl<'err, 'builder, 'graph> ItemVisitor
         self.visit_block(block_fn.block());
     }
 
+    fn visit_extern_fn_decl(&mut self, extern_fn: &ExternFnDeclaration) {
+        trace!("Visiting declaration of extern fn {}", extern_fn.name());
+        self.infer_var(&extern_fn.id(), extern_fn.span(),
+            format!("extern fn {}", extern_fn.name()));
+
+        for &(ref param, ref _param_ty) in extern_fn.params() {
+            trace!("Inferring the type of {} param {}",
+                extern_fn.name(), param.name());
+            self.infer_var(&param.id(), param.span(),
+                format!("extern fn {} param {}",
+                    extern_fn.name(), param.name()));
+        }
+    }
+
     fn visit_typedef(&mut self, typedef: &Typedef) {
         trace!("Visiting typedef {}", typedef.name());
         self.infer_var(&typedef.id(), typedef.span(),
@@ -152,6 +191,15 @@ impl<'err, 'builder, 'graph> StatementVisitor
         visit::walk_return(self, return_);
     }
 
+    fn visit_assert_stmt(&mut self, assert: &Assert) {
+        trace!("Visiting assert statement");
+        visit::walk_assert(self, assert);
+        if assert.equality_operands().is_some() && !assert.id().is_default() {
+            self.infer_var(&assert.id(), assert.span(),
+                           "values of assert_eq".to_string());
+        }
+    }
+
     fn visit_if_block(&mut self, if_block: &IfBlock) {
         trace!("Visiting if block");
         visit::walk_if_block(self, if_block);
@@ -165,8 +213,15 @@ impl<'err, 'builder, 'graph> StatementVisitor
     fn visit_declaration(&mut self, decl: &Declaration) {
         trace!("Visiting declaration of {}", decl.name());
         self.visit_expression(decl.value());
-        self.infer_var(&decl.id(), decl.span(),
-                       format!("definition of variable {}", decl.name()));
+        if let Expression::IfExpression(ref if_expr) = *decl.value() {
+            // Mismatched branches were already reported.
+            if self.unresolved.contains(&if_expr.id()) {
+                self.unresolved.insert(decl.id());
+                return
+            }
+        }
+        self.infer_var(&decl.id(), decl.ident().span(),
+                       format!("variable {}", decl.name()));
     }
 }
 
@@ -184,6 +239,28 @@ impl<'err, 'builder, 'graph> ExpressionVisitor
 
     fn visit_if_expr(&mut self, if_expr: &IfExpression) {
         visit::walk_if_expr(self, if_expr);
+        if if_expr.id().is_default() {
+            return
+        }
+        let (true_id, else_id) = if_expr.branch_ids();
+        let true_ty = self.concrete_type_of(&true_id);
+        let else_ty = self.concrete_type_of(&else_id);
+        if let (Some(true_ty), Some(else_ty)) = (true_ty, else_ty) {
+            if !else_ty.is_assignable_to(&true_ty) {
+                debug!("Emitting error: if branches are {} and {}",
+                       true_ty, else_ty);
+                self.unresolved.insert(if_expr.id());
+                self.errors.add_error(CheckerError::new(
+                    vec![if_expr.true_expr().span(),
+                         if_expr.else_expr().span()],
+                    format!("if-expression branches have incompatible \
+                             types: `{}` vs `{}`", true_ty, else_ty)
+                ));
+                return
+            }
+        }
+        self.infer_var(&if_expr.id(), if_expr.span(),
+                       "if expression".to_string());
     }
 
     fn visit_unary_op(&mut self, unary_op: &UnaryOperation) {
@@ -195,8 +272,19 @@ impl<'err, 'builder, 'graph> ExpressionVisitor
     }
 
     fn visit_fn_call(&mut self, fn_call: &FnCall) {
-        self.infer_var(&fn_call.id(), fn_call.span(),
+        let inferred = self.infer_var(&fn_call.id(), fn_call.span(),
             format!("Call to {}", fn_call.text()));
+        if inferred {
+            let callee_ty = &self.results[&fn_call.id()];
+            if let ConcreteType::Named(ref named) = *callee_ty {
+                debug!("Emitting error: {} is not callable", fn_call.text());
+                self.errors.add_error(CheckerError::new(
+                    vec![fn_call.span(), fn_call.ident().span()],
+                    format!("{} is not a function and cannot be called - \
+                             it has type {}", fn_call.text(), named.name())
+                ));
+            }
+        }
         for arg in fn_call.args() {
             self.visit_expression(arg.expression());
         }
@@ -211,3 +299,66 @@ impl<'err, 'builder, 'graph> ExpressionVisitor
                     assign.lvalue().name()));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pipeline::{Runner, CompilationError, check_source};
+
+    #[test]
+    fn unresolved_variables_are_reported_once_each() {
+        let source = "fn id(x: float) -> float => x\n\
+                      fn main()\n\
+                      \x20   let y = id(x: 1)\n\
+                      \x20   let z = id(x: y)\n\
+                      \x20   let w = z\n";
+        let result = Runner::from_string(source, "test".into())
+            .parse().expect("Could not parse test source")
+            .identify().expect("Could not identify test source")
+            .check();
+        let errors = match result {
+            Err(CompilationError::CheckingError { errors, .. }) => errors,
+            other => panic!("Expected unresolved types, got {:?}", other)
+        };
+        let reported = errors.errors().iter()
+            .map(|error| {
+                let start = error.offender().expect("Error had no span")
+                    .start();
+                (error.text().to_string(), start.line(), start.column())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(reported, vec![
+            ("Could not determine type of variable y - no info".to_string(),
+             2, 8),
+            ("Could not determine type of variable z - no info".to_string(),
+             3, 8),
+            ("Could not determine type of variable w - no info".to_string(),
+             4, 8),
+        ]);
+    }
+
+    #[test]
+    fn if_expr_branches_must_have_the_same_type() {
+        let source = "fn f(c: bool)\n\
+                      \x20   let x = if c => 1 else true\n";
+        let result = Runner::from_string(source, "test".into())
+            .parse().expect("Could not parse test source")
+            .identify().expect("Could not identify test source")
+            .check();
+        let errors = match result {
+            Err(CompilationError::CheckingError { errors, .. }) => errors,
+            other => panic!("Expected mismatched branches, got {:?}", other)
+        };
+        assert_eq!(errors.errors().len(), 1, "{:?}", errors.errors());
+        let error = &errors.errors()[0];
+        assert_eq!(error.text(),
+                   "if-expression branches have incompatible types: \
+                    `float` vs `bool`");
+        let columns = error.spans().iter()
+            .map(|span| span.start().column())
+            .collect::<Vec<_>>();
+        assert_eq!(columns, vec![20, 27]);
+
+        check_source("fn g(c: bool) -> float\n\
+                     \x20   if c => 1 else 2\n");
+    }
+}
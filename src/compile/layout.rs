@@ -0,0 +1,92 @@
+//! LLVM representation and layout of protosnirk types.
+
+use identify::ConcreteType;
+use llvm::{Context, TargetData, Type};
+
+impl ConcreteType {
+    /// Gets the LLVM type used to represent this type.
+    ///
+    /// # Panics
+    /// If this is a named type other than one of the primitives.
+    pub fn llvm_type<'ctx>(&self, context: &'ctx Context) -> Type<'ctx> {
+        match *self {
+            ConcreteType::Named(ref name) => {
+                match name.name() {
+                    "()" => Type::void(context),
+                    "bool" => Type::int1(context),
+                    "float" => Type::double(context),
+                    "char" => Type::int32(context),
+                    other => panic!("Unexpected concrete type {}", other)
+                }
+            },
+            ConcreteType::Function(ref fn_ty) => {
+                let params = fn_ty.params().iter()
+                    .map(|&(_, ref param_ty)| param_ty.llvm_value_type(context))
+                    .collect::<Vec<_>>();
+                Type::function(&fn_ty.return_ty().llvm_value_type(context),
+                               params, false)
+            }
+        }
+    }
+
+    /// Gets the LLVM type of values of this type, such as variables.
+    ///
+    /// This is the same as `llvm_type`, except that function values are
+    /// pointers to the function.
+    pub fn llvm_value_type<'ctx>(&self, context: &'ctx Context) -> Type<'ctx> {
+        match *self {
+            ConcreteType::Function(_) => self.llvm_type(context).pointer(0),
+            ConcreteType::Named(_) => self.llvm_type(context)
+        }
+    }
+
+    /// Number of bytes a value of this type takes up with the given data
+    /// layout.
+    ///
+    /// `()` takes up no space. A function value is a pointer to the
+    /// function, so it takes up the size of a pointer.
+    pub fn size_in_bytes(&self, context: &Context, layout: &TargetData) -> u64 {
+        match *self {
+            ConcreteType::Named(ref name) if name.name() == "()" => 0,
+            ConcreteType::Named(_) => {
+                layout.abi_size_of(&self.llvm_type(context))
+            },
+            ConcreteType::Function(_) => layout.pointer_size()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use identify::{ConcreteType, FnType, NamedType};
+    use llvm::{Context, Type};
+
+    fn named(name: &str) -> ConcreteType {
+        ConcreteType::Named(NamedType::new(name.to_string()))
+    }
+
+    #[test]
+    fn sizes_follow_the_data_layout() {
+        let context = Context::new();
+        let module = context.new_module("test");
+        module.set_data_layout_str("e-m:e-i64:64-f80:128-n8:16:32:64-S128");
+        let layout = module.target_data();
+
+        let fn_ty = ConcreteType::Function(
+            FnType::new(vec![("x".to_string(), named("float"))],
+                        named("bool")));
+        let sizes = ["()", "bool", "char", "float"].iter()
+            .map(|name| named(name))
+            .chain(Some(fn_ty))
+            .map(|ty| ty.size_in_bytes(&context, &layout))
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, vec![0, 1, 4, 8, 8]);
+
+        // Structs are padded to the alignment of their largest field.
+        let fields = [Type::double(&context), Type::int1(&context)];
+        let pair = Type::structure(&context, &fields, false);
+        assert_eq!(layout.abi_size_of(&pair), 16);
+        let packed_pair = Type::structure(&context, &fields, true);
+        assert_eq!(layout.abi_size_of(&packed_pair), 9);
+    }
+}
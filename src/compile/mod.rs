@@ -1,5 +1,7 @@
+mod layout;
 mod module_compiler;
 mod module_provider;
 
 pub use self::module_provider::{ModuleProvider, SimpleModuleProvider};
-pub use self::module_compiler::ModuleCompiler;
+pub use self::module_compiler::{ModuleCompiler, FnCompiledCallback,
+                                FloatComparisons, DEFAULT_PANIC_HANDLER};
@@ -1,14 +1,43 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use ast::{*, visit::*};
 use identify::ConcreteType;
 use check::TypeMapping;
 use compile::ModuleProvider;
 
-use llvm_sys::{LLVMIntPredicate, LLVMRealPredicate, LLVMTypeKind};
+use llvm_sys::{LLVMTypeKind, LLVMLinkage};
 use llvm_sys::analysis::LLVMVerifierFailureAction;
 
-use llvm::{Module, Value, Type, Builder, Context};
+use llvm::{Module, Value, Type, Builder, Context, BasicBlock};
+
+/// Callback given the name and IR of each function compiled by a
+/// `ModuleCompiler`.
+pub type FnCompiledCallback<'b> = &'b mut dyn FnMut(&str, String);
+
+/// Name of the runtime function called when an `assert` fails.
+///
+/// `llvm.trap` is an intrinsic, so by default no runtime needs to be linked.
+pub const DEFAULT_PANIC_HANDLER: &str = "llvm.trap";
+
+/// Name of the fn which prints a value, as declared in the prelude.
+pub const PRINT_FN: &str = "print";
+
+/// How float comparisons treat NaN.
+///
+/// A comparison is unordered when either side is NaN. LLVM has an ordered
+/// predicate for each comparison, which is false if its operands are
+/// unordered, and an unordered one which is true.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloatComparisons {
+    /// IEEE 754 semantics, as in C and Rust: NaN is not equal to anything,
+    /// including itself, so `!=` is true and every other comparison false.
+    #[default]
+    Ieee,
+    /// Every comparison, including `!=`, is false when NaN is compared.
+    Ordered,
+    /// Every comparison, including `==`, is true when NaN is compared.
+    Unordered
+}
 
 //#[derive(Debug)]
 // https://github.com/immington-industries/protosnirk/issues/52
@@ -22,6 +51,18 @@ pub struct ModuleCompiler<'ctx, 'b, M: ModuleProvider<'ctx>> where 'ctx: 'b {
     current_type: Type<'ctx>,
     types: TypeMapping,
     scope_manager: &'b mut HashMap<ScopedId, Value<'ctx>>,
+    /// The fns declared in the module. Their IDs are bound to the fns
+    /// themselves rather than to allocas, so references don't load them.
+    declared_fns: HashSet<ScopedId>,
+    /// For each param of each declared function, the fn which computes its
+    /// default value from the params before it, if it has a default.
+    fn_defaults: HashMap<ScopedId, Vec<Option<Value<'ctx>>>>,
+    /// Called with the name and IR of each function once it's compiled.
+    fn_compiled: Option<FnCompiledCallback<'b>>,
+    /// Name of the `fn()` called when an `assert` fails.
+    panic_handler: String,
+    /// Predicates used by float comparisons.
+    float_comparisons: FloatComparisons,
 }
 
 impl<'ctx, 'b, M: ModuleProvider<'ctx>> ModuleCompiler<'ctx, 'b, M> {
@@ -41,16 +82,315 @@ impl<'ctx, 'b, M: ModuleProvider<'ctx>> ModuleCompiler<'ctx, 'b, M> {
             scope_manager,
             optimizations,
             current_type: Type::void(&context),
+            declared_fns: HashSet::new(),
+            fn_defaults: HashMap::new(),
+            fn_compiled: None,
+            panic_handler: DEFAULT_PANIC_HANDLER.to_string(),
+            float_comparisons: FloatComparisons::default(),
         }
     }
+
+    /// Sets the name of the runtime function to call when an `assert` fails.
+    ///
+    /// The handler is declared as a `void()` function in the module the
+    /// first time it's needed, so embedders can link in their own (such as
+    /// `__protosnirk_panic`). It is expected not to return.
+    pub fn set_panic_handler<S: Into<String>>(&mut self, name: S) {
+        self.panic_handler = name.into();
+    }
+
+    /// Sets how float comparisons treat NaN, which is IEEE 754 semantics by
+    /// default.
+    pub fn set_float_comparisons(&mut self, comparisons: FloatComparisons) {
+        self.float_comparisons = comparisons;
+    }
+
+    /// Sets a callback to be given the name and IR of each function after
+    /// it has been compiled (and optimized, if enabled).
+    pub fn on_fn_compiled(&mut self, callback: FnCompiledCallback<'b>) {
+        self.fn_compiled = Some(callback);
+    }
     pub fn decompose(self) -> (M, TypeMapping) {
         (self.module_provider, self.types)
     }
 
+    /// Gets the function with the given name, declaring it in the module if
+    /// it hasn't been used yet.
+    fn declared_fn(&self, name: &str, fn_type: &Type<'ctx>) -> Value<'ctx> {
+        if let Some(function) = self.current_module().get_function(name) {
+            return function
+        }
+        trace!("Declaring fn {}", name);
+        self.current_module().add_function(name, fn_type)
+    }
+
+    /// Gets the panic handler, declaring it if needed.
+    fn panic_handler(&self) -> Value<'ctx> {
+        let handler_type = Type::function(&Type::void(self.context),
+                                          Vec::new(), false);
+        self.declared_fn(&self.panic_handler, &handler_type)
+    }
+
+    /// Gets the `print(value: float)` fn which shows the values of a failed
+    /// `assert_eq`, declaring it if the prelude isn't used.
+    fn print_fn(&self) -> Value<'ctx> {
+        let print_type = Type::function(&Type::void(self.context),
+                                        vec![Type::double(self.context)],
+                                        false);
+        self.declared_fn(PRINT_FN, &print_type)
+    }
+
+    /// Whether the builder's current block already ends in a terminator,
+    /// such as the `ret` of a `return`.
+    ///
+    /// Nothing can be emitted after a terminator, so the rest of the
+    /// statements in the AST block are unreachable.
+    fn block_is_terminated(&self) -> bool {
+        self.builder.insert_block().terminator().is_some()
+    }
+
+    /// Compiles a statement whose value (if any) is not used.
+    fn compile_stmt_for_effect(&mut self, stmt: &Statement) {
+        let stack_len = self.ir_code.len();
+        match *stmt {
+            Statement::Expression(Expression::IfExpression(ref if_expr)) => {
+                trace!("Compiling if expression for effect");
+                self.compile_if_expr(if_expr, false);
+            },
+            _ => self.visit_stmt(stmt)
+        }
+        if self.ir_code.len() > stack_len {
+            trace!("Discarding value of statement");
+            self.ir_code.truncate(stack_len);
+        }
+    }
+
+    /// Compiles an if expression, only building a phi of the branches'
+    /// values if `needs_value` is set.
+    fn compile_if_expr(&mut self, if_expr: &IfExpression, needs_value: bool) {
+        // Build conditional expr
+        self.visit_expression(if_expr.condition());
+        let condition_expr = self.ir_code.pop()
+            .expect("Did not get value from if conditional");
+        // A condition folded to a constant only needs its taken branch.
+        if let Some(condition) = condition_expr.const_int_value() {
+            trace!("If expression condition is always {}", condition != 0);
+            if condition != 0 {
+                self.visit_expression(if_expr.true_expr());
+            }
+            else {
+                self.visit_expression(if_expr.else_expr());
+            }
+            if !needs_value {
+                self.ir_code.pop();
+            }
+            return
+        }
+        // Create basic blocks in the function
+        let function = self.builder.insert_block().parent()
+            .expect("Just now inserted a block");
+        let then_block = self.context.append_basic_block(&function, "ife_then");
+        let else_block = self.context.append_basic_block(&function, "ife_else");
+        let end_block = self.context.append_basic_block(&function, "ife_end");
+        self.builder.build_cond_br(&condition_expr, &then_block, &else_block);
+
+        // Emit the then code
+        self.builder.position_at_end(&then_block);
+        self.visit_expression(if_expr.true_expr());
+        let then_value = self.ir_code.pop()
+            .expect("Did not get IR value from visiting `then` clause of if expression");
+        self.builder.build_br(&end_block);
+        let then_end_block = self.builder.insert_block();
+
+        // Emit the else code
+        self.builder.position_at_end(&else_block);
+        self.visit_expression(if_expr.else_expr()); // self.current_type set
+        let else_value = self.ir_code.pop()
+            .expect("Did not get IR value from visiting `else` clause of if expression");
+        self.builder.build_br(&end_block);
+        let else_end_block = self.builder.insert_block();
+
+        self.builder.position_at_end(&end_block);
+
+        if !needs_value {
+            trace!("If expression value is unused, not building phi");
+            return
+        }
+        let phi = self.builder.build_phi(&self.current_type, "ifephi");
+
+        phi.add_incoming(vec![then_value], vec![then_end_block]);
+        phi.add_incoming(vec![else_value], vec![else_end_block]);
+        self.ir_code.push(phi);
+        // self.current_type stays the same.
+    }
+
+    /// Builds a comparison of two values of the same type, with `icmp` for
+    /// integers of any width and `fcmp` for floats of any width.
+    ///
+    /// Integers are compared as unsigned, as the only integer types, `bool`
+    /// and `char`, have no sign. Floats use the predicates picked by the
+    /// compiler's `FloatComparisons`.
+    fn build_comparison(&self,
+                        operator: BinaryOperator,
+                        left: &Value<'ctx>,
+                        right: &Value<'ctx>) -> Value<'ctx> {
+        use llvm_sys::LLVMIntPredicate::*;
+        use llvm_sys::LLVMRealPredicate::*;
+        let (int_predicate, ordered, unordered, name) = match operator {
+            BinaryOperator::Equality =>
+                (LLVMIntEQ, LLVMRealOEQ, LLVMRealUEQ, "eq"),
+            BinaryOperator::NonEquality =>
+                (LLVMIntNE, LLVMRealONE, LLVMRealUNE, "neq"),
+            BinaryOperator::LessThan =>
+                (LLVMIntULT, LLVMRealOLT, LLVMRealULT, "lt"),
+            BinaryOperator::LessThanEquals =>
+                (LLVMIntULE, LLVMRealOLE, LLVMRealULE, "le"),
+            BinaryOperator::GreaterThan =>
+                (LLVMIntUGT, LLVMRealOGT, LLVMRealUGT, "gt"),
+            BinaryOperator::GreaterThanEquals =>
+                (LLVMIntUGE, LLVMRealOGE, LLVMRealUGE, "ge"),
+            other => panic!("{:?} is not a comparison", other)
+        };
+        let real_predicate = match self.float_comparisons {
+            FloatComparisons::Ieee if operator == BinaryOperator::NonEquality =>
+                unordered,
+            FloatComparisons::Ieee | FloatComparisons::Ordered => ordered,
+            FloatComparisons::Unordered => unordered
+        };
+        match left.type_of().get_kind() {
+            LLVMTypeKind::LLVMIntegerTypeKind =>
+                self.builder.build_icmp(int_predicate, left, right,
+                                        &format!("{}_int", name)),
+            LLVMTypeKind::LLVMHalfTypeKind
+            | LLVMTypeKind::LLVMFloatTypeKind
+            | LLVMTypeKind::LLVMDoubleTypeKind
+            | LLVMTypeKind::LLVMX86_FP80TypeKind
+            | LLVMTypeKind::LLVMFP128TypeKind
+            | LLVMTypeKind::LLVMPPC_FP128TypeKind =>
+                self.builder.build_fcmp(real_predicate, left, right,
+                                        &format!("{}_float", name)),
+            other => panic!("Unexpected type {:?} for {:?}", other, operator)
+        }
+    }
+
+    /// Adds a fn to the module with the fns computing its params' defaults.
+    fn declare_fn(&mut self,
+                  ident: &Identifier,
+                  defaults: Vec<Option<Value<'ctx>>>) -> Value<'ctx> {
+        trace!("Declaring fn {}", ident.name());
+        let fn_type = self.llvm_fn_type_of(&ident.id());
+        let fn_ref = self.current_module().add_function(ident.name(), &fn_type);
+        self.scope_manager.insert(ident.id().clone(), fn_ref.clone());
+        self.declared_fns.insert(ident.id().clone());
+        self.fn_defaults.insert(ident.id().clone(), defaults);
+        fn_ref
+    }
+
+    /// Declares a private fn for each param of the fn which has a default
+    /// value. It's given the params before the default, and returns the
+    /// default's value.
+    ///
+    /// Calls which leave out a param call its default fn, so the default is
+    /// only compiled once however many calls use it.
+    fn declare_param_defaults(&mut self, block_fn: &BlockFnDeclaration)
+                              -> Vec<Option<Value<'ctx>>> {
+        let param_types = block_fn.typed_params(&self.types).into_iter()
+            .map(|(_, concrete)| self.llvm_type_of_concrete(concrete
+                .expect("Attempted to find unknown param type")))
+            .collect::<Vec<_>>();
+        block_fn.params().iter().zip(block_fn.param_defaults()).enumerate()
+            .map(|(ix, (&(ref param, _), default))| {
+                if default.is_none() {
+                    return None
+                }
+                let name = format!("{}.default.{}",
+                                   block_fn.name(), param.name());
+                trace!("Declaring default fn {}", name);
+                let default_type = Type::function(&param_types[ix],
+                                                  param_types[..ix].to_vec(),
+                                                  false);
+                let default_fn = self.current_module()
+                    .add_function(&name, &default_type);
+                default_fn.set_linkage(LLVMLinkage::LLVMPrivateLinkage);
+                Some(default_fn)
+            })
+            .collect()
+    }
+
+    /// Compiles the fns declared by `declare_param_defaults` for the params
+    /// of the fn.
+    fn compile_param_defaults(&mut self, block_fn: &BlockFnDeclaration) {
+        let default_fns = self.fn_defaults[&block_fn.id()].clone();
+        for (ix, (default_fn, default)) in default_fns.iter()
+                .zip(block_fn.param_defaults()).enumerate() {
+            let (default_fn, default) = match (default_fn, default) {
+                (Some(default_fn), Some(default)) => (default_fn, default),
+                _ => continue
+            };
+            trace!("Compiling default fn {}", default_fn.get_name());
+            let entry_block = self.context.append_basic_block(default_fn,
+                                                              "entry");
+            self.builder.position_at_end(&entry_block);
+            // The default refers to the params before it by their IDs, so
+            // they're bound to this fn's params, as in the fn itself.
+            for (&(ref param, _), ir_param) in
+                    block_fn.params()[..ix].iter().zip(default_fn.get_params()) {
+                ir_param.set_name(param.name());
+                let alloca = self.builder
+                    .build_alloca(&ir_param.type_of(), param.name());
+                self.builder.build_store(&ir_param, &alloca);
+                self.scope_manager.insert(param.id().clone(), alloca);
+            }
+            self.visit_expression(default);
+            let value = self.ir_code.pop()
+                .expect("Could not get value of default fn arg");
+            self.builder.build_ret(&value);
+
+            if !default_fn.verify(
+                    LLVMVerifierFailureAction::LLVMPrintMessageAction) {
+                error!("Current module IR:\n{}",
+                       self.current_module().print_to_string());
+                panic!("Validation error for {}", default_fn.get_name());
+            }
+            if self.optimizations {
+                self.module_provider.pass_manager().run(default_fn);
+            }
+        }
+    }
+
+    /// Compiles one of the blocks of an if block, saving its value for the
+    /// if's phi and branching to the end of the if.
+    ///
+    /// Gives whether the block reaches the end of the if.
+    fn compile_if_branch(&mut self,
+                         block: &Block,
+                         valued_if: bool,
+                         end_block: &BasicBlock<'b>,
+                         incoming_values: &mut Vec<Value<'ctx>>,
+                         incoming_blocks: &mut Vec<BasicBlock<'b>>) -> bool {
+        self.visit_block(block);
+        // A block which returns doesn't give a value or reach the end. This
+        // includes blocks which return from the taken branch of an if with
+        // a constant condition, which `Block::diverges` doesn't know about.
+        if self.block_is_terminated() {
+            return false
+        }
+        if valued_if {
+            let value = self.ir_code.pop()
+                .expect("Did not get value from valued if block");
+            incoming_values.push(value);
+            incoming_blocks.push(self.builder.insert_block());
+        }
+        trace!("Adding branch to if end block");
+        self.builder.build_br(end_block);
+        true
+    }
+
     fn current_module(&self) -> &Module<'ctx> {
         self.module_provider.module()
     }
 
+    /// Gets the LLVM type of values with the given ID.
     fn llvm_type_of(&self, id: &ScopedId) -> Type<'ctx> {
         trace!("Finding type of ID {:?}", id);
         let concrete = self.types.get(id)
@@ -59,25 +399,15 @@ impl<'ctx, 'b, M: ModuleProvider<'ctx>> ModuleCompiler<'ctx, 'b, M> {
     }
 
     fn llvm_type_of_concrete(&self, concrete: &ConcreteType) -> Type<'ctx> {
-        match concrete {
-            &ConcreteType::Named(ref name) => {
-                match name.name() {
-                    "()" => Type::void(&self.context),
-                    "bool" => Type::int1(&self.context),
-                    "float" => Type::double(&self.context),
-                    other => panic!("Unexpected concrete type {}", other)
-                }
-            },
-            &ConcreteType::Function(ref fn_ty) => {
-                let mut params = Vec::new();
-                for &(ref _name, ref param_ty) in fn_ty.params() {
-                    params.push(self.llvm_type_of_concrete(param_ty));
-                }
-                Type::function(
-                    &self.llvm_type_of_concrete(fn_ty.return_ty()),
-                    params, false)
-            }
-        }
+        concrete.llvm_value_type(self.context)
+    }
+
+    /// Gets the LLVM function type of the fn with the given ID.
+    fn llvm_fn_type_of(&self, fn_id: &ScopedId) -> Type<'ctx> {
+        trace!("Finding fn type of ID {:?}", fn_id);
+        self.types.get(fn_id)
+            .expect("Attempted to find unknown fn type")
+            .llvm_type(self.context)
     }
 }
 
@@ -87,6 +417,31 @@ impl<'ctx, 'b, M> UnitVisitor for ModuleCompiler<'ctx, 'b, M>
     fn visit_unit(&mut self, unit: &Unit) {
         trace!("Checking a unit");
 
+        // Every fn is declared before any are compiled, so that fns can call
+        // the ones after them.
+        for item in unit.items() {
+            match *item {
+                Item::BlockFnDeclaration(ref block_fn) => {
+                    let defaults = self.declare_param_defaults(block_fn);
+                    self.declare_fn(block_fn.ident(), defaults);
+                },
+                Item::ExternFnDeclaration(ref extern_fn) => {
+                    let fn_ref = self.declare_fn(extern_fn.ident(),
+                                                 Vec::new());
+                    for (&(ref param, _), ref ir_param) in
+                            extern_fn.params().iter().zip(fn_ref.get_params()) {
+                        ir_param.set_name(param.name());
+                    }
+                },
+                Item::Typedef(_) => {}
+            }
+        }
+        // Defaults can call any fn, so they're compiled once all are declared.
+        for item in unit.items() {
+            if let Item::BlockFnDeclaration(ref block_fn) = *item {
+                self.compile_param_defaults(block_fn);
+            }
+        }
         visit::walk_unit(self, unit);
 
         // The final ir_code value should be a reference to the function
@@ -108,19 +463,13 @@ impl<'ctx, 'b, M> ItemVisitor for ModuleCompiler<'ctx, 'b, M>
     fn visit_block_fn_decl(&mut self, block_fn: &BlockFnDeclaration) {
         trace!("Checking declaration of {}", block_fn.name());
 
-        let fn_type = self.llvm_type_of(&block_fn.id());
+        let fn_type = self.llvm_fn_type_of(&block_fn.id());
         let fn_ret_type_kind = fn_type.return_type()
             .expect("Block fn's LLVM type did not have a return type")
             .get_kind();
         let fn_returns_void =
             fn_ret_type_kind == LLVMTypeKind::LLVMVoidTypeKind;
-        let fn_ref = self.current_module().add_function(
-            block_fn.name(), &fn_type);
-
-        // Gotta insert the fn ref first so it can be called recursively
-        self.scope_manager.insert(block_fn.id().clone(), fn_ref.clone());
-        trace!("Inserted {} into the scope manager",
-            block_fn.name());
+        let fn_ref = self.scope_manager[&block_fn.id()].clone();
 
         // Gonna be fancy and have a separate basic block for parameters
         let entry_block = self.context.append_basic_block(&fn_ref, "entry");
@@ -128,18 +477,22 @@ impl<'ctx, 'b, M> ItemVisitor for ModuleCompiler<'ctx, 'b, M>
         self.builder.position_at_end(&entry_block);
         trace!("Ready to build {}", block_fn.name());
 
-        let fn_params = fn_ref.get_params();
-        trace!("fn has {} params", fn_params.len());
+        trace!("fn has {} params", block_fn.arity());
+        debug_assert_eq!(block_fn.arity(), fn_ref.get_params().len(),
+            "Fn {} was declared with a different number of params",
+            block_fn.name());
 
         // Rename args to %argname, create+remember allocas and store the
         // function values there. This allows LLVM to mutate function params
         // even if we don't allow it right now.
-        for (&(ref ast_param, _), ref ir_param) in
-                        block_fn.params().iter().zip(fn_ref.get_params()) {
+        for ((ast_param, concrete), ir_param) in
+                block_fn.typed_params(&self.types).into_iter()
+                    .zip(fn_ref.get_params()) {
             trace!("Adding fn param {} (ix {:?})",
                 ast_param.name(), ast_param.id());
             ir_param.set_name(ast_param.name());
-            let param_type = self.llvm_type_of(&ast_param.id());
+            let param_type = self.llvm_type_of_concrete(concrete
+                .expect("Attempted to find unknown param type"));
             let alloca = self.builder
                 .build_alloca(&param_type, ast_param.name());
             self.builder.build_store(&ir_param, &alloca);
@@ -163,10 +516,13 @@ impl<'ctx, 'b, M> ItemVisitor for ModuleCompiler<'ctx, 'b, M>
                     "No IR code remaining, assuming the last stmt was return");
             }
         }
-        else {
+        else if !self.block_is_terminated() {
             trace!("block fn returns void, appending ret void");
             self.builder.build_ret_void();
         }
+        else {
+            trace!("block fn already returned, not appending ret void");
+        }
 
 
         if !fn_ref.verify(LLVMVerifierFailureAction::LLVMPrintMessageAction) {
@@ -175,10 +531,24 @@ impl<'ctx, 'b, M> ItemVisitor for ModuleCompiler<'ctx, 'b, M>
             panic!("Validation error for {}", block_fn.name());
         }
 
+        debug_assert!(self.ir_code.is_empty(),
+            "{} values left on the IR stack after compiling {}",
+            self.ir_code.len(), block_fn.name());
+
         if self.optimizations {
             trace!("Running optimizations on fn {}", block_fn.name());
             self.module_provider.pass_manager().run(&fn_ref);
         }
+
+        if let Some(ref mut fn_compiled) = self.fn_compiled {
+            fn_compiled(block_fn.name(), fn_ref.print_to_string());
+        }
+    }
+
+    fn visit_extern_fn_decl(&mut self, extern_fn: &ExternFnDeclaration) {
+        // Extern fns are only declared, in `visit_unit`: they're linked in
+        // from elsewhere.
+        trace!("Skipping extern fn {}", extern_fn.name());
     }
 
     fn visit_typedef(&mut self, _typedef: &Typedef) {
@@ -192,9 +562,26 @@ impl<'ctx, 'b, M> BlockVisitor for ModuleCompiler<'ctx, 'b, M>
     fn visit_block(&mut self, block: &Block) {
         trace!("Visiting block");
         // We know from typeck that the last block statement must be an
-        // expression. So we just walk the block and assume that self.ir_code
-        // will receive the last expression.
-        visit::walk_block(self, block);
+        // expression. So we assume that self.ir_code will receive the last
+        // expression, and compile every other statement for effect only.
+        let value_ix = if block.has_source() {
+            block.stmts().len().checked_sub(1)
+        }
+        else {
+            None
+        };
+        for (ix, stmt) in block.stmts().iter().enumerate() {
+            if self.block_is_terminated() {
+                trace!("Skipping unreachable statements from {}", stmt.span());
+                break
+            }
+            if Some(ix) == value_ix {
+                self.visit_stmt(stmt);
+            }
+            else {
+                self.compile_stmt_for_effect(stmt);
+            }
+        }
         if block.has_source() {
             trace!("Block has source, setting ID");
             self.current_type = self.llvm_type_of(&block.id());
@@ -212,121 +599,113 @@ impl<'ctx, 'b, M> StatementVisitor for ModuleCompiler<'ctx, 'b, M>
 
     fn visit_if_block(&mut self, if_block: &IfBlock) {
         trace!("Checking if block");
-        // Create some lists of values to use later
         let condition_count = if_block.conditionals().len();
         let valued_if = if_block.has_source();
-        let function = self.builder.insert_block().get_parent()
+        let function = self.builder.insert_block().parent()
             .expect("Just inserted a block");
 
-        let mut condition_blocks = Vec::with_capacity(condition_count);
         let mut incoming_values =
             Vec::with_capacity(if valued_if { condition_count } else {0});
+        let mut incoming_blocks =
+            Vec::with_capacity(if valued_if { condition_count } else {0});
+        // The end block is only needed once a condition isn't constant.
+        let mut end_block = None;
+        // Whether the builder is already positioned at the end block.
+        let mut at_end_block = false;
+        // Whether any branch of the if continues after it.
+        let mut reaches_end = false;
+        // The block which runs if none of the conditions are taken, or the
+        // one which always runs if a condition is constant `true`.
+        let mut fallthrough = if_block.else_block();
 
         trace!("Preparing to emit {} conditionals", condition_count);
-        // Populate a list of the future blocks to have
-        for (ix, _conditional) in if_block.conditionals().iter().enumerate() {
-            trace!("Creating condition block {}", ix);
-            // We skip adding the first one to this list because we know we
-            // will have at least one later so we handle it separately.
-            if ix != 0usize {
-                let name = format!("if_{}_cond", ix + 1);
-                condition_blocks.push(
-                    self.context.append_basic_block(&function, &name)
-                );
-            }
-            let name = format!("if_{}_then", ix + 1);
-            condition_blocks.push(
-                self.context.append_basic_block(&function, &name)
-            );
-        }
-        // If there's an else it needs a block
-        if if_block.has_else() {
-            trace!("Creating else block");
-            condition_blocks.push(
-                self.context.append_basic_block(&function, "else_block")
-            );
-        }
-
-        let int1_type = Type::int1(self.context);
-        let int1_zero = int1_type.const_int(0u64, false);
-
-        trace!("Creating end block");
-        condition_blocks.push(self.context.append_basic_block(&function,
-                                                                     "if_end"));
-
-        let mut ix = 0;
-        for conditional in if_block.conditionals() {
+        for (ix, conditional) in if_block.conditionals().iter().enumerate() {
             trace!("Checking expr for condition {}", ix);
             self.visit_expression(conditional.condition());
             let cond_value = self.ir_code.pop()
                 .expect("Did not get IR value from if block condition");
-            let cond_cmp_name = format!("if_{}_cmp", ix);
-            let cond_cmp = self.builder.build_icmp(LLVMIntPredicate::LLVMIntEQ,
-                    &cond_value, &int1_zero, &cond_cmp_name);
-
-            trace!("Building a break to next blocks {} -> {}, {}",
-                cond_cmp_name, ix, ix + 1);
-            self.builder.build_cond_br(&cond_cmp,
-                                                 &condition_blocks[ix],
-                                                 &condition_blocks[ix + 1]);
-
-            // Go to the `if_true` block of this conditional
-            trace!("Positioning at end of cond block {}", ix);
-            self.builder.position_at_end(&condition_blocks[ix]);
-            trace!("Checking conditional block");
-            self.visit_block(conditional.block());
-            // If this is a valued if, save the value ref for this branch of the condition
-            if valued_if {
-                let value = self.ir_code.pop()
-                    .expect("Did not get value from valued if block");
-                incoming_values.push(value);
+            match cond_value.const_int_value() {
+                Some(0) => {
+                    trace!("Condition {} is always false, skipping it", ix);
+                    continue
+                },
+                Some(_) => {
+                    trace!("Condition {} is always true, skipping the rest", ix);
+                    fallthrough = Some(conditional.block());
+                    break
+                },
+                None => {}
             }
 
-            // After block, go to done
-            trace!("Adding branch to cond end block");
-            let last_ix = condition_blocks.len() - 1;
-            self.builder.build_br(&condition_blocks[last_ix]);
+            let end = end_block.get_or_insert_with(|| {
+                trace!("Creating end block");
+                self.context.append_basic_block(&function, "if_end")
+            }).clone();
+            let then_block = self.context.append_basic_block(&function,
+                &format!("if_{}_then", ix + 1));
+            let next_block = if ix + 1 < condition_count {
+                self.context.append_basic_block(&function,
+                    &format!("if_{}_cond", ix + 2))
+            }
+            else if if_block.has_else() {
+                self.context.append_basic_block(&function, "else_block")
+            }
+            else {
+                at_end_block = true;
+                end.clone()
+            };
+            trace!("Building a break to the then and next blocks of {}", ix);
+            self.builder.build_cond_br(&cond_value, &then_block, &next_block);
 
+            trace!("Checking conditional block");
+            self.builder.position_at_end(&then_block);
+            reaches_end |= self.compile_if_branch(conditional.block(),
+                valued_if, &end, &mut incoming_values, &mut incoming_blocks);
             // Position at the beginning of the next block
-            trace!("Moving onto block {}", ix + 1);
-            self.builder.position_at_end(&condition_blocks[ix + 1]);
-            ix += 2;
+            self.builder.position_at_end(&next_block);
         }
 
         trace!("Finished checking conditions");
-        // If there's an else, check that too
-        if let Some(ref else_block) = if_block.else_block() {
-            trace!("Checking else block");
-            self.visit_block(else_block);
-            if valued_if {
-                let value = self.ir_code.pop()
-                    .expect("Did not get value from else of valued if block");
-                incoming_values.push(value);
+        let end_block = match end_block {
+            Some(end_block) => end_block,
+            None => {
+                // Every condition was constant, so there's no branching and
+                // any value is left on the stack by the block. If the block
+                // returns, the enclosing `visit_block` stops at its
+                // terminator.
+                if let Some(block) = fallthrough {
+                    trace!("Checking the only block of the if");
+                    self.visit_block(block);
+                }
+                return
+            }
+        };
+        if let Some(block) = fallthrough {
+            trace!("Checking fallthrough block");
+            reaches_end |= self.compile_if_branch(block, valued_if,
+                &end_block, &mut incoming_values, &mut incoming_blocks);
+        }
+        else {
+            if !at_end_block {
+                self.builder.build_br(&end_block);
             }
-            // Branch to end after else
-            let last_ix = condition_blocks.len() - 1;
-            self.builder.build_br(&condition_blocks[last_ix]);
+            reaches_end = true;
         }
 
-        // Remove the end block from condition blocks for borrowck + phi reasons
-        let cond_end_block = condition_blocks.pop()
-            .expect("Somehow there were 0 conditional blocks");
         // Position at end block - this lets us get on with the function
-        self.builder.position_at_end(&cond_end_block);
+        self.builder.position_at_end(&end_block);
 
+        if !reaches_end {
+            trace!("Every branch of the if block returns");
+            self.builder.build_unreachable();
+        }
         // If we need to push a value, create a phi
-        if valued_if {
-            let mut incoming_conditions = condition_blocks
-                .chunks(2)
-                .map(|c| c[0].clone())
-                .collect::<Vec<_>>();
-            incoming_conditions.push(condition_blocks.pop()
-                .expect("No condition blocks"));
+        else if valued_if {
             trace!("Generating phi node with {} values and {} edges",
-                incoming_values.len(), incoming_conditions.len());
+                incoming_values.len(), incoming_blocks.len());
             let phi_type = self.llvm_type_of(&if_block.id());
             let phi = self.builder.build_phi(&phi_type, "if_phi");
-            phi.add_incoming(incoming_values, incoming_conditions);
+            phi.add_incoming(incoming_values, incoming_blocks);
             self.ir_code.push(phi);
             self.current_type = phi_type;
         }
@@ -356,6 +735,62 @@ impl<'ctx, 'b, M> StatementVisitor for ModuleCompiler<'ctx, 'b, M>
         }
         self.current_type = Type::void(&self.context);
     }
+
+    fn visit_assert_stmt(&mut self, assert: &Assert) {
+        trace!("Checking assert statement");
+        let function = self.builder.insert_block().parent()
+            .expect("Just inserted a block");
+        let fail_block = self.context.append_basic_block(&function,
+                                                         "assert_fail");
+        let end_block = self.context.append_basic_block(&function,
+                                                        "assert_end");
+
+        // The operands of an `assert_eq` are printed if it fails.
+        let mut shown_values = Vec::new();
+        if let Some((left, right)) = assert.equality_operands() {
+            self.visit_expression(left);
+            let left_value = self.ir_code.pop()
+                .expect("Did not get IR value from assert_eq left");
+            self.visit_expression(right);
+            let right_value = self.ir_code.pop()
+                .expect("Did not get IR value from assert_eq right");
+            let cond_value = self.build_comparison(BinaryOperator::Equality,
+                                                   &left_value, &right_value);
+            self.builder.build_cond_br(&cond_value, &end_block, &fail_block);
+            shown_values.push(left_value);
+            shown_values.push(right_value);
+        }
+        else {
+            self.visit_expression(assert.condition());
+            let cond_value = self.ir_code.pop()
+                .expect("Did not get IR value from assert condition");
+            self.builder.build_cond_br(&cond_value, &end_block, &fail_block);
+        }
+
+        // The handler shouldn't return, but there's no guarantee an
+        // embedder's handler is marked noreturn.
+        self.builder.position_at_end(&fail_block);
+        if !shown_values.is_empty() {
+            let print_fn = self.print_fn();
+            let double_type = Type::double(self.context);
+            for value in shown_values {
+                // `print` takes a float, so bools and chars are converted.
+                let printed = if value.type_of().get_kind()
+                        == LLVMTypeKind::LLVMDoubleTypeKind {
+                    value
+                } else {
+                    self.builder.build_ui_to_fp(&value, &double_type, "shown")
+                };
+                self.builder.build_call(&print_fn, vec![printed], "");
+            }
+        }
+        let handler = self.panic_handler();
+        self.builder.build_call(&handler, Vec::new(), "");
+        self.builder.build_unreachable();
+
+        self.builder.position_at_end(&end_block);
+        self.current_type = Type::void(self.context);
+    }
 }
 
 impl<'ctx, 'b, M> ExpressionVisitor for ModuleCompiler<'ctx, 'b, M>
@@ -375,10 +810,14 @@ impl<'ctx, 'b, M> ExpressionVisitor for ModuleCompiler<'ctx, 'b, M>
                 (Type::double(&self.context).const_real(f),
                 Type::double(&self.context))
             },
+            &LiteralValue::Char(c) => {
+                (Type::int32(&self.context).const_int(c as u64, false),
+                 Type::int32(&self.context))
+            },
             &LiteralValue::Unit => {
-                // Not directly used.
-                //Type::void(self.context).const_null()
-                unimplemented!()
+                // Like a call to a void fn, `()` has no IR value.
+                self.current_type = Type::void(self.context);
+                return
             }
         };
         self.current_type = literal_type;
@@ -391,11 +830,16 @@ impl<'ctx, 'b, M> ExpressionVisitor for ModuleCompiler<'ctx, 'b, M>
         let var_alloca = self.scope_manager.get(&ident_ref.id())
             .expect("Attempted to check var ref but had no alloca for it")
             .clone();
+        self.current_type = self.llvm_type_of(&ident_ref.id());
+        if self.declared_fns.contains(&ident_ref.id()) {
+            trace!("Using fn {} as a value", ident_ref.name());
+            self.ir_code.push(var_alloca);
+            return
+        }
         let load_name = format!("load_{}", ident_ref.name());
         trace!("Creating {}", load_name);
         let builder = self.builder;
         let var_load = builder.build_load(&var_alloca, &load_name);
-        self.current_type = self.llvm_type_of(&ident_ref.id());
         self.ir_code.push(var_load);
     }
 
@@ -423,8 +867,10 @@ impl<'ctx, 'b, M> ExpressionVisitor for ModuleCompiler<'ctx, 'b, M>
                 (builder.build_fsub(&inner_value, &literal_zero, "negate"), double_type)
             },
             // The unary + operator is always a no-op.
-            UnaryOperator::Addition =>
-                (inner_value, self.current_type.clone())
+            UnaryOperator::Addition => {
+                let inner_type = inner_value.type_of();
+                (inner_value, inner_type)
+            }
         };
         self.current_type = type_;
         self.ir_code.push(value);
@@ -442,7 +888,6 @@ impl<'ctx, 'b, M> ExpressionVisitor for ModuleCompiler<'ctx, 'b, M>
             .expect("Could not generate rvalue of binary op");
         let builder = self.builder;
         trace!("Appending binary operation");
-        use llvm_sys::LLVMRealPredicate::*;
         let (bin_op_value, bin_op_type) = match binary_op.operator() {
             BinaryOperator::Addition => {
                 (builder.build_fadd(&left_register, &right_register, "add"),
@@ -464,41 +909,23 @@ impl<'ctx, 'b, M> ExpressionVisitor for ModuleCompiler<'ctx, 'b, M>
                 (builder.build_frem(&left_register, &right_register, "rem"),
                 Type::double(&self.context))
             },
-            BinaryOperator::Equality => {
-                let eq_type_kind = left_register.get_type().get_kind();
-
-                (if eq_type_kind == LLVMTypeKind::LLVMDoubleTypeKind {
-                    // Not sure about NaN == NaN here.
-                    self.builder.build_fcmp(LLVMRealPredicate::LLVMRealOEQ,
-                        &left_register, &right_register, "eq_double")
-                }
-                else if eq_type_kind == LLVMTypeKind::LLVMIntegerTypeKind {
-                    self.builder.build_icmp(LLVMIntPredicate::LLVMIntEQ,
-                        &left_register, &right_register, "eq_int")
-                }
-                else {
-                    panic!("Unexpected type for equality check");
-                },
-                Type::int1(&self.context))
-            },
-           BinaryOperator::NonEquality => {
-                (builder.build_fcmp(LLVMRealONE, &left_register, &right_register, "neqtmp"),
-                Type::int1(&self.context))
-            },
-           BinaryOperator::LessThan => {
-                (builder.build_fcmp(LLVMRealOLT, &left_register, &right_register, "lttmp"),
-                Type::int1(&self.context))
-            },
-           BinaryOperator::LessThanEquals => {
-                (builder.build_fcmp(LLVMRealOLE, &left_register, &right_register, "letmp"),
-                Type::int1(&self.context))
-            },
-           BinaryOperator::GreaterThan => {
-                (builder.build_fcmp(LLVMRealOGT, &left_register, &right_register, "gttmp"),
-                Type::int1(&self.context))
+            BinaryOperator::Power => {
+                let double_type = Type::double(self.context);
+                let pow_type = Type::function(&double_type,
+                    vec![double_type.clone(), double_type.clone()], false);
+                let pow = self.declared_fn("llvm.pow.f64", &pow_type);
+                (builder.build_call(&pow,
+                    vec![left_register, right_register], "pow"),
+                double_type)
             },
-           BinaryOperator::GreaterThanEquals => {
-                (builder.build_fcmp(LLVMRealOGE, &left_register, &right_register, "getmp"),
+            BinaryOperator::Equality
+            | BinaryOperator::NonEquality
+            | BinaryOperator::LessThan
+            | BinaryOperator::LessThanEquals
+            | BinaryOperator::GreaterThan
+            | BinaryOperator::GreaterThanEquals => {
+                (self.build_comparison(binary_op.operator(),
+                                       &left_register, &right_register),
                 Type::int1(&self.context))
             }
         };
@@ -515,72 +942,369 @@ impl<'ctx, 'b, M> ExpressionVisitor for ModuleCompiler<'ctx, 'b, M>
 
         trace!("Found function type {:?}", fn_type);
 
-        let mut arg_values = Vec::with_capacity(fn_call.args().len());
-
-        for (_ix, &(ref name, _)) in fn_type.params().iter().enumerate() {
-            for arg in fn_call.args() {
-                if arg.name().name() == name {
-                    self.visit_expression(arg.expression());
-                    arg_values.push(self.ir_code.pop()
-                        .expect("Could not get alloca for named var of fn arg"));
-                    break
-                }
+        let mut arg_values = Vec::with_capacity(fn_type.params().len());
+        let default_fns = self.fn_defaults.get(&fn_call.id()).cloned()
+            .unwrap_or_default();
+
+        let args = fn_call.arg_exprs();
+        for (ix, &(ref name, _)) in fn_type.params().iter().enumerate() {
+            if let Some(&(_, arg_expr)) = args.iter()
+                    .find(|&&(arg_name, _)| arg_name == Some(name)) {
+                self.visit_expression(arg_expr);
+                arg_values.push(self.ir_code.pop()
+                    .expect("Could not get alloca for named var of fn arg"));
+            }
+            else if let Some(&Some(ref default_fn)) = default_fns.get(ix) {
+                trace!("Using default value for param {}", name);
+                let default = self.builder.build_call(
+                    default_fn, arg_values.clone(), &format!("default_{}", name));
+                arg_values.push(default);
             }
         }
 
-        let fn_ref = &self.scope_manager[&fn_call.id()];
+        let fn_ref = if self.declared_fns.contains(&fn_call.id()) {
+            self.scope_manager[&fn_call.id()].clone()
+        }
+        else {
+            trace!("Calling fn value {}", fn_call.text());
+            let fn_alloca = &self.scope_manager[&fn_call.id()];
+            self.builder.build_load(fn_alloca,
+                                    &format!("load_{}", fn_call.text()))
+        };
         let fn_return_type = self.llvm_type_of_concrete(fn_type.return_ty());
         trace!("Got a function ref to call");
         if fn_return_type.get_kind() == LLVMTypeKind::LLVMVoidTypeKind {
             trace!("Building call void {}", fn_call.text());
-            let call = self.builder.build_call(fn_ref, arg_values, "");
+            let call = self.builder.build_call(&fn_ref, arg_values, "");
             call.set_name("");
         }
         else {
             let name = format!("call_{}", fn_call.text());
             trace!("Building call {}", name);
-            let call = self.builder.build_call(fn_ref, arg_values, &name);
+            let call = self.builder.build_call(&fn_ref, arg_values, &name);
             self.ir_code.push(call);
         };
         self.current_type = fn_return_type;
     }
 
     fn visit_if_expr(&mut self, if_expr: &IfExpression) {
-        // Build conditional expr
-        self.visit_expression(if_expr.condition());
-        let condition_expr = self.ir_code.pop()
-            .expect("Did not get value from if conditional");
-        // Create basic blocks in the function
-        let function = self.builder.insert_block().get_parent()
-            .expect("Just now inserted a block");
-        let then_block = self.context.append_basic_block(&function, "ife_then");
-        let else_block = self.context.append_basic_block(&function, "ife_else");
-        let end_block = self.context.append_basic_block(&function, "ife_end");
-        self.builder.build_cond_br(&condition_expr, &then_block, &else_block);
+        self.compile_if_expr(if_expr, true);
+    }
+}
 
-        // Emit the then code
-        self.builder.position_at_end(&then_block);
-        self.visit_expression(if_expr.true_expr());
-        let then_value = self.ir_code.pop()
-            .expect("Did not get IR value from visiting `then` clause of if expression");
-        self.builder.build_br(&end_block);
-        let then_end_block = self.builder.insert_block();
+#[cfg(test)]
+mod tests {
+    use compile::ModuleProvider;
+    use llvm::Context;
+    use pipeline::{Runner, CompileRunner, compile_source_to_ir, check_source};
+    use super::FloatComparisons;
 
-        // Emit the else code
-        self.builder.position_at_end(&else_block);
-        self.visit_expression(if_expr.else_expr()); // self.current_type set
-        let else_value = self.ir_code.pop()
-            .expect("Did not get IR value from visiting `else` clause of if expression");
-        self.builder.build_br(&end_block);
-        let else_end_block = self.builder.insert_block();
+    fn compile_to_ir(source: &str) -> String {
+        compile_source_to_ir(source, "test")
+            .expect("Could not compile test source")
+    }
 
-        self.builder.position_at_end(&end_block);
+    #[test]
+    fn param_defaults_are_compiled_once() {
+        let ir = compile_to_ir(
+            "fn scale(x: float, by: float = 2, plus: float = by * x) -> float\n\
+            \x20   x * by + plus\n\
+            \n\
+            fn main()\n\
+            \x20   let a: float = scale(x: 1)\n\
+            \x20   let b: float = scale(x: 2, by: 3)\n");
+        assert_eq!(ir.matches("define private double @scale.default.by(\
+                               double %x)").count(), 1,
+                   "by's default was not compiled once:\n{}", ir);
+        assert_eq!(ir.matches("define private double @scale.default.plus(\
+                               double %x, double %by)").count(), 1,
+                   "plus's default was not compiled once:\n{}", ir);
+        assert_eq!(ir.matches("call double @scale.default.plus(").count(), 2,
+                   "plus's default was not called by both calls:\n{}", ir);
+        assert!(ir.contains("call double @scale.default.plus(\
+                             double 2.000000e+00, double 3.000000e+00)"),
+                "plus's default was not given the call's args:\n{}", ir);
+        let main = &ir[ir.find("define void @main").expect("No main")..];
+        assert_eq!(main.matches("alloca").count(), 2,
+                   "Default args were stored in main:\n{}", ir);
+    }
 
-        let phi = self.builder.build_phi(&self.current_type, "ifephi");
+    #[test]
+    fn empty_unit_fns_return_void() {
+        let ir = compile_to_ir("fn foo()\n\
+                                \x20   // nothing\n\
+                                fn bar() -> ()\n\
+                                \x20   // nothing\n");
+        for name in &["foo", "bar"] {
+            let start = ir.find(&format!("define void @{}()", name))
+                .unwrap_or_else(|| panic!("{} was not defined:\n{}", name, ir));
+            let body = &ir[start..];
+            let body = &body[..body.find("}\n").expect("Unclosed fn")];
+            assert!(body.trim_end().ends_with("ret void"),
+                    "{} did not return void:\n{}", name, ir);
+        }
+    }
 
-        phi.add_incoming(vec![then_value], vec![then_end_block]);
-        phi.add_incoming(vec![else_value], vec![else_end_block]);
-        self.ir_code.push(phi);
-        // self.current_type stays the same.
+    #[test]
+    fn call_args_are_in_declaration_order() {
+        let ir = compile_to_ir(
+            "fn sub(a: float, b: float, c: float) -> float\n\
+            \x20   a - b - c\n\
+            \n\
+            fn main()\n\
+            \x20   let x: float = sub(c: 3, a: 1, b: 2)\n");
+        assert!(ir.contains("@sub(double 1.000000e+00, double 2.000000e+00, \
+                                  double 3.000000e+00)"),
+                "Call args out of order in IR:\n{}", ir);
+    }
+
+    #[test]
+    fn if_expr_statement_value_is_discarded() {
+        let ir = compile_to_ir(
+            "fn pick(x: bool) -> float\n\
+            \x20   if x => 1 else 2\n\
+            \x20   return 3\n\
+            \n\
+            fn main()\n\
+            \x20   let y: float = pick(x: true)\n");
+        assert!(!ir.contains("phi"), "Unused if value built a phi:\n{}", ir);
+        assert!(ir.contains("ret double 3.000000e+00"),
+                "pick did not return 3:\n{}", ir);
+    }
+
+    #[test]
+    fn unconstrained_numeric_literal_is_float() {
+        // There is no `int` type yet, so numeric literals are never left
+        // ambiguous: they are always `float`.
+        let ir = compile_to_ir("fn main()\n\
+                                \x20   let x = 5\n");
+        assert!(ir.contains("%x = alloca double"),
+                "x was not a float:\n{}", ir);
+    }
+
+    #[test]
+    fn extern_fns_are_declared_and_called() {
+        let ir = compile_to_ir(
+            "extern fn sqrt(x: float) -> float\n\
+            \n\
+            fn main()\n\
+            \x20   let y: float = sqrt(x: 4)\n");
+        assert!(ir.contains("declare double @sqrt(double"),
+                "Extern fn was not declared:\n{}", ir);
+        assert!(ir.contains("call double @sqrt("),
+                "Extern fn was not called:\n{}", ir);
+    }
+
+    #[test]
+    fn failing_asserts_call_panic_handler() {
+        let source = "fn check(x: float)\n\
+                      \x20   assert x > 0\n\
+                      \x20   assert x < 10\n";
+        let ir = compile_to_ir(source);
+        assert!(ir.contains("declare void @llvm.trap()"),
+                "Default panic handler not declared:\n{}", ir);
+
+        let context = Context::new();
+        let mut runner = CompileRunner::new(&context);
+        runner.set_panic_handler("__protosnirk_panic");
+        let provider = runner.compile(check_source(source), false);
+        let ir = provider.module().print_to_string();
+        assert!(ir.contains("declare void @__protosnirk_panic()"),
+                "Custom panic handler not declared:\n{}", ir);
+        assert_eq!(ir.matches("call void @__protosnirk_panic()").count(), 2,
+                   "Failing asserts should call the handler:\n{}", ir);
+        assert!(!ir.contains("llvm.trap"),
+                "Default handler used despite custom one:\n{}", ir);
+    }
+
+    #[test]
+    fn code_after_return_is_not_compiled() {
+        let source = "fn f() -> float\n\
+                      \x20   return 1\n\
+                      \x20   2\n\
+                      fn g()\n\
+                      \x20   do\n\
+                      \x20       return\n\
+                      \x20       let x = 3\n\
+                      \x20   let y = 4\n";
+        let ir = compile_to_ir(source);
+        assert!(ir.contains("ret double 1.000000e+00"), "No return:\n{}", ir);
+        for unreachable in &["2.000000e+00", "3.000000e+00", "4.000000e+00"] {
+            assert!(!ir.contains(unreachable),
+                    "Unreachable {} was compiled:\n{}", unreachable, ir);
+        }
+    }
+
+    #[test]
+    fn float_modulus_uses_frem() {
+        let ir = compile_to_ir("fn rem(x: float) -> float\n\
+                                \x20   x % 2.5\n\
+                                \n\
+                                fn folded() -> float\n\
+                                \x20   7.5 % 2\n");
+        assert!(ir.contains("frem double %load_x, 2.500000e+00"),
+                "Unexpected x % 2.5:\n{}", ir);
+        assert!(ir.contains("ret double 1.500000e+00"),
+                "Unexpected 7.5 % 2:\n{}", ir);
+    }
+
+    #[test]
+    fn operator_fns_can_be_bound_and_called() {
+        let ir = compile_to_ir(
+            "fn main() -> float\n\
+            \x20   let add = (+)\n\
+            \x20   add(left: 1, right: 2)\n");
+        assert!(ir.contains("define double @\"(+)\"(double %left, double %right)"),
+                "Operator fn not defined:\n{}", ir);
+        assert!(ir.contains("fadd double %load_left, %load_right"),
+                "Operator fn doesn't add:\n{}", ir);
+        assert!(ir.contains("store double (double, double)* @\"(+)\""),
+                "Operator fn not bound:\n{}", ir);
+        assert!(ir.contains("call double %load_add(double 1.000000e+00, \
+                             double 2.000000e+00)"),
+                "Bound operator fn not called:\n{}", ir);
+    }
+
+    #[test]
+    fn constant_if_expr_compiles_taken_branch() {
+        let ir = compile_to_ir("fn f() -> float\n\
+                                \x20   if true => 1 else 2\n");
+        assert!(!ir.contains("br i1") && !ir.contains("phi"),
+                "Constant if expression branched:\n{}", ir);
+        assert!(ir.contains("ret double 1.000000e+00"),
+                "Constant if expression did not give 1:\n{}", ir);
+    }
+
+    #[test]
+    fn constant_if_block_compiles_taken_block() {
+        let ir = compile_to_ir("fn f() -> float\n\
+                                \x20   if 2 < 1\n\
+                                \x20       1\n\
+                                \x20   else if true\n\
+                                \x20       2\n\
+                                \x20   else\n\
+                                \x20       3\n");
+        assert!(!ir.contains("br i1") && !ir.contains("phi"),
+                "Constant if block branched:\n{}", ir);
+        assert!(ir.contains("ret double 2.000000e+00"),
+                "Constant if block did not give 2:\n{}", ir);
+    }
+
+    #[test]
+    fn if_block_branches_to_then_on_true() {
+        let ir = compile_to_ir("fn f(x: bool) -> float\n\
+                                \x20   if x\n\
+                                \x20       1\n\
+                                \x20   else\n\
+                                \x20       2\n");
+        assert!(ir.contains("br i1 %load_x, label %if_1_then, label %else_block"),
+                "If block did not branch on its condition:\n{}", ir);
+    }
+
+    #[test]
+    fn mutually_recursive_fns_call_each_other() {
+        let ir = compile_to_ir("fn is_even(n: float) -> bool\n\
+                                \x20   if n == 0 => true else is_odd(n: n - 1)\n\
+                                fn is_odd(n: float) -> bool\n\
+                                \x20   if n == 0 => false else is_even(n: n - 1)\n");
+        assert!(ir.contains("call i1 @is_odd("), "is_odd not called:\n{}", ir);
+        assert!(ir.contains("call i1 @is_even("), "is_even not called:\n{}", ir);
+    }
+
+    #[test]
+    fn unannotated_mutually_recursive_fns_return_unit() {
+        // A fn without a return type returns `()` rather than having it
+        // inferred, so there's nothing to infer through the cycle.
+        let ir = compile_to_ir("fn ping()\n\
+                                \x20   pong()\n\
+                                fn pong()\n\
+                                \x20   ping()\n");
+        assert!(ir.contains("define void @ping()"), "ping not void:\n{}", ir);
+        assert!(ir.contains("call void @pong()"), "pong not called:\n{}", ir);
+    }
+
+    #[test]
+    fn comparisons_use_operand_type() {
+        let ir = compile_to_ir("fn f(a: float, b: float, c: char, d: bool) -> bool\n\
+                                \x20   let lt = a < b\n\
+                                \x20   let ge = a >= b\n\
+                                \x20   let eq = c == 'x'\n\
+                                \x20   d != lt\n");
+        for expected in &["fcmp olt double", "fcmp oge double",
+                          "icmp eq i32", "icmp ne i1"] {
+            assert!(ir.contains(expected), "No {}:\n{}", expected, ir);
+        }
+    }
+
+    #[test]
+    fn special_floats_are_llvm_constants() {
+        let ir = compile_to_ir("fn infinity() -> float => inf\n\
+                                fn quiet_nan() -> float => nan\n\
+                                fn twelve() -> float => 0x1.8p3\n");
+        for expected in &["ret double 0x7FF0000000000000",
+                          "ret double 0x7FF8000000000000",
+                          "ret double 1.200000e+01"] {
+            assert!(ir.contains(expected), "No {}:\n{}", expected, ir);
+        }
+    }
+
+    #[test]
+    fn failed_assert_eq_prints_both_values() {
+        let source = "fn check(a: float, b: bool)\n\
+                      \x20   assert_eq(a, 2 + 2)\n\
+                      \x20   assert_eq(b, a > 1)\n";
+        let checked = check_source(source);
+        assert!(checked.lints().is_empty(),
+                "assert_eq was linted: {:?}", checked.lints());
+        let ir = compile_to_ir(source);
+        for expected in &["declare void @print(double)", "fcmp oeq double",
+                          "icmp eq i1", "uitofp i1"] {
+            assert!(ir.contains(expected), "No {}:\n{}", expected, ir);
+        }
+        assert_eq!(ir.matches("call void @print(double").count(), 4,
+                   "Both values of each assert_eq not printed:\n{}", ir);
+        assert_eq!(ir.matches("call void @llvm.trap()").count(), 2);
+
+        assert!(Runner::from_string("fn main()\n    assert_eq(1, true)\n",
+                                    "test".into())
+                    .parse().expect("Could not parse test source")
+                    .identify()
+                    .map(|unit| unit.check().is_err())
+                    .unwrap_or(true),
+                "assert_eq of a float and a bool was allowed");
+    }
+
+    #[test]
+    fn nan_is_unequal_to_itself() {
+        let source = "fn nan_eq() -> bool\n\
+                      \x20   nan == nan\n\
+                      fn nan_neq() -> bool\n\
+                      \x20   nan != nan\n\
+                      fn neq(a: float, b: float) -> bool\n\
+                      \x20   a != b\n";
+        let compile_with = |comparisons| {
+            let context = Context::new();
+            let mut runner = CompileRunner::new(&context);
+            runner.set_float_comparisons(comparisons);
+            let provider = runner.compile(check_source(source), false);
+            provider.module().print_to_string()
+        };
+        let results = |ir: &str| {
+            ir.split("define ").skip(1)
+                .map(|fn_ir| fn_ir.contains("ret i1 true"))
+                .take(2)
+                .collect::<Vec<_>>()
+        };
+
+        let ir = compile_to_ir(source);
+        assert_eq!(results(&ir), [false, true], "Default semantics:\n{}", ir);
+        assert!(ir.contains("fcmp une double"), "Expected une:\n{}", ir);
+        let ir = compile_with(FloatComparisons::Ieee);
+        assert_eq!(results(&ir), [false, true], "IEEE semantics:\n{}", ir);
+        let ir = compile_with(FloatComparisons::Ordered);
+        assert_eq!(results(&ir), [false, false], "Ordered semantics:\n{}", ir);
+        assert!(ir.contains("fcmp one double"), "Expected one:\n{}", ir);
+        let ir = compile_with(FloatComparisons::Unordered);
+        assert_eq!(results(&ir), [true, true], "Unordered semantics:\n{}", ir);
     }
 }
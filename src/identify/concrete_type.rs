@@ -1,5 +1,7 @@
 //! Concrete type definitions.
 
+use std::fmt;
+
 /// A fully qualified type.
 ///
 /// These are first identified in `identify/types`,
@@ -17,6 +19,60 @@ pub enum ConcreteType {
     Function(FnType),
 }
 
+impl ConcreteType {
+    /// Whether the types have the same structure, regardless of the names of
+    /// fns' params.
+    ///
+    /// `(x: float) -> float` and `(y: float) -> float` are structurally
+    /// equal, as params are compared by position, so either fn could be
+    /// assigned to a variable of the other's type. `==` also compares the
+    /// param names.
+    pub fn structurally_eq(&self, other: &ConcreteType) -> bool {
+        match (self, other) {
+            (ConcreteType::Named(named), ConcreteType::Named(other_named)) =>
+                named == other_named,
+            (ConcreteType::Function(fn_ty), ConcreteType::Function(other_fn)) =>
+                fn_ty.args.len() == other_fn.args.len()
+                && fn_ty.args.iter().zip(&other_fn.args)
+                    .all(|((_, arg), (_, other_arg))|
+                        arg.structurally_eq(other_arg))
+                && fn_ty.ret.structurally_eq(&other_fn.ret),
+            _ => false
+        }
+    }
+
+    /// Whether a value of this type can be used where a `target` is
+    /// expected, such as a call argument or the rvalue of an assignment.
+    ///
+    /// Currently this is when the types are structurally equal. Coercions
+    /// belong here as they're added: a never type would be assignable to
+    /// anything, and numeric types could widen once there's more than
+    /// `float`.
+    pub fn is_assignable_to(&self, target: &ConcreteType) -> bool {
+        self.structurally_eq(target)
+    }
+}
+
+/// Shows the type as it would be written, i.e. `float` or
+/// `fn(x: float) -> bool`.
+impl fmt::Display for ConcreteType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConcreteType::Named(ref named) => f.write_str(named.name()),
+            ConcreteType::Function(ref fn_ty) => {
+                try!(f.write_str("fn("));
+                for (ix, (name, ty)) in fn_ty.params().iter().enumerate() {
+                    if ix > 0 {
+                        try!(f.write_str(", "));
+                    }
+                    try!(write!(f, "{}: {}", name, ty));
+                }
+                write!(f, ") -> {}", fn_ty.return_ty())
+            }
+        }
+    }
+}
+
 /// A named type.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct NamedType {
@@ -51,3 +107,45 @@ impl FnType {
         &*self.ret
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ConcreteType, FnType, NamedType};
+
+    fn float() -> ConcreteType {
+        ConcreteType::Named(NamedType::new("float".into()))
+    }
+
+    fn fn_type(params: &[&str], ret: ConcreteType) -> ConcreteType {
+        ConcreteType::Function(FnType::new(
+            params.iter().map(|name| (name.to_string(), float())).collect(),
+            ret))
+    }
+
+    #[test]
+    fn fn_types_are_structurally_eq_regardless_of_param_names() {
+        let takes_x = fn_type(&["x"], float());
+        let takes_y = fn_type(&["y"], float());
+        assert!(takes_x.structurally_eq(&takes_y));
+        assert_ne!(takes_x, takes_y);
+
+        let bool_ty = ConcreteType::Named(NamedType::new("bool".into()));
+        assert!(!takes_x.structurally_eq(&fn_type(&["x"], bool_ty)));
+        assert!(!takes_x.structurally_eq(&fn_type(&["x", "y"], float())));
+        assert!(!takes_x.structurally_eq(&float()));
+    }
+
+    #[test]
+    fn assignable_types_are_structurally_eq() {
+        let bool_ty = ConcreteType::Named(NamedType::new("bool".into()));
+        assert!(float().is_assignable_to(&float()));
+        assert!(fn_type(&["x"], float())
+                .is_assignable_to(&fn_type(&["y"], float())));
+
+        assert!(!float().is_assignable_to(&bool_ty));
+        assert!(!bool_ty.is_assignable_to(&float()));
+        assert!(!fn_type(&["x"], float()).is_assignable_to(&float()));
+        assert!(!fn_type(&["x"], float())
+                .is_assignable_to(&fn_type(&["x"], bool_ty)));
+    }
+}
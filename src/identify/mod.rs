@@ -52,8 +52,8 @@ pub use self::type_scope_builder::TypeScopeBuilder;
 pub use self::concrete_type::*;
 pub use self::names::OriginManager;
 
-use ast::{Unit, ScopedId, visit::UnitVisitor};
-use check::ErrorCollector;
+use ast::{Item, Unit, ScopedId, visit::UnitVisitor};
+use check::{CheckerError, ErrorCollector};
 
 use self::names::*;
 use self::types::*;
@@ -77,11 +77,100 @@ impl<'var_scope, 'ty_scope, 'err> ASTIdentifier<'var_scope, 'ty_scope, 'err> {
                -> ASTIdentifier<'var_scope, 'ty_scope, 'err> {
         ASTIdentifier { var_scope, type_scope, errors }
     }
+
+    /// Marks the unit as identified, or reports an error if it already was.
+    ///
+    /// Gives whether the unit can be identified.
+    fn mark_identified(&mut self, unit: &Unit) -> bool {
+        if !unit.mark_identified() {
+            self.errors.add_error(CheckerError::new(vec![unit.span()],
+                "Unit has already been identified, so it can't be \
+                 identified again"));
+            return false
+        }
+        true
+    }
+
+    /// Identifies a unit of edited fns, using the scopes built when their
+    /// unit was identified.
+    ///
+    /// Each fn takes the IDs of the fn it replaces, so only its body is
+    /// identified. Its signature must not have changed, as it may be used by
+    /// the unit's other fns.
+    pub fn visit_edited_unit(&mut self, unit: &Unit) {
+        if !self.mark_identified(unit) {
+            return
+        }
+        for item in unit.items() {
+            let block_fn = match *item {
+                Item::BlockFnDeclaration(ref block_fn) => block_fn,
+                ref other => {
+                    self.errors.add_error(CheckerError::new(
+                        vec![other.ident().span()],
+                        format!("Cannot re-check {}, only fns can be \
+                                 re-checked", other.ident().name())));
+                    continue
+                }
+            };
+            let fn_id = match self.var_scope.get(block_fn.name()) {
+                Some(fn_id) => fn_id.clone(),
+                None => {
+                    self.errors.add_error(CheckerError::new(
+                        vec![block_fn.span()],
+                        format!("Function {} was not declared in the checked \
+                                 unit", block_fn.name())));
+                    continue
+                }
+            };
+            trace!("Reusing id {:?} for fn {}", fn_id, block_fn.name());
+            block_fn.ident().set_id(fn_id);
+            // Params are also defined globally by `ItemVarIdentifier`.
+            for (param, _) in block_fn.params() {
+                let global_name = format!("{}::{}",
+                                          block_fn.name(), param.name());
+                if let Some(param_id) = self.var_scope.get(&global_name) {
+                    param.set_id(param_id.clone());
+                }
+            }
+        }
+        if !self.errors.errors().is_empty() {
+            return
+        }
+        // Identifying the signatures again gives the same types if they're
+        // unchanged. Renamed or added params change the type.
+        let checked_types = unit.items().iter()
+            .map(|item| self.type_scope.get_type(&item.ident().id()).cloned())
+            .collect::<Vec<_>>();
+        ItemTypeIdentifier::new(self.errors, self.type_scope)
+                           .visit_unit(unit);
+        for (item, checked) in unit.items().iter().zip(checked_types) {
+            let fn_type = self.type_scope.get_type(&item.ident().id());
+            if fn_type != checked.as_ref() {
+                self.errors.add_error(CheckerError::new(
+                    vec![item.ident().span()],
+                    format!("The signature of function {} has changed, so \
+                             its unit must be checked again",
+                             item.ident().name())));
+            }
+        }
+        if !self.errors.errors().is_empty() {
+            return
+        }
+        let first_id = ScopedId::default().pushed();
+        ExpressionVarIdentifier::new(self.errors, self.var_scope,
+                                     self.type_scope, first_id)
+                                .visit_unit(unit);
+        ExprTypeIdentifier::new(self.errors, self.type_scope)
+                           .visit_unit(unit);
+    }
 }
 
 impl<'var_scope, 'ty_scope, 'err> UnitVisitor
                                 for ASTIdentifier<'var_scope, 'ty_scope, 'err> {
     fn visit_unit(&mut self, unit: &Unit) {
+        if !self.mark_identified(unit) {
+            return
+        }
         // The ItemVarIdentifier uses its ScopedId to set up actual scoping.
         // This could be handled by ScopeBuilder.
         let first_id = ScopedId::default().pushed();
@@ -92,10 +181,195 @@ impl<'var_scope, 'ty_scope, 'err> UnitVisitor
         ItemTypeIdentifier::new(self.errors, self.type_scope)
                            .visit_unit(unit);
         debug!("Calling ExpressionVarIdentifier");
-        ExpressionVarIdentifier::new(self.errors, self.var_scope, first_id)
+        ExpressionVarIdentifier::new(self.errors, self.var_scope,
+                                     self.type_scope, first_id)
                                 .visit_unit(unit);
         debug!("Calling ExprTypeIdentifier");
         ExprTypeIdentifier::new(self.errors, self.type_scope)
                            .visit_unit(unit);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ast::{Declaration, Identifier, ScopedId, Unit};
+    use ast::visit::{AnalysisVisitor, UnitVisitor};
+    use check::ErrorCollector;
+    use lex::IterTokenizer;
+    use parse::Parser;
+    use super::{ASTIdentifier, NameScopeBuilder, TypeScopeBuilder};
+
+    /// Identifies a parsed unit with new scope builders.
+    fn identify_unit(unit: &Unit) -> ErrorCollector {
+        let mut names = NameScopeBuilder::new();
+        let mut types = TypeScopeBuilder::with_primitives();
+        let mut errors = ErrorCollector::new();
+        ASTIdentifier::new(&mut names, &mut types, &mut errors)
+            .visit_unit(unit);
+        errors
+    }
+
+    /// Parses and identifies the source, giving the errors found.
+    fn identify(source: &str) -> (Unit, ErrorCollector) {
+        let unit = Parser::new(IterTokenizer::new(source.chars()))
+            .parse_unit().expect("Could not parse test source");
+        let errors = identify_unit(&unit);
+        (unit, errors)
+    }
+
+    #[test]
+    fn identifying_twice_is_an_error() {
+        let source = "fn main()\n    let x = 1\n    let y = x\n";
+        let (unit, errors) = identify(source);
+        assert!(errors.errors().is_empty(), "Errors: {:?}", errors.errors());
+        assert!(unit.is_identified());
+        let ids = VariableIds::default().analyze(&unit);
+
+        let errors = identify_unit(&unit);
+        let texts = errors.errors().iter().map(|error| error.text())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["Unit has already been identified, so it can't \
+                            be identified again"]);
+        // The unit's IDs were left alone.
+        assert_eq!(VariableIds::default().analyze(&unit), ids);
+
+        // Whether a unit was identified doesn't change what it is.
+        let parsed = Parser::new(IterTokenizer::new(source.chars()))
+            .parse_unit().expect("Could not parse test source");
+        let marked = parsed.clone();
+        assert!(marked.mark_identified());
+        assert_eq!(parsed, marked);
+    }
+
+    #[test]
+    fn duplicate_params_are_reported_once() {
+        let source = "fn f(x: float, y: float, x: float)\n    x\n";
+        let (_, errors) = identify(source);
+
+        assert_eq!(errors.errors().len(), 1, "Errors: {:?}", errors.errors());
+        let error = &errors.errors()[0];
+        assert_eq!(error.text(), "Parameter x of function f is declared twice");
+        let columns = error.spans().iter()
+            .map(|span| span.start().column())
+            .collect::<Vec<_>>();
+        assert_eq!(columns, [5, 25]);
+    }
+
+    #[test]
+    fn chained_comparisons_are_reported_once() {
+        for source in &["fn main()\n    let x = 1 < 2 < 3\n",
+                        "fn main()\n    let x = 1 < 2 <= 3 > 4\n"] {
+            let (_, errors) = identify(source);
+
+            assert_eq!(errors.errors().len(), 1,
+                       "Errors for {:?}: {:?}", source, errors.errors());
+            assert!(errors.errors()[0].to_string()
+                        .starts_with("Comparisons cannot be chained"),
+                    "Unexpected error {}", errors.errors()[0]);
+        }
+    }
+
+    #[test]
+    fn paths_without_a_return_value_are_reported() {
+        let cases = [
+            ("fn f(x: bool) -> float\n    if x\n        return 1\n",
+             Some((1, 4))),
+            ("fn f(x: bool) -> float\n    if x\n        1\n    else\n        \
+              let y = 2\n", Some((4, 8))),
+            ("fn f(x: bool) -> float\n    do\n        let y = 2\n",
+             Some((2, 8))),
+            ("fn f(x: bool) -> float\n    if x\n        return 1\n    \
+              else if true\n        2\n    else\n        return 3\n", None),
+            ("fn f(x: bool) -> float\n    return 1\n    let y = 2\n", None),
+        ];
+        for &(source, expected) in &cases {
+            let (_, errors) = identify(source);
+
+            let found = errors.errors().iter()
+                .map(|error| {
+                    assert!(error.text().starts_with(
+                        "Function f is missing a return value"),
+                        "Unexpected error for {:?}: {}", source, error);
+                    let start = error.offender().expect("No span").start();
+                    (start.line(), start.column())
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(found, expected.into_iter().collect::<Vec<_>>(),
+                       "Wrong errors for {:?}", source);
+        }
+    }
+
+    /// Gets the ids of the variables declared and referenced in a unit, in
+    /// source order.
+    #[derive(Default)]
+    struct VariableIds(Vec<(String, ScopedId)>);
+
+    impl AnalysisVisitor for VariableIds {
+        type Output = Vec<(String, ScopedId)>;
+
+        fn finish(self) -> Vec<(String, ScopedId)> {
+            self.0
+        }
+
+        fn visit_declaration(&mut self, decl: &Declaration) {
+            self.visit_expression(decl.value());
+            self.0.push((format!("let {}", decl.name()), decl.id()));
+        }
+
+        fn visit_var_ref(&mut self, ident: &Identifier) {
+            self.0.push((ident.name().to_string(), ident.id()));
+        }
+    }
+
+    #[test]
+    fn variables_resolve_to_the_scope_they_are_declared_in() {
+        let source = "fn main()\n\
+                      \x20   let x = 1\n\
+                      \x20   do\n\
+                      \x20       let y = x\n\
+                      \x20       let z = y\n\
+                      \x20   let y = x\n\
+                      \x20   let z = y\n";
+        let (unit, errors) = identify(source);
+        assert!(errors.errors().is_empty(), "Errors: {:?}", errors.errors());
+
+        let ids = VariableIds::default().analyze(&unit);
+        let names = ids.iter().map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["let x",
+                           "x", "let y", "y", "let z",
+                           "x", "let y", "y", "let z"]);
+        let id = |ix: usize| &ids[ix].1;
+        assert!(ids.iter().all(|(_, id)| !id.is_default()));
+
+        // The inner block sees the outer `x`.
+        assert_eq!(id(1), id(0));
+        // Its `y` and `z` are in a deeper scope than the outer ones.
+        assert_eq!(id(3), id(2));
+        assert_eq!(id(7), id(6));
+        assert_ne!(id(2), id(6));
+        assert_ne!(id(4), id(8));
+        assert_eq!(id(2).popped().popped(), id(6).popped(),
+                   "Inner {:?} not in a scope under outer {:?}", id(2), id(6));
+        // References after the block resolve to the outer variables.
+        assert_eq!(id(5), id(0));
+    }
+
+    #[test]
+    fn variables_cannot_be_shadowed_in_inner_scopes() {
+        let source = "fn main()\n\
+                      \x20   let x = 1\n\
+                      \x20   do\n\
+                      \x20       let x = 2\n\
+                      \x20   let y = x\n";
+        let (unit, errors) = identify(source);
+
+        let texts = errors.errors().iter().map(|error| error.text())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["Variable x is already declared"]);
+        // The outer `x` is still the one used after the block.
+        let ids = VariableIds::default().analyze(&unit);
+        assert_eq!(ids[2].0, "x");
+        assert_eq!(ids[2].1, ids[0].1);
+    }
+}
@@ -2,7 +2,7 @@
 
 use lex::Span;
 use ast::{*, visit::*};
-use identify::{NameScopeBuilder, OriginManager};
+use identify::{NameScopeBuilder, TypeScopeBuilder, OriginManager};
 use check::{CheckerError, ErrorCollector};
 
 /// Identifies variables in blocks.
@@ -10,6 +10,8 @@ use check::{CheckerError, ErrorCollector};
 pub struct ExpressionVarIdentifier<'err, 'builder> {
     errors: &'err mut ErrorCollector,
     builder: &'builder mut NameScopeBuilder,
+    /// Types of the unit's items, used to tell which fns return `()`.
+    types: &'builder TypeScopeBuilder,
     /// `ScopedId` to give to expressions
     current_id: ScopedId,
     /// `ScopedId` of the current function which we
@@ -17,19 +19,114 @@ pub struct ExpressionVarIdentifier<'err, 'builder> {
     /// Stack of lvalues which can be assigned to the current expression.
     /// For example, a block in a function which returns a value would have
     /// an lvalue of the function's ID.
-    lvalues: OriginManager
+    lvalues: OriginManager,
+    /// Names of params which have not been defined yet while checking the
+    /// default values of a function's params.
+    later_params: Vec<String>
 }
 impl<'err, 'builder> ExpressionVarIdentifier<'err, 'builder> {
     pub fn new(errors: &'err mut ErrorCollector,
                builder: &'builder mut NameScopeBuilder,
+               types: &'builder TypeScopeBuilder,
                current_id: ScopedId)
                -> ExpressionVarIdentifier<'err, 'builder> {
         ExpressionVarIdentifier {
             errors,
             builder,
+            types,
             current_id,
             current_fn_id: ScopedId::default(),
-            lvalues: OriginManager::new()
+            lvalues: OriginManager::new(),
+            later_params: Vec::new()
+        }
+    }
+}
+
+impl<'err, 'builder> ExpressionVarIdentifier<'err, 'builder> {
+    /// Emit an error if the given expression is used as a value but does not
+    /// have one.
+    ///
+    /// See https://github.com/immington-industries/protosnirk/issues/30
+    fn check_has_value(&mut self, expr: &Expression) {
+        if let Expression::Assignment(ref assign) = *expr {
+            debug!("Emitting error: assignment to {} used as a value",
+                assign.lvalue().name());
+            let error_text = format!(
+                "Assignment to {} cannot be used as a value",
+                assign.lvalue().name());
+            self.errors.add_error(CheckerError::new(
+                vec![assign.span()], error_text
+            ));
+        }
+    }
+}
+
+/// Finds the statement through which control can reach the end of a fn's
+/// `block` without giving it a value, if there is one.
+///
+/// Each path through the block has to either return or end with an
+/// expression for the block's value.
+fn path_without_value(block: &Block) -> Option<&Statement> {
+    if block.stmts().iter().any(Statement::diverges) {
+        return None
+    }
+    // Empty blocks are reported when they're visited.
+    let last_stmt = block.stmts().last()?;
+    match *last_stmt {
+        Statement::Expression(_) | Statement::Return(_) => None,
+        Statement::DoBlock(ref do_block) => path_without_value(do_block.block()),
+        Statement::IfBlock(ref if_block) => match if_block.else_block() {
+            Some(else_block) => if_block.conditionals().iter()
+                .map(|cond| cond.block())
+                .chain(Some(else_block))
+                .filter_map(path_without_value)
+                .next(),
+            None => Some(last_stmt)
+        },
+        Statement::Declaration(_) | Statement::Assert(_) => Some(last_stmt)
+    }
+}
+
+/// Gets the comparison `expr` is, if it is one.
+fn as_comparison(expr: &Expression) -> Option<&BinaryOperation> {
+    match *expr {
+        Expression::BinaryOp(ref bin_op) if bin_op.operator().is_comparison() =>
+            Some(bin_op),
+        _ => None
+    }
+}
+
+/// Whether `bin_op` is a comparison with a comparison for an operand.
+fn is_chained_comparison(bin_op: &BinaryOperation) -> bool {
+    bin_op.operator().is_comparison()
+        && (as_comparison(bin_op.left()).is_some()
+            || as_comparison(bin_op.right()).is_some())
+}
+
+impl<'err, 'builder> ExpressionVarIdentifier<'err, 'builder> {
+    /// Emit an error if an ordering comparison, such as `<`, compares the
+    /// result of another comparison, as in `a < b < c`.
+    ///
+    /// Comparisons give `bool`s, which can't be ordered, so this would be a
+    /// type error anyway.
+    fn check_chained_comparison(&mut self, bin_op: &BinaryOperation) {
+        if !bin_op.operator().is_ordering() {
+            return
+        }
+        let operands = [bin_op.left(), bin_op.right()];
+        // Longer chains are reported once, at their innermost link.
+        if operands.iter().filter_map(|&operand| as_comparison(operand))
+                .any(is_chained_comparison) {
+            return
+        }
+        if let Some(inner) = operands.iter().filter_map(|&o| as_comparison(o)).next() {
+            debug!("Emitting error: chained comparison at {}", bin_op.span());
+            self.errors.add_error(CheckerError::new(
+                vec![bin_op.span(), inner.span()],
+                "Comparisons cannot be chained: this compares the bool \
+                 result of another comparison")
+                .with_note("compare each pair of values separately, \
+                            i.e. `a < b` and `b < c` instead of `a < b < c`"));
         }
     }
 }
@@ -61,13 +158,24 @@ impl<'err, 'builder> ItemVisitor for ExpressionVarIdentifier<'err, 'builder> {
         self.current_id.push();
         self.builder.new_scope();
 
-        for &(ref param, ref _param_type) in block_fn.params() {
+        for (ix, &(ref param, ref _param_type)) in
+                block_fn.params().iter().enumerate() {
             let param_name = param.name();
             if param.id().is_default() {
                 debug!("Skipping block fn {} because param {} does no ID",
                     block_fn.name(), param_name);
                 return
             }
+            // Default values can use the params before them, so we check
+            // them before the param itself is defined.
+            if let Some(default) = block_fn.param_default(ix) {
+                trace!("Checking default value of param {}", param_name);
+                self.later_params = block_fn.params()[ix..].iter()
+                    .map(|&(ref later, _)| later.name().to_string())
+                    .collect();
+                self.visit_expression(default);
+                self.later_params.clear();
+            }
             // We re-define parameters here even though they've already been
             // identified, because the NameScopeBuilder discards its scopes
             // after visiting.
@@ -77,8 +185,36 @@ impl<'err, 'builder> ItemVisitor for ExpressionVarIdentifier<'err, 'builder> {
                                       param.span());
         }
 
-        if block_fn.has_explicit_return_type() {
-            self.lvalues.add_source(block_fn.id().clone());
+        // Fns which return `()` don't need a value, even when it's written
+        // out or named by a typedef.
+        if block_fn.has_explicit_return_type()
+                && !self.types.is_unit_type(&block_fn.return_type().id()) {
+            if block_fn.block().stmts().is_empty() {
+                debug!("Emitting error: {} has an empty body",
+                    block_fn.name());
+                let error_text = format!(
+                    "Function {} is missing a return value: its body is empty",
+                    block_fn.name());
+                self.errors.add_error(CheckerError::new(
+                    vec![block_fn.span()], error_text
+                ));
+            }
+            else if let Some(stmt) = path_without_value(block_fn.block()) {
+                debug!("Emitting error: {} can end without a value",
+                    block_fn.name());
+                let error_text = format!(
+                    "Function {} is missing a return value: control can reach \
+                     the end of its body here", block_fn.name());
+                let mut error = CheckerError::new(vec![stmt.span()], error_text);
+                if let Statement::IfBlock(_) = *stmt {
+                    error = error.with_note("The if has no `else` for when its \
+                                             conditions are false");
+                }
+                self.errors.add_error(error);
+            }
+            else {
+                self.lvalues.add_source(block_fn.id().clone());
+            }
         }
 
         self.current_fn_id = block_fn.id().clone();
@@ -97,6 +233,10 @@ impl<'err, 'builder> ItemVisitor for ExpressionVarIdentifier<'err, 'builder> {
         // pushing handled by `visit_block`, we reset current_id on next item.
     }
 
+    fn visit_extern_fn_decl(&mut self, _extern_fn: &ExternFnDeclaration) {
+        // skip, extern fns have no expressions
+    }
+
     fn visit_typedef(&mut self, _typedef: &Typedef) {
         // skip, only visiting expressions
     }
@@ -124,12 +264,9 @@ impl<'err, 'builder> BlockVisitor for ExpressionVarIdentifier<'err, 'builder> {
                 // so we have to construct a terrible one.
                 // https://github.com/immington-industries/protosnirk/issues/39
 
-                let error_message = format!(
-                    "Code includes an empty block expression"
-                );
                 self.errors.add_error(CheckerError::new(
                     vec![Span::default()],
-                    error_message
+                    "Code includes an empty block expression"
                 ));
                 self.lvalues.pop_source();
                 return
@@ -150,7 +287,11 @@ impl<'err, 'builder> BlockVisitor for ExpressionVarIdentifier<'err, 'builder> {
             // Ensure the last statement should return to this block.
             self.lvalues.add_source(block.id().clone());
             // We want the last source
-            self.visit_stmt(block.stmts().last().expect("Checked expect"));
+            let last_stmt = block.stmts().last().expect("Checked expect");
+            if let Statement::Expression(ref last_expr) = *last_stmt {
+                self.check_has_value(last_expr);
+            }
+            self.visit_stmt(last_stmt);
         }
         else {
             visit::walk_block(self, block);
@@ -180,11 +321,14 @@ impl<'err, 'builder> StatementVisitor
         let has_lvalue = self.lvalues.has_source();
         if has_lvalue {
             trace!("Found expression if block");
+            // Without an `else`, no branch runs when every condition is
+            // `false`, so the block can't always produce a value.
             if !if_block.has_else() {
                 debug!("Expression if block did not have else");
                 self.errors.add_error(CheckerError::new(
                     vec![if_block.span()],
-                    format!("If block needed to return a value but did not")
+                    "If block is used as a value but does not cover every \
+                     case: it needs an `else` for when its conditions are false"
                 ));
                 return
             }
@@ -260,6 +404,15 @@ impl<'err, 'builder> StatementVisitor
             }
         }
     }
+
+    fn visit_assert_stmt(&mut self, assert: &Assert) {
+        trace!("Visiting assert statement");
+        if assert.equality_operands().is_some() {
+            assert.set_id(self.current_id.clone());
+            self.current_id.increment();
+        }
+        visit::walk_assert(self, assert);
+    }
 }
 
 impl<'err, 'builder> ExpressionVisitor
@@ -268,6 +421,11 @@ impl<'err, 'builder> ExpressionVisitor
     fn visit_literal_expr(&mut self, _literal: &Literal) { }
 
     fn visit_if_expr(&mut self, if_expr: &IfExpression) {
+        self.check_has_value(if_expr.condition());
+        self.check_has_value(if_expr.true_expr());
+        self.check_has_value(if_expr.else_expr());
+        if_expr.set_id(self.current_id.clone());
+        self.current_id.increment();
         visit::walk_if_expr(self, if_expr);
     }
 
@@ -276,6 +434,7 @@ impl<'err, 'builder> ExpressionVisitor
     }
 
     fn visit_binary_op(&mut self, bin_op: &BinaryOperation) {
+        self.check_chained_comparison(bin_op);
         visit::walk_bin_op(self, bin_op);
     }
 
@@ -283,6 +442,10 @@ impl<'err, 'builder> ExpressionVisitor
         trace!("Visiting assignment to {}", assign.lvalue().name());
         // Give the required rvalue to the expression
         // Enables https://github.com/immington-industries/protosnirk/issues/27
+        // The lvalue has to be identified first so its ID can be the source.
+        if let Some(var_id) = self.builder.get(assign.lvalue().name()).cloned() {
+            assign.lvalue().set_id(var_id);
+        }
         let lvalue_id = assign.lvalue().id().clone();
         if lvalue_id.is_default() {
             trace!("Found assignment to unknown var");
@@ -300,7 +463,6 @@ impl<'err, 'builder> ExpressionVisitor
         if self.lvalues.has_top_source(&assign.lvalue().id()) {
             self.lvalues.pop_source();
         }
-        self.visit_var_ref(assign.lvalue());
     }
 
     fn visit_var_ref(&mut self, ident: &Identifier) {
@@ -308,6 +470,16 @@ impl<'err, 'builder> ExpressionVisitor
         if let Some(var_id) = self.builder.get(ident.name()).cloned() {
             ident.set_id(var_id);
         }
+        else if self.later_params.iter().any(|name| name == ident.name()) {
+            debug!("Emitting error: default value uses later param {}",
+                ident.name());
+            let err_text = format!(
+                "Default value cannot refer to parameter {} declared after it",
+                ident.name());
+            self.errors.add_error(CheckerError::new(
+                vec![ident.span()], err_text
+            ));
+        }
         else {
             debug!("Emitting error: unknown ident {}", ident.name());
             // Unknown var
@@ -325,6 +497,7 @@ impl<'err, 'builder> ExpressionVisitor
             fn_call.ident().set_id(fn_id);
             // Check args
             for arg in fn_call.args() {
+                self.check_has_value(arg.expression());
                 self.visit_expression(arg.expression());
             }
         }
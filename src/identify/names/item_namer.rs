@@ -1,5 +1,6 @@
 //! AST visitor which assigns the ScopedIds of types on items.
 
+use lex::Span;
 use ast::{*, visit::*};
 use check::{CheckerError, ErrorCollector};
 use identify::NameScopeBuilder;
@@ -23,46 +24,33 @@ impl<'err, 'builder> ItemVarIdentifier<'err, 'builder> {
             current_id
         }
     }
-}
-
-impl<'err, 'builder> UnitVisitor for ItemVarIdentifier<'err, 'builder> {
-    fn visit_unit(&mut self, unit: &Unit) {
-        trace!("Visting a unit");
-        // items are defined on the top level of the ScopedId.
-        // We're passed in a ScopedId which is assumed to be non-default
-        // so that the first item doesn't get a default scopedId
-        self.builder.new_scope();
 
-        visit::walk_unit(self, unit);
-
-        self.current_id.increment();
-    }
-}
-
-impl<'err, 'builder> ItemVisitor for ItemVarIdentifier<'err, 'builder> {
-    fn visit_block_fn_decl(&mut self, block_fn: &BlockFnDeclaration) {
-        trace!("Visiting fn definition {}", block_fn.name());
-        if let Some(previous_def_id) = self.builder.get(block_fn.name()) {
+    /// Names a function and its params.
+    fn identify_fn(&mut self,
+                   ident: &Identifier,
+                   params: &[(Identifier, TypeExpression)],
+                   span: Span) {
+        if let Some(previous_def_id) = self.builder.get(ident.name()) {
             let previous_span = self.builder.info_for(previous_def_id)
                 .expect("checked expect");
             // fn has been previously defined
-            debug!("Emitting error: {} already declared", block_fn.name());
+            debug!("Emitting error: {} already declared", ident.name());
             self.errors.add_error(CheckerError::new(
-                vec![block_fn.span(), *previous_span],
-                format!("Function {} is already declared", block_fn.name())
+                vec![span, *previous_span],
+                format!("Function {} is already declared", ident.name())
             ));
             return
         }
         // If it was not in the builder its ID should be default.
-        debug_assert!(block_fn.ident().id().is_default(),
-            "Block fn {:?} already had an ID", block_fn);
+        debug_assert!(ident.id().is_default(),
+            "Fn {:?} already had an ID", ident);
 
         let fn_id = self.current_id.clone();
-        trace!("Created id {:?} for block fn {}", fn_id, block_fn.name());
-        self.builder.define_local(block_fn.name().to_string(),
+        trace!("Created id {:?} for fn {}", fn_id, ident.name());
+        self.builder.define_local(ident.name().to_string(),
                                   fn_id.clone(),
-                                  block_fn.span());
-        block_fn.set_id(fn_id);
+                                  span);
+        ident.set_id(fn_id);
 
         // Also name the params, in a new scope.
         // Consider a function with ID [..., n]:
@@ -79,31 +67,44 @@ impl<'err, 'builder> ItemVisitor for ItemVarIdentifier<'err, 'builder> {
 
         // https://github.com/immington-industries/protosnirk/issues/50
 
-        for &(ref param, ref _param_type) in block_fn.params() {
+        for (ix, &(ref param, ref _param_type)) in params.iter().enumerate() {
             let param_name = param.name();
+            let earlier_param = params[..ix].iter()
+                .find(|&&(ref earlier, _)| earlier.name() == param_name);
+            if let Some(&(ref earlier, _)) = earlier_param {
+                debug!("Emitting error: {} has duplicate param {}",
+                    ident.name(), param_name);
+                let error_text = format!(
+                    "Parameter {} of function {} is declared twice",
+                    param_name, ident.name());
+                self.errors.add_error(CheckerError::new(
+                    vec![earlier.span(), param.span()], error_text
+                ));
+                return // Stop checking params if there's a dupe.
+            }
             if let Some(_previous_def_id) = self.builder.get(param_name) {
                 debug!("Emitting error: {} in {} already declared",
-                    param_name, block_fn.name());
+                    param_name, ident.name());
                 let error_text = format!(
                     "Parameter {} of function {} is already declared",
-                    param.name(), block_fn.name());
+                    param.name(), ident.name());
                 self.errors.add_error(CheckerError::new(
-                    vec![block_fn.span()], error_text
+                    vec![span], error_text
                 ));
                 return // Stop checking params if there's a dupe.
             }
 
             trace!("Created id {:?} for {} param {}",
-                self.current_id, block_fn.name(), param.name());
+                self.current_id, ident.name(), param.name());
             self.builder.define_local(param_name.to_string(),
                                       self.current_id.clone(),
                                       param.span());
             // We also put the param in the global scope as this is the only
             // scope visible outside the visitor.
             self.builder.define_global(
-                format!("{}::{}", block_fn.name(), param_name),
+                format!("{}::{}", ident.name(), param_name),
                 self.current_id.clone(),
-                block_fn.span());
+                span);
             param.set_id(self.current_id.clone());
 
             self.current_id.increment();
@@ -113,6 +114,33 @@ impl<'err, 'builder> ItemVisitor for ItemVarIdentifier<'err, 'builder> {
         self.current_id.pop();
         self.current_id.increment();
     }
+}
+
+impl<'err, 'builder> UnitVisitor for ItemVarIdentifier<'err, 'builder> {
+    fn visit_unit(&mut self, unit: &Unit) {
+        trace!("Visting a unit");
+        // items are defined on the top level of the ScopedId.
+        // We're passed in a ScopedId which is assumed to be non-default
+        // so that the first item doesn't get a default scopedId
+        self.builder.new_scope();
+
+        visit::walk_unit(self, unit);
+
+        self.current_id.increment();
+    }
+}
+
+impl<'err, 'builder> ItemVisitor for ItemVarIdentifier<'err, 'builder> {
+    fn visit_block_fn_decl(&mut self, block_fn: &BlockFnDeclaration) {
+        trace!("Visiting fn definition {}", block_fn.name());
+        self.identify_fn(block_fn.ident(), block_fn.params(), block_fn.span());
+    }
+
+    fn visit_extern_fn_decl(&mut self, extern_fn: &ExternFnDeclaration) {
+        trace!("Visiting extern fn declaration {}", extern_fn.name());
+        self.identify_fn(extern_fn.ident(), extern_fn.params(),
+                         extern_fn.span());
+    }
 
     fn visit_typedef(&mut self, typedef: &Typedef) {
         trace!("Visiting type alias {}", typedef.name());
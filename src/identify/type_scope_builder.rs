@@ -13,6 +13,7 @@ pub const PRIMITIVE_TYPE_NAMES: &[&'static str] = &[
     "()",
     "bool",
     "float",
+    "char",
 ];
 
 #[derive(Debug, PartialEq, Clone)]
@@ -53,6 +54,28 @@ impl TypeScopeBuilder {
         self.names.get(name).and_then(|id| self.types.get(id))
     }
 
+    /// Gets the names of all types in scope: the primitives, followed by user
+    /// defined types in the order they were declared.
+    pub fn type_names(&self) -> Vec<&str> {
+        let mut user_types = self.names.iter()
+            .filter(|&(name, _)| !PRIMITIVE_TYPE_NAMES.contains(&name.as_str()))
+            .collect::<Vec<_>>();
+        user_types.sort_by_key(|&(_, id)| id);
+
+        PRIMITIVE_TYPE_NAMES.iter().cloned()
+            .chain(user_types.into_iter().map(|(name, _)| name.as_str()))
+            .collect()
+    }
+
+    /// Whether the type with the given ID is `()`, including through
+    /// typedefs of it.
+    pub fn is_unit_type(&self, id: &ScopedId) -> bool {
+        match self.get_type(id) {
+            Some(ty) => self.named_type("()") == Some(ty),
+            None => false
+        }
+    }
+
     /// Add a new concrete type with the given ID to the type scope.
     pub fn add_type(&mut self, id: ScopedId, ty: ConcreteType) {
         self.types.insert(id, ty);
@@ -63,3 +86,19 @@ impl TypeScopeBuilder {
         self.types.insert(id, ty);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pipeline::check_source;
+
+    #[test]
+    fn type_names_include_primitives_and_typedefs() {
+        let checked = check_source("typedef Meters = float\n\
+                                    typedef Distance = Meters\n\
+                                    \n\
+                                    fn walk(to: Distance) -> Meters\n\
+                                    \x20   to\n");
+        assert_eq!(checked.type_builder().type_names(),
+                   ["()", "bool", "float", "char", "Meters", "Distance"]);
+    }
+}
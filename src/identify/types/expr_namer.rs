@@ -38,6 +38,10 @@ impl<'err, 'builder> ItemVisitor for ExprTypeIdentifier<'err, 'builder> {
         self.visit_block(block_fn.block());
     }
 
+    fn visit_extern_fn_decl(&mut self, _extern_fn: &ExternFnDeclaration) {
+        // skip, extern fns have no expressions
+    }
+
     fn visit_typedef(&mut self, _typedef: &Typedef) {
         // skip, only visiting expressions
     }
@@ -61,6 +65,11 @@ impl<'err, 'builder> StatementVisitor for ExprTypeIdentifier<'err, 'builder> {
         visit::walk_return(self, return_);
     }
 
+    fn visit_assert_stmt(&mut self, assert: &Assert) {
+        trace!("Visiting an assert statement");
+        visit::walk_assert(self, assert);
+    }
+
     fn visit_if_block(&mut self, if_block: &IfBlock) {
         trace!("Visiting an if block");
         visit::walk_if_block(self, if_block);
@@ -1,13 +1,12 @@
 //! Builds the `TypeGraph` using code within functions
 
 use ast::{*, visit::*};
-use identify::TypeScopeBuilder;
+use identify::{ConcreteType, TypeScopeBuilder};
 use identify::types::{TypeGraph, InferenceSource};
 use check::{CheckerError, ErrorCollector};
 
 use petgraph::graph::NodeIndex;
 
-use std::ops::Deref;
 
 /// Creates type equations for functions.
 #[derive(Debug)]
@@ -22,6 +21,8 @@ pub struct ExprTypographer<'err, 'builder, 'graph> {
     current_type: NodeIndex,
     /// Return type of the current function
     fn_ret_type: NodeIndex,
+    /// Whether the current function returns `()`, possibly by an alias
+    fn_returns_unit: bool,
 }
 
 impl<'err, 'builder, 'graph> ExprTypographer<'err, 'builder, 'graph> {
@@ -35,6 +36,7 @@ impl<'err, 'builder, 'graph> ExprTypographer<'err, 'builder, 'graph> {
             graph,
             current_type: NodeIndex::default(),
             fn_ret_type: NodeIndex::default(),
+            fn_returns_unit: false,
         }
     }
 
@@ -44,6 +46,25 @@ impl<'err, 'builder, 'graph> ExprTypographer<'err, 'builder, 'graph> {
             .and_then(|unary_id| self.graph.get_type(unary_id))
             .expect(&format!("Did not have primitive {}", name))
     }
+
+    /// Adds the signature of a fn which isn't declared in the unit, but in
+    /// an already checked unit whose items are being reused.
+    ///
+    /// Gives `None` if the identifier isn't a fn.
+    fn add_checked_fn(&mut self, ident: &Identifier) -> Option<NodeIndex> {
+        match self.builder.get_type(&ident.id()) {
+            Some(&ConcreteType::Function(_)) => { },
+            _ => return None
+        }
+        trace!("Adding signature of checked fn {}", ident.name());
+        // The type is added first, as `add_type` would find the variable.
+        let fn_ty_ix = self.graph.add_type(ident.id());
+        let fn_ix = self.graph.add_variable(ident.id());
+        // var_f: ty_f
+        self.graph.add_inference(fn_ix, fn_ty_ix,
+            InferenceSource::FnSignature(ident.clone()));
+        Some(fn_ix)
+    }
 }
 
 impl<'err, 'builder, 'graph> UnitVisitor
@@ -77,8 +98,8 @@ impl<'err, 'builder, 'graph> ItemVisitor
         // inferring returns.
 
         let need_ret_value =
-            block_fn.return_type().id().deref() !=
-            self.builder.named_type_id("()").expect("Primitive");
+            !self.builder.is_unit_type(&block_fn.return_type().id());
+        self.fn_returns_unit = !need_ret_value;
 
         trace!("fn {} needs ret value? {}",
             block_fn.name(), need_ret_value);
@@ -97,7 +118,8 @@ impl<'err, 'builder, 'graph> ItemVisitor
             InferenceSource::FnSignature(block_fn.ident().clone()));
 
         // Add in connections to the parameter variables.
-        for &(ref param_ident, ref param_expr) in block_fn.params() {
+        for (ix, &(ref param_ident, ref param_expr)) in
+                block_fn.params().iter().enumerate() {
             trace!("Checking {} param {}",
                 block_fn.name(), param_ident.name());
             let param_id = param_ident.id();
@@ -110,6 +132,13 @@ impl<'err, 'builder, 'graph> ItemVisitor
             // var_param: ty_param
             self.graph.add_inference(param_ix, param_ty_ix,
                 InferenceSource::FnParameter(param_ident.clone()));
+
+            // var_param: default
+            if let Some(default) = block_fn.param_default(ix) {
+                self.visit_expression(default);
+                self.graph.add_inference(param_ix, self.current_type,
+                    InferenceSource::ParamDefault(param_ident.clone()));
+            }
         }
 
         self.visit_block(block_fn.block());
@@ -126,6 +155,33 @@ impl<'err, 'builder, 'graph> ItemVisitor
         }
     }
 
+    fn visit_extern_fn_decl(&mut self, extern_fn: &ExternFnDeclaration) {
+        trace!("Visiting extern fn {}", extern_fn.name());
+        let fn_id = extern_fn.id();
+        if fn_id.is_default() {
+            debug!("Skipping extern fn {}, has no ID", extern_fn.name());
+            return
+        }
+
+        // There's no block to check, but the fn and its params still need
+        // types.
+        let fn_ix = self.graph.add_variable(fn_id.clone());
+        let fn_ty_ix = self.graph.add_type(fn_id.clone());
+
+        // var_f: ty_f
+        self.graph.add_inference(fn_ix, fn_ty_ix,
+            InferenceSource::FnSignature(extern_fn.ident().clone()));
+
+        for &(ref param_ident, ref param_expr) in extern_fn.params() {
+            let param_ix = self.graph.add_variable(param_ident.id().clone());
+            let param_ty_ix = self.graph.add_type(param_expr.id().clone());
+
+            // var_param: ty_param
+            self.graph.add_inference(param_ix, param_ty_ix,
+                InferenceSource::FnParameter(param_ident.clone()));
+        }
+    }
+
     fn visit_typedef(&mut self, _typedef: &Typedef) {
         // Only looking at expressions
     }
@@ -247,6 +303,32 @@ impl<'err, 'builder, 'graph> StatementVisitor
         self.current_type = self.primitive_type_ix("()");
     }
 
+    fn visit_assert_stmt(&mut self, assert: &Assert) {
+        trace!("Visiting assert statement");
+        if let Some((left, right)) = assert.equality_operands() {
+            // The condition is `left == right`, which is always a bool.
+            // tassert = tleft, tassert = tright
+            self.visit_expression(left);
+            let left_type_id = self.current_type;
+            self.visit_expression(right);
+            let right_type_id = self.current_type;
+            let values_type_id = self.graph.add_variable(assert.id());
+            self.graph.add_inference(values_type_id, left_type_id,
+                InferenceSource::EqualityOperator);
+            self.graph.add_inference(values_type_id, right_type_id,
+                InferenceSource::EqualityOperator);
+            self.current_type = self.primitive_type_ix("()");
+            return
+        }
+        self.visit_expression(assert.condition());
+        // tcond = tbool
+        let bool_ty_ix = self.primitive_type_ix("bool");
+        self.graph.add_inference(self.current_type, bool_ty_ix,
+            InferenceSource::AssertConditionBool);
+
+        self.current_type = self.primitive_type_ix("()");
+    }
+
     fn visit_return_stmt(&mut self, return_: &Return) {
         trace!("Visiting return type");
         // Expr matches block's return.
@@ -262,12 +344,13 @@ impl<'err, 'builder, 'graph> StatementVisitor
             }
         }
         // return
-        else {
-            // Need to make sure this is a type error.
-            // ty_fn : ty_()
-            let unary_type = self.primitive_type_ix("()");
-            self.graph.add_inference(self.fn_ret_type, unary_type,
-                InferenceSource::ExplicitReturn);
+        else if !self.fn_returns_unit {
+            debug!("Emitting error: bare return in fn with a return value");
+            self.errors.add_error(CheckerError::new(
+                vec![return_.span()],
+                "Missing return value: `return` needs a value \
+                 in a function which does not return ()"
+            ));
         }
     }
 }
@@ -283,8 +366,11 @@ impl<'err, 'builder, 'graph> ExpressionVisitor
             debug!("Skipping unidentified var {}", ident.name());
             return
         }
-        self.current_type = self.graph.variable(&ident.id())
-            .expect("Graph did not contain identified variable");
+        self.current_type = match self.graph.variable(&ident.id()) {
+            Some(var_ix) => var_ix,
+            None => self.add_checked_fn(ident)
+                .expect("Graph did not contain identified variable")
+        };
     }
 
     fn visit_if_expr(&mut self, if_expr: &IfExpression) {
@@ -317,8 +403,6 @@ impl<'err, 'builder, 'graph> ExpressionVisitor
         // I _think_ it's better to have the syntax also imply a balanced, valued
         // if expression.
 
-        let if_expr_ty = self.graph.add_expression();
-
         self.visit_expression(if_expr.condition());
         let bool_ty_ix = self.primitive_type_ix("bool");
 
@@ -334,15 +418,30 @@ impl<'err, 'builder, 'graph> ExpressionVisitor
         // We do not point them at each other here to avoid a loop.
         // I don't think inference can handle this right now.
 
-        // ty_if_cond = ty_if_else
-        self.graph.add_inference(right_ty_id, left_ty_id,
+        if if_expr.id().is_default() {
+            debug!("Skipping unidentified if expression");
+            // ty_if_cond = ty_if_else
+            self.graph.add_inference(right_ty_id, left_ty_id,
+                InferenceSource::IfBranchesSame);
+            self.current_type = left_ty_id;
+            return
+        }
+        // The branches get their own IDs so a mismatch can say which
+        // branch has which type.
+        let (true_id, else_id) = if_expr.branch_ids();
+        let true_ty = self.graph.add_variable(true_id);
+        self.graph.add_inference(true_ty, left_ty_id,
+            InferenceSource::IfBranchesSame);
+        let else_ty = self.graph.add_variable(else_id);
+        self.graph.add_inference(else_ty, right_ty_id,
             InferenceSource::IfBranchesSame);
 
+        let if_expr_ty = self.graph.add_variable(if_expr.id());
         // ty_if_expr: ty_if_cond
         self.graph.add_inference(if_expr_ty, left_ty_id,
             InferenceSource::IfBranchesSame);
         // ty_if_expr: ty_if_else
-        self.graph.add_inference(if_expr_ty, left_ty_id,
+        self.graph.add_inference(if_expr_ty, right_ty_id,
             InferenceSource::IfBranchesSame);
 
         self.current_type = if_expr_ty;
@@ -350,17 +449,20 @@ impl<'err, 'builder, 'graph> ExpressionVisitor
 
     fn visit_unary_op(&mut self, unary_op: &UnaryOperation) {
         let float_type = self.primitive_type_ix("float");
-        // Require a numeric value for `-expr`
+        // Require a numeric value for `-expr` and `+expr`
+        self.visit_expression(unary_op.inner());
+        // t_expr = tint
+        self.graph.add_inference(self.current_type, float_type,
+            InferenceSource::NumericOperator);
         match unary_op.operator() {
-            UnaryOperator::Negation | UnaryOperator::Addition => {
-                self.visit_expression(unary_op.inner());
-                // t_expr = tint
-                self.graph.add_inference(self.current_type, float_type,
-                    InferenceSource::NumericOperator);
+            UnaryOperator::Negation => {
                 let unary_op_expr_ty = self.graph.add_expression();
                 self.graph.add_inference(unary_op_expr_ty, float_type,
                     InferenceSource::NumericOperator);
+                self.current_type = unary_op_expr_ty;
             },
+            // `+expr` is `expr`, so it keeps the inner expression's type.
+            UnaryOperator::Addition => {}
         }
     }
 
@@ -402,7 +504,8 @@ impl<'err, 'builder, 'graph> ExpressionVisitor
                 self.graph.add_inference(binop_type, bool_type,
                     InferenceSource::BooleanOperator);
             },
-            Addition | Subtraction | Multiplication | Division | Modulus => {
+            Addition | Subtraction | Multiplication | Division | Modulus
+            | Power => {
                 // lhs and rhs are numeric, result is numeric
                 let float_type = self.primitive_type_ix("float");
                 // lhs = number
@@ -448,6 +551,7 @@ impl<'err, 'builder, 'graph> ExpressionVisitor
             match *literal.value() {
                 LiteralValue::Bool(_) => self.primitive_type_ix("bool"),
                 LiteralValue::Float(_) => self.primitive_type_ix("float"),
+                LiteralValue::Char(_) => self.primitive_type_ix("char"),
                 LiteralValue::Unit => self.primitive_type_ix("()")
             };
         let expr_ty = self.graph.add_expression();
@@ -471,8 +575,11 @@ impl<'err, 'builder, 'graph> ExpressionVisitor
 
         // Attempt to find the function, either through top-level declaration
         // or through local binding.
-        let fn_ix = self.graph.get_type(&fn_id)
+        let mut fn_ix = self.graph.get_type(&fn_id)
             .or_else(|| self.graph.variable(&fn_id));
+        if fn_ix.is_none() {
+            fn_ix = self.add_checked_fn(fn_call.ident());
+        }
 
         if fn_ix.is_none() {
             debug!("Could not find type of function {}", fn_call.text());
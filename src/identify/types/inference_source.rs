@@ -14,6 +14,8 @@ pub enum InferenceSource {
     FnReturnType(Identifier),
     /// Inference source is the parameter of a function.
     FnParameter(Identifier),
+    /// Inference source is the default value of a function parameter.
+    ParamDefault(Identifier),
     /// Inference source is a typedef alias.
     Typedef(Identifier),
     /// Inference source is the call argument of a function.
@@ -30,6 +32,8 @@ pub enum InferenceSource {
     IfConditionalBool,
     /// Inference source is the if branches being the same.
     IfBranchesSame,
+    /// Inference source is the condition of an assert being a bool.
+    AssertConditionBool,
     /// Inference source is a `return` matching the fn return type.
     ExplicitReturn,
     /// Inference source is an implicit return matching a block.
@@ -60,6 +64,9 @@ impl fmt::Debug for InferenceSource {
             FnParameter(ref id) => f.debug_tuple("FnParam")
                                  .field(&id.name())
                                  .finish(),
+            ParamDefault(ref id) => f.debug_tuple("ParamDefault")
+                                  .field(&id.name())
+                                  .finish(),
             Typedef(ref id) => f.debug_tuple("Typedef")
                                 .field(&id.name())
                                 .finish(),
@@ -80,6 +87,7 @@ impl fmt::Debug for InferenceSource {
                                    .finish(),
             IfConditionalBool => f.write_str("IfCond"),
             IfBranchesSame => f.write_str("IfBranchEq"),
+            AssertConditionBool => f.write_str("AssertCond"),
             ExplicitReturn => f.write_str("ReturnStmt"),
             ImplicitReturn => f.write_str("ReturnExpr"),
             Assignment => f.write_str("Assign"),
@@ -19,53 +19,46 @@ impl<'err, 'builder> ItemTypeIdentifier<'err, 'builder> {
                -> ItemTypeIdentifier<'err, 'builder> {
         ItemTypeIdentifier { errors, builder }
     }
-}
 
-impl<'err, 'builder> UnitVisitor for ItemTypeIdentifier<'err, 'builder> {
-    fn visit_unit(&mut self, unit: &Unit) {
-        trace!("Visting a unit");
-        visit::walk_unit(self, unit);
-    }
-}
-
-impl<'err, 'builder> ItemVisitor for ItemTypeIdentifier<'err, 'builder> {
-    fn visit_block_fn_decl(&mut self, fn_decl: &BlockFnDeclaration) {
-        trace!("Visiting block fn {}", fn_decl.name());
-        if fn_decl.id().is_default() {
-            debug!("Skipping fn {} with default ID", fn_decl.name());
+    /// Identifies the concrete type of a function from its signature.
+    fn identify_fn_type(&mut self,
+                        ident: &Identifier,
+                        params: &[(Identifier, TypeExpression)],
+                        return_ty: &TypeExpression) {
+        if ident.id().is_default() {
+            debug!("Skipping fn {} with default ID", ident.name());
             return
         }
 
         // Declared functions' types are handled here because we do not want
         // to run full type inference at the item level.
-        let mut arg_types = Vec::with_capacity(fn_decl.params().len());
+        let mut arg_types = Vec::with_capacity(params.len());
 
-        for &(ref param_ident, ref param_ty_expr) in fn_decl.params() {
+        for &(ref param_ident, ref param_ty_expr) in params {
             trace!("Calling TypeIdentifier for {} param {}",
-                fn_decl.name(), param_ident.name());
+                ident.name(), param_ident.name());
             TypeIdentifier::new(self.errors, self.builder)
                            .visit_type_expr(param_ty_expr);
             // Stop if we can't idenify a parameter type.
             if param_ty_expr.id().is_default() {
                 debug!("Unable to identify type of {} param {}",
-                    fn_decl.name(), param_ident.name());
+                    ident.name(), param_ident.name());
                 return
             }
             let param_ty = self.builder.get_type(&param_ty_expr.id())
                 .expect("TypeIdentifier did not update param's type ID");
             trace!("{} param {} has type id {:?}",
-                fn_decl.name(), param_ident.name(), param_ty);
+                ident.name(), param_ident.name(), param_ty);
             arg_types.push((param_ident.name().to_string(),
                             param_ty.clone()));
         }
-        let return_ty = fn_decl.return_type();
         trace!("Calling TypeIdentifier for {} return type {:?}",
-            fn_decl.name(), return_ty);
+            ident.name(), return_ty);
         TypeIdentifier::new(self.errors, self.builder)
                        .visit_type_expr(return_ty);
 
         if return_ty.id().is_default() {
-            debug!("Unable to identify return type of {}", fn_decl.name());
+            debug!("Unable to identify return type of {}", ident.name());
             return
         }
         let ret_ty = self.builder.get_type(&return_ty.id())
@@ -74,8 +67,29 @@ impl<'err, 'builder> ItemVisitor for ItemTypeIdentifier<'err, 'builder> {
 
         let fn_concrete = ConcreteType::Function(
             FnType::new(arg_types, ret_ty));
-        trace!("fn {} has concrete type {:?}", fn_decl.name(), fn_concrete);
-        self.builder.add_type(fn_decl.id().clone(), fn_concrete);
+        trace!("fn {} has concrete type {:?}", ident.name(), fn_concrete);
+        self.builder.add_type(ident.id().clone(), fn_concrete);
+    }
+}
+
+impl<'err, 'builder> UnitVisitor for ItemTypeIdentifier<'err, 'builder> {
+    fn visit_unit(&mut self, unit: &Unit) {
+        trace!("Visting a unit");
+        visit::walk_unit(self, unit);
+    }
+}
+
+impl<'err, 'builder> ItemVisitor for ItemTypeIdentifier<'err, 'builder> {
+    fn visit_block_fn_decl(&mut self, fn_decl: &BlockFnDeclaration) {
+        trace!("Visiting block fn {}", fn_decl.name());
+        self.identify_fn_type(fn_decl.ident(), fn_decl.params(),
+                              fn_decl.return_type());
+    }
+
+    fn visit_extern_fn_decl(&mut self, extern_fn: &ExternFnDeclaration) {
+        trace!("Visiting extern fn {}", extern_fn.name());
+        self.identify_fn_type(extern_fn.ident(), extern_fn.params(),
+                              extern_fn.return_type());
     }
 
     fn visit_typedef(&mut self, typedef: &Typedef) {
@@ -93,7 +107,7 @@ impl<'err, 'builder> ItemVisitor for ItemTypeIdentifier<'err, 'builder> {
             debug!("Unable to identify type of typedef {}", typedef.name());
             return
         }
-        else if *type_expr_id == *typedef.id() {
+        else if type_expr_id == typedef.id() {
             debug!("Found circular definition of typedef {}", typedef.name());
             // Won't catch indirection:
             // typedef Foo = Bar
@@ -5,9 +5,9 @@ use identify::types::InferenceSource;
 
 use petgraph::Directed;
 use petgraph::graph::{Graph, NodeIndex, EdgeIndex};
-use petgraph::visit::Dfs;
+use petgraph::visit::{Dfs, EdgeRef};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::path::Path;
 
 /// Represents a node in the type inference graph, or
@@ -57,6 +57,7 @@ pub const PRIMITIVE_TYPE_NAMES: &[&'static str] = &[
     "()",
     "bool",
     "float",
+    "char",
 ];
 
 impl TypeGraph {
@@ -185,13 +186,63 @@ impl TypeGraph {
         }
     }
 
+    /// Explains why a variable has its type: the sources of the inferences
+    /// leading from the variable to a concrete type.
+    ///
+    /// The shortest chain of inferences is given, starting at the variable.
+    /// `Inferred` edges, which are shortcuts added once a type is known,
+    /// aren't followed. Unknown variables and variables which don't lead to
+    /// a concrete type give an empty chain.
+    pub fn explain(&self, var: &ScopedId) -> Vec<InferenceSource> {
+        let var_ix = match self.variables.get(var) {
+            Some(var_ix) => *var_ix,
+            None => return Vec::new()
+        };
+        // Breadth-first search, remembering the edge each node was found by
+        let mut found_by = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(var_ix);
+        while let Some(next_ix) = queue.pop_front() {
+            if let TypeNode::ConcreteType(_) = self.graph[next_ix] {
+                let mut sources = Vec::new();
+                let mut current_ix = next_ix;
+                while let Some(&edge_ix) = found_by.get(&current_ix) {
+                    let (source_ix, _) = self.graph.edge_endpoints(edge_ix)
+                        .expect("Edge was found in the graph");
+                    sources.push(self.graph[edge_ix].clone());
+                    current_ix = source_ix;
+                }
+                sources.reverse();
+                return sources
+            }
+            for edge in self.graph.edges(next_ix) {
+                if *edge.weight() == InferenceSource::Inferred
+                    || edge.target() == var_ix
+                    || found_by.contains_key(&edge.target()) {
+                    continue
+                }
+                found_by.insert(edge.target(), edge.id());
+                queue.push_back(edge.target());
+            }
+        }
+        Vec::new()
+    }
+
+    /// Render the graph in Graphviz DOT format.
+    ///
+    /// Nodes are labeled with the variable, type, or expression they
+    /// represent, and edges with their `InferenceSource`.
+    pub fn to_dot(&self) -> String {
+        use petgraph::dot::Dot;
+        format!("{:?}", Dot::with_config(&self.graph, &[]))
+    }
+
     /// Call `dot -Tsvg` on the given file
     pub fn write_svg<P: AsRef<Path>>(&self, path: P) {
         use std::io::Write;
         use std::process::{Command, Stdio};
         use std::fs::OpenOptions;
-        use petgraph::dot::Dot;
-        let dot = Dot::with_config(&self.graph, &[]);
+        let dot = self.to_dot();
 
         let mut dot_cmd = Command::new("dot")
                                   .arg("-Tsvg")
@@ -203,7 +254,7 @@ impl TypeGraph {
         { // Lock stdin
             let mut stdin = dot_cmd.stdin.as_mut()
                 .expect("Couldn't get an stdin");
-            write!(&mut stdin, "{:?}", dot).expect("Could not write graph");
+            write!(&mut stdin, "{}", dot).expect("Could not write graph");
         }
 
         let output = dot_cmd.wait_with_output()
@@ -219,3 +270,64 @@ impl TypeGraph {
             .expect("Could not write file for svg");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ast::{Item, ScopedId, Statement};
+    use identify::types::InferenceSource;
+    use pipeline::Runner;
+    use super::{TypeGraph, PRIMITIVE_TYPE_NAMES};
+
+    #[test]
+    fn dot_export_has_nodes_and_labeled_edges() {
+        let mut graph = TypeGraph::with_primitives();
+        // let x = <float>
+        let float_ix = graph.get_type(
+            &ScopedId::default().incremented().incremented().incremented())
+            .expect("Primitive float type");
+        let var_ix = graph.add_variable(
+            ScopedId::default().incremented().pushed());
+        let expr_ix = graph.add_expression();
+        graph.add_inference(expr_ix, float_ix, InferenceSource::NumericOperator);
+        graph.add_inference(var_ix, expr_ix, InferenceSource::Assignment);
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph {"), "Not a digraph: {}", dot);
+        let edges = dot.lines().filter(|line| line.contains(" -> ")).count();
+        let nodes = dot.lines()
+            .filter(|line| line.contains("label") && !line.contains(" -> "))
+            .count();
+        assert_eq!(nodes, PRIMITIVE_TYPE_NAMES.len() + 2);
+        assert_eq!(edges, 2);
+        assert!(dot.contains("NumOp") && dot.contains("Assign"),
+            "Edges not labeled with sources: {}", dot);
+    }
+
+    #[test]
+    fn explain_follows_inferences_to_a_concrete_type() {
+        let source = "fn f(x: float) -> float\n\
+                      \x20   let y = x\n\
+                      \x20   y\n";
+        let runner = Runner::from_string(source, "test".into())
+            .parse().expect("Could not parse test source")
+            .identify().expect("Could not identify test source");
+        let block_fn = match runner.unit().items()[0] {
+            Item::BlockFnDeclaration(ref block_fn) => block_fn,
+            ref other => panic!("Expected a fn declaration, got {:?}", other)
+        };
+        let param_id = block_fn.params()[0].0.id();
+        let decl_id = match block_fn.block().stmts()[0] {
+            Statement::Declaration(ref decl) => decl.ident().id(),
+            ref other => panic!("Expected a declaration, got {:?}", other)
+        };
+        let graph = runner.type_graph();
+
+        assert_eq!(format!("{:?}", graph.explain(&param_id)),
+                   "[FnParam(\"x\")]");
+        assert_eq!(format!("{:?}", graph.explain(&block_fn.ident().id())),
+                   "[IsTheFn(\"f\")]");
+        assert_eq!(format!("{:?}", graph.explain(&decl_id)),
+                   "[Let(\"y\"), FnParam(\"x\")]");
+        assert!(graph.explain(&ScopedId::default()).is_empty());
+    }
+}
@@ -0,0 +1,88 @@
+//! Index of the lines in source text, used to render diagnostics.
+
+use lex::Location;
+
+/// Maps positions in a source string to the lines they appear on.
+///
+/// The index is built once from the source, so looking up the line of each
+/// diagnostic is a binary search instead of a scan of the whole text.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'src> {
+    source: &'src str,
+    /// The char index (as used by `Location::index`) and byte offset of the
+    /// start of each line.
+    line_starts: Vec<(u32, usize)>
+}
+
+impl<'src> LineIndex<'src> {
+    /// Builds the line index of the given source.
+    pub fn new(source: &'src str) -> LineIndex<'src> {
+        let mut line_starts = vec![(0, 0)];
+        for (char_ix, (byte_ix, ch)) in source.char_indices().enumerate() {
+            if ch == '\n' {
+                line_starts.push((char_ix as u32 + 1, byte_ix + 1));
+            }
+        }
+        LineIndex { source, line_starts }
+    }
+
+    /// The source text which was indexed.
+    pub fn source(&self) -> &'src str {
+        self.source
+    }
+
+    /// Number of lines in the source.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Gets the (0-indexed) line containing the given char index.
+    pub fn line_of(&self, index: u32) -> u32 {
+        match self.line_starts.binary_search_by_key(&index, |&(start, _)| start) {
+            Ok(line) => line as u32,
+            Err(next_line) => next_line as u32 - 1
+        }
+    }
+
+    /// Gets the text of the given (0-indexed) line, without its line ending.
+    pub fn line_text(&self, line: u32) -> Option<&'src str> {
+        let line = line as usize;
+        let start = match self.line_starts.get(line) {
+            Some(&(_, start)) => start,
+            None => return None
+        };
+        let end = self.line_starts.get(line + 1)
+            .map(|&(_, next_start)| next_start - 1)
+            .unwrap_or(self.source.len());
+        let text = &self.source[start .. end];
+        Some(text.trim_end_matches('\r'))
+    }
+
+    /// Gets the text of the line the given location is on.
+    pub fn location_line_text(&self, location: &Location) -> Option<&'src str> {
+        self.line_text(self.line_of(location.index()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineIndex;
+    use lex::Location;
+
+    #[test]
+    fn it_finds_line_text_of_offsets() {
+        let source = "fn foo()\n    bar\r\n\nbaz ünicode\nend";
+        let index = LineIndex::new(source);
+        assert_eq!(index.line_count(), 5);
+
+        let expected = [(0, "fn foo()"), (8, "fn foo()"), (9, "    bar"),
+                        (13, "    bar"), (18, ""), (19, "baz ünicode"),
+                        (24, "baz ünicode"), (31, "end"), (33, "end")];
+        for &(offset, line_text) in &expected {
+            let location = Location::of().index(offset).build();
+            assert_eq!(index.location_line_text(&location), Some(line_text),
+                "Wrong line text at offset {}", offset);
+        }
+        assert_eq!(index.line_text(5), None);
+    }
+}
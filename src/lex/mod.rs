@@ -4,13 +4,17 @@ mod span;
 mod token;
 pub mod tokens;
 mod textiter;
+mod line_index;
 pub mod tokenizer;
+mod str_tokenizer;
 
 pub use self::span::{Location, Span};
 pub use self::token::{Token, TokenData};
 pub use self::tokens::TokenType;
 pub use self::textiter::{TextIter, PeekTextIter};
+pub use self::line_index::LineIndex;
 pub use self::tokenizer::{Tokenizer, IterTokenizer};
+pub use self::str_tokenizer::{StrTokenizer, SourceToken};
 
 /// Type representing a borrowed or owned string
 pub type CowStr = ::std::borrow::Cow<'static, str>;
@@ -0,0 +1,144 @@
+//! Tokenizer whose tokens borrow their text from the source.
+
+use std::str::Chars;
+
+use lex::{Location, Span, Token, TokenData, IterTokenizer};
+
+/// A token whose text is a slice of the source it was read from.
+///
+/// Unlike `Token`, reading one doesn't allocate its text. `to_token` gives
+/// an owned `Token` for where a `'static` one is needed, such as the parser.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SourceToken<'src> {
+    start: Location,
+    text: &'src str,
+    data: TokenData,
+    indent_column: Option<u32>
+}
+
+impl<'src> SourceToken<'src> {
+    /// Gets the source text of this token.
+    pub fn text(&self) -> &'src str {
+        self.text
+    }
+
+    /// The data associated with this token
+    pub fn data(&self) -> TokenData {
+        self.data
+    }
+
+    /// The column of the indentation a `BeginBlock` starts, or which an
+    /// `EndBlock` returns to, if the tokenizer was reporting them.
+    pub fn indent_column(&self) -> Option<u32> {
+        self.indent_column
+    }
+
+    /// The location of this token where it starts in its source text
+    pub fn start(&self) -> Location {
+        self.start
+    }
+
+    /// Get the span of this token including its source text
+    pub fn span(&self) -> Span {
+        Span::from(self.start ..=
+                   self.start.offset(self.text.chars().count() as u32))
+    }
+
+    /// Creates an owned `Token` with a copy of this token's text.
+    pub fn to_token(&self) -> Token {
+        let token = Token::new(self.text.to_string(), self.start, self.data);
+        match self.indent_column {
+            Some(column) => token.with_indent_column(column),
+            None => token
+        }
+    }
+}
+
+/// Tokenizes a `str`, giving `SourceToken`s which borrow their text from it.
+///
+/// This wraps an `IterTokenizer` which elides identifiers' text, so
+/// identifiers are read without allocating. Other tokens are read as usual.
+#[derive(Debug)]
+pub struct StrTokenizer<'src> {
+    source: &'src str,
+    tokens: IterTokenizer<Chars<'src>>,
+    /// Char index and matching byte index of the last token end.
+    cursor: (u32, usize)
+}
+
+impl<'src> StrTokenizer<'src> {
+    pub fn new(source: &'src str) -> StrTokenizer<'src> {
+        StrTokenizer {
+            source,
+            tokens: IterTokenizer::new(source.chars())
+                .with_elided_ident_text(),
+            cursor: (0, 0)
+        }
+    }
+
+    /// Gives `BeginBlock` and `EndBlock` tokens the column of the
+    /// indentation they start or return to.
+    pub fn with_indent_columns(mut self) -> StrTokenizer<'src> {
+        self.tokens = self.tokens.with_indent_columns();
+        self
+    }
+
+    /// Gets the next token from the tokenizer
+    pub fn next(&mut self) -> SourceToken<'src> {
+        let token = self.tokens.next();
+        let text = match token.data() {
+            TokenData::BeginBlock | TokenData::EndBlock | TokenData::EOF => "",
+            _ => {
+                // The tokenizer stops right after the token's text.
+                let start = self.byte_index(token.start().index());
+                let end = self.byte_index(self.tokens.location().index());
+                &self.source[start..end]
+            }
+        };
+        SourceToken {
+            start: token.start(),
+            text,
+            data: token.data(),
+            indent_column: token.indent_column()
+        }
+    }
+
+    /// Gets the byte index of a char index, which must not be before the
+    /// end of the previous token.
+    fn byte_index(&mut self, char_index: u32) -> usize {
+        let (cursor_char, cursor_byte) = self.cursor;
+        debug_assert!(char_index >= cursor_char,
+                      "Token at char {} is before the previous token's end {}",
+                      char_index, cursor_char);
+        let byte_index = cursor_byte + self.source[cursor_byte..].chars()
+            .take((char_index - cursor_char) as usize)
+            .map(char::len_utf8)
+            .sum::<usize>();
+        self.cursor = (char_index, byte_index);
+        byte_index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lex::{IterTokenizer, TokenData};
+    use super::StrTokenizer;
+
+    #[test]
+    fn source_tokens_match_owned_tokens() {
+        let source = "fn naïve(x: float) -> bool\n\
+                      \x20   let mut ŷ = x * .5\n\
+                      \x20   ŷ = ŷ + 1 // comment\n\
+                      \x20   ŷ >= 2 and true\n";
+        let mut owned = IterTokenizer::new(source.chars());
+        let mut borrowed = StrTokenizer::new(source);
+        loop {
+            let expected = owned.next();
+            let token = borrowed.next();
+            assert_eq!(token.to_token(), expected);
+            if expected.data() == TokenData::EOF {
+                break
+            }
+        }
+    }
+}
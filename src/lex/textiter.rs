@@ -22,7 +22,9 @@ pub struct PeekTextIter<T> where T: Iterator<Item=char> {
     /// Current column in the source
     current_column: u32,
     /// Current byte in the source
-    current_char: u32
+    current_char: u32,
+    /// Characters consumed since the last `take_recorded`, if recording.
+    recorded: Option<String>
 }
 impl<T: Iterator<Item=char>> PeekTextIter<T> {
     pub fn new(iter: Peekable<T>) -> PeekTextIter<T> {
@@ -30,9 +32,31 @@ impl<T: Iterator<Item=char>> PeekTextIter<T> {
             iter: iter,
             current_line: 0,
             current_column: 0,
-            current_char: 0
+            current_char: 0,
+            recorded: None
         }
     }
+
+    /// Starts keeping a copy of each consumed character.
+    pub fn record(&mut self) {
+        if self.recorded.is_none() {
+            self.recorded = Some(String::new());
+        }
+    }
+
+    /// Whether consumed characters are being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recorded.is_some()
+    }
+
+    /// Takes the characters recorded since this was last called.
+    ///
+    /// Empty if the iterator isn't recording.
+    pub fn take_recorded(&mut self) -> String {
+        self.recorded.as_mut()
+            .map(|recorded| recorded.split_off(0))
+            .unwrap_or_default()
+    }
 }
 
 impl<T: Iterator<Item=char>> TextIter for PeekTextIter<T> {
@@ -65,6 +89,9 @@ impl<T: Iterator<Item=char>> Iterator for PeekTextIter<T> {
             },
             None => {}
         }
+        if let (Some(ref mut recorded), Some(ch)) = (self.recorded.as_mut(), result) {
+            recorded.push(ch);
+        }
         trace!("> Next char {:?}", result);
         result
     }
@@ -19,7 +19,16 @@ pub struct Token {
     /// Text of the token at that location
     text: CowStr,
     /// Additional data (type/literal) provided by the lexer
-    data: TokenData
+    data: TokenData,
+    /// Whitespace and comments between the previous token and this one.
+    ///
+    /// Only kept by tokenizers which were asked to preserve trivia, and
+    /// boxed so tokens without trivia stay small.
+    leading_trivia: Option<Box<str>>,
+    /// The indentation column of a `BeginBlock` or `EndBlock`.
+    ///
+    /// Only kept by tokenizers which were asked to report it.
+    indent_column: Option<u32>
 }
 
 impl Token {
@@ -28,11 +37,49 @@ impl Token {
         &self.text
     }
 
+    /// Gets an owned copy of the original source text of this token.
+    pub fn text_owned(&self) -> String {
+        self.text.to_string()
+    }
+
     /// The data associated with this token
     pub fn data(&self) -> TokenData {
         self.data
     }
 
+    /// Whitespace and comments in the source before this token.
+    ///
+    /// This is empty unless the tokenizer was preserving trivia.
+    pub fn leading_trivia(&self) -> &str {
+        self.leading_trivia.as_ref().map_or("", |trivia| trivia)
+    }
+
+    /// Sets the leading trivia of this token.
+    pub fn with_leading_trivia<T: Into<String>>(mut self, trivia: T) -> Token {
+        let trivia = trivia.into();
+        self.leading_trivia = if trivia.is_empty() {
+            None
+        }
+        else {
+            Some(trivia.into_boxed_str())
+        };
+        self
+    }
+
+    /// The column of the indentation a `BeginBlock` starts, or which an
+    /// `EndBlock` returns to.
+    ///
+    /// This is `None` unless the tokenizer was reporting indent columns.
+    pub fn indent_column(&self) -> Option<u32> {
+        self.indent_column
+    }
+
+    /// Sets the indent column of this token.
+    pub fn with_indent_column(mut self, column: u32) -> Token {
+        self.indent_column = Some(column);
+        self
+    }
+
     /// The location of this token where it starts in its source text
     pub fn start(&self) -> Location {
         self.start
@@ -52,7 +99,13 @@ impl Token {
     pub fn new<T: Into<CowStr>>(text: T,
                                 start: Location,
                                 data: TokenData) -> Token {
-        Token { text: text.into(), start, data }
+        Token {
+            text: text.into(),
+            start,
+            data,
+            leading_trivia: None,
+            indent_column: None
+        }
     }
 
     /// Creates a new token representing an identifier
@@ -60,7 +113,9 @@ impl Token {
         Token {
             text: text.into(),
             data: TokenData::Ident,
-            start
+            start,
+            leading_trivia: None,
+            indent_column: None
         }
     }
 
@@ -69,7 +124,9 @@ impl Token {
         Token {
             text: Cow::Borrowed(""),
             data: TokenData::BeginBlock,
-            start
+            start,
+            leading_trivia: None,
+            indent_column: None
         }
     }
 
@@ -78,7 +135,9 @@ impl Token {
         Token {
             text: Cow::Borrowed(""),
             data: TokenData::EndBlock,
-            start
+            start,
+            leading_trivia: None,
+            indent_column: None
         }
     }
 
@@ -87,7 +146,9 @@ impl Token {
         Token {
             text: Cow::Borrowed(""),
             data: TokenData::EOF,
-            start
+            start,
+            leading_trivia: None,
+            indent_column: None
         }
     }
 }
@@ -101,12 +162,20 @@ impl Display for Token {
 /// Token enum - tokens are pretty simple, mostly dependent on string matching.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TokenData {
-    /// Token is a numeric literal
-    NumberLiteral,
+    /// Token is a numeric literal without a decimal point or exponent,
+    /// such as `5`
+    IntLiteral,
+    /// Token is a numeric literal with a decimal point or exponent, such as
+    /// `5.0` or `1e3`
+    FloatLiteral,
     /// Token is unit type literal `()`
     UnitLiteral,
     /// Token is boolean literal `true` or `false`
     BoolLiteral,
+    /// Token is a character literal such as `'a'` or `'\n'`.
+    ///
+    /// The text includes the quotes and is unescaped by the parser.
+    CharLiteral,
     /// Token is some name
     Ident,
     /// Token is a keyword
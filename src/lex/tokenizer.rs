@@ -4,6 +4,7 @@
 
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::mem;
 
 use unicode_categories::UnicodeCategories;
 
@@ -17,13 +18,21 @@ pub trait Tokenizer {
     fn next(&mut self) -> Token;
 }
 
+/// Initial capacity of the word buffer when identifier text is elided.
+///
+/// Longer words grow the buffer once.
+const WORD_BUFFER_CAPACITY: usize = 64;
+
 /// If the given char is a symbol.
 pub fn char_is_symbol(ch: char) -> bool {
     ch == '%' || ch == '/' ||
     ch == '(' || ch == ')' ||
+    ch == '{' || ch == '}' ||
+    ch == '[' || ch == ']' ||
     ch == '-' || ch == '*' ||
     ch == ',' || ch == ':' ||
-    ch == '!' ||
+    ch == '!' || ch == ';' ||
+    ch == '.' ||
     ch.is_symbol()
 }
 
@@ -65,6 +74,12 @@ pub struct IterTokenizer<I> where I: Iterator<Item=char> {
     tokenizer_state: TokenizerState,
     /// Stack of indents being made.
     indent_size_stack: Vec<u32>,
+    /// Whether indentation tokens are given their indent column.
+    indent_columns: bool,
+    /// Whether identifiers are given empty text, so they aren't allocated.
+    elides_ident_text: bool,
+    /// Reused buffer for reading words when identifier text is elided.
+    word_buffer: String,
     /// Peekable iterator over the characters
     iter: PeekTextIter<I>
 }
@@ -80,9 +95,21 @@ impl<I: Iterator<Item=char>> Tokenizer for IterTokenizer<I> {
 impl<I: Iterator<Item=char>> IterTokenizer<I> {
     /// Creates a new StaticStrTokenizer from the given string
     pub fn new(input: I) -> IterTokenizer<I> {
+        IterTokenizer::with_symbols(input, tokens::default_symbols())
+    }
+
+    /// Creates a new tokenizer which recognizes the given table of symbols
+    /// instead of `tokens::default_symbols()`.
+    ///
+    /// Symbols must begin with a character accepted by `char_is_symbol`.
+    /// Every prefix of a multi-char symbol must also be in the table, as
+    /// `Partial` or `CompletePrefix`, so the tokenizer can build up to it.
+    pub fn with_symbols(input: I,
+                        symbols: HashMap<CowStr, TokenizerSymbolRule>)
+                        -> IterTokenizer<I> {
         IterTokenizer {
             keywords: tokens::default_keywords(),
-            symbols: tokens::default_symbols(),
+            symbols,
 
             // Will be overridden later when reading file
             // If first line beins with a space, error
@@ -91,13 +118,81 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
             // This will discard spacing at the beginning of a file
             tokenizer_state: TokenizerState::LookingForNewline,
             indent_size_stack: vec![0u32],
+            indent_columns: false,
+            elides_ident_text: false,
+            word_buffer: String::new(),
 
             iter: PeekTextIter::new(input.peekable())
         }
     }
 
+    /// Keeps whitespace and comments as the leading trivia of each token.
+    ///
+    /// The trivia of the final `EOF` token holds anything after the last
+    /// real token, so joining each token's trivia and text (up to and
+    /// including `EOF`) gives back the exact source. The parser ignores
+    /// trivia.
+    pub fn with_trivia(mut self) -> IterTokenizer<I> {
+        self.iter.record();
+        self
+    }
+
+    /// Gives `BeginBlock` and `EndBlock` tokens the column of the
+    /// indentation they start or return to, such as for a reformatter.
+    ///
+    /// The parser ignores indent columns.
+    pub fn with_indent_columns(mut self) -> IterTokenizer<I> {
+        self.indent_columns = true;
+        self
+    }
+
+    /// Gives identifier tokens empty text, and keywords and `bool` literals
+    /// their text from the keyword table, so reading a word doesn't
+    /// allocate.
+    ///
+    /// This is for tokenizers which can take the text from their source
+    /// instead, such as `StrTokenizer`. An identifier ends at the tokenizer's
+    /// `location()` after it's read.
+    pub fn with_elided_ident_text(mut self) -> IterTokenizer<I> {
+        self.elides_ident_text = true;
+        self.word_buffer = String::with_capacity(WORD_BUFFER_CAPACITY);
+        self
+    }
+
+    /// The location of the next character to be read.
+    pub fn location(&self) -> Location {
+        self.iter.location()
+    }
+
     /// Gets the next token from the tokenizer
     pub fn next(&mut self) -> Token {
+        let mut token = self.next_token();
+        if self.indent_columns {
+            match token.data() {
+                TokenData::BeginBlock | TokenData::EndBlock => {
+                    // The indent stack has been pushed or popped to the
+                    // indentation after the token.
+                    let column = *self.indent_size_stack.last()
+                        .expect("Indent stack was missing leading 0");
+                    token = token.with_indent_column(column);
+                },
+                _ => {}
+            }
+        }
+        if !self.iter.is_recording() {
+            return token
+        }
+        // Everything consumed up to the token's text is trivia.
+        let mut trivia = self.iter.take_recorded();
+        debug_assert!(trivia.ends_with(token.text()),
+            "Consumed {:?} for token {:?}", trivia, token);
+        let trivia_len = trivia.len() - token.text().len();
+        trivia.truncate(trivia_len);
+        token.with_leading_trivia(trivia)
+    }
+
+    /// Gets the next token, leaving its trivia in the iterator's recording.
+    fn next_token(&mut self) -> Token {
         trace!(">Calling next on {:?}, peeked {:?}",
             self.tokenizer_state, self.iter.peek());
         match self.tokenizer_state {
@@ -128,26 +223,32 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
 
     /// Get the next `BlockBegin` token(s)
     fn next_indent(&mut self) -> Token {
-        let peek_attempt = self.iter.peek();
-        if peek_attempt.is_none() {
-            self.tokenizer_state = TokenizerState::ReachedEOF;
-            return self.next_eof()
-        }
+        // Take all consecutive spaces. Lines with only whitespace don't
+        // change the indentation, so they're skipped here rather than by
+        // going back through `next_line`, which would recurse once per
+        // blank line.
         let mut space_count = 0;
-        let mut peeked = peek_attempt.expect("Checked expect");
-        // Take all consecutive spaces
-        trace!("Taking consecutive spaces starting with {:?}", peeked);
-        while char_is_spacing(peeked) {
-            self.iter.next();
-            space_count += 1;
-            let next_peek = self.iter.peek();
-            if next_peek.is_none() {
-                break
+        loop {
+            match self.iter.peek() {
+                None => {
+                    self.tokenizer_state = TokenizerState::ReachedEOF;
+                    return self.next_eof()
+                },
+                Some('\n') | Some('\r') => {
+                    trace!("Skipping whitespace-only line");
+                    self.consume_line_ending();
+                    space_count = 0;
+                },
+                Some(peeked) if char_is_spacing(peeked) => {
+                    self.iter.next();
+                    space_count += 1;
+                    // TODO error on mixed tabs/spaces
+                },
+                Some(_) => break
             }
-            peeked = next_peek.expect("checked expect");
-            // TODO error on mixed tabs/spaces
         }
-        trace!("Peeked to {}, with {} spaces", peeked, space_count);
+        trace!("Peeked to {:?}, with {} spaces",
+            self.iter.peek(), space_count);
         // Now that indents are found, go back to regular tokens.
         self.tokenizer_state = TokenizerState::LookingForNewline;
 
@@ -161,7 +262,7 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
         // Equal indentation: no starting block, go directly to parsing line
         if space_count == current_indent {
             trace!("Indentation is the same, calling next_line");
-            self.next_line() // Mutually recursive for empty lines
+            self.next_line()
         }
         // Greater Indendation: new block
         else if space_count > current_indent {
@@ -252,33 +353,16 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
 
         trace!("Consumed all the spacing chars");
 
-        // We handle \r first, then look at the following \n.
-        // TODO warn on mixed \r\n and \n
-        if peek == '\r' {
-            self.iter.next(); // comsume \r
-            // Give an error for \r at EOF
-            if self.iter.peek().is_none() {
-                // TODO error here
-                panic!("Hanging `\\r` at EOF, {:?}", self.iter.location());
-            }
-            // Peek for the \n
-            let expected_newline = self.iter.peek().expect("Already peeked");
-            if expected_newline != '\n' {
-                // TODO need to format i.e. `\t` -> `\\t` here...
-                panic!("Invalid control sequence `\\r{}`", expected_newline);
-            }
-            peek = expected_newline; // peeked \n here
-        }
-
-        // We either ran into it after some amount of whitespace, or found it
-        // after `\r`. Line is done, parse the indents on the next one.
-        if peek == '\n' {
-            self.iter.next(); // Original `peek` OR `peek` from the if above
+        // Line is done, parse the indents on the next one.
+        if peek == '\r' || peek == '\n' {
+            self.consume_line_ending();
             self.tokenizer_state = TokenizerState::LookingForIndent;
-            self.next_indent() // Mutually recursive for emtpy lines
+            self.next_indent()
         }
-        else if peek.is_number() {
-            self.parse_float_literal()
+        else if peek.is_number() || peek == '.' {
+            self.parse_number_literal()
+        } else if peek == '\'' {
+            self.parse_char_literal()
         } else if peek == '_' || peek.is_letter() {
             self.parse_keyword_or_ident()
         } else if char_is_symbol(peek) {
@@ -289,6 +373,23 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
         }
     }
 
+    /// Consumes the peeked `\n`, or `\r` and the `\n` after it.
+    fn consume_line_ending(&mut self) {
+        // TODO warn on mixed \r\n and \n
+        if self.iter.next() != Some('\r') {
+            return
+        }
+        match self.iter.peek() {
+            Some('\n') => {
+                self.iter.next();
+            },
+            // TODO error here
+            None => panic!("Hanging `\\r` at EOF, {:?}", self.iter.location()),
+            // TODO need to format i.e. `\t` -> `\\t` here...
+            Some(other) => panic!("Invalid control sequence `\\r{}`", other)
+        }
+    }
+
     /// Parse a symbol
     ///
     /// This logic differs from that of keyword parsing in that
@@ -312,10 +413,10 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
             if sym.starts_with("///") {
                 // doc comment - will be implemented later on
                 self.take_while(|ch| ch != '\n', &mut sym);
-                return self.next()
+                return self.next_token()
             } else if sym.starts_with("//") {
                 self.skip_while(|ch| ch != '\n');
-                return self.next()
+                return self.next_token()
             }
 
             let symbol_type = self.symbols.get(&Cow::Borrowed(&*sym)).cloned();
@@ -358,6 +459,9 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
 
     /// Parse keyword or identifier
     fn parse_keyword_or_ident(&mut self) -> Token {
+        if self.elides_ident_text {
+            return self.parse_keyword_or_elided_ident()
+        }
         let mut token_string = String::new();
         let location = self.iter.location();
         let is_kw = self.take_while_ident(&mut token_string);
@@ -367,35 +471,67 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
         else if token_string == "true" || token_string == "false" {
             Token::new(token_string, location, TokenData::BoolLiteral)
         }
+        else if token_string == "inf" || token_string == "nan" {
+            Token::new(token_string, location, TokenData::FloatLiteral)
+        }
         else {
             Token::new_ident(token_string, location)
         }
     }
 
-    /// Parse a floating point literal
-    fn parse_float_literal(&mut self) -> Token {
+    /// Parse keyword or identifier into the reused word buffer, giving
+    /// identifiers empty text.
+    fn parse_keyword_or_elided_ident(&mut self) -> Token {
+        let location = self.iter.location();
+        let mut word = mem::take(&mut self.word_buffer);
+        word.clear();
+        let is_kw = self.take_while_ident(&mut word);
+        let token = match self.keywords.get(&*word) {
+            Some(keyword) if is_kw =>
+                Token::new(keyword.clone(), location, TokenData::Keyword),
+            _ if word == "true" =>
+                Token::new("true", location, TokenData::BoolLiteral),
+            _ if word == "false" =>
+                Token::new("false", location, TokenData::BoolLiteral),
+            _ if word == "inf" =>
+                Token::new("inf", location, TokenData::FloatLiteral),
+            _ if word == "nan" =>
+                Token::new("nan", location, TokenData::FloatLiteral),
+            _ => Token::new_ident("", location)
+        };
+        self.word_buffer = word;
+        token
+    }
+
+    /// Parse a numeric literal, which is a float literal if it has a
+    /// decimal point or an exponent.
+    ///
+    /// Either side of the decimal point may be left out, as in `.5` or `5.`.
+    /// A `.` without numbers on either side is the `Dot` symbol instead.
+    fn parse_number_literal(&mut self) -> Token {
         let mut token_string = String::new();
         let location = self.iter.location();
         self.take_while(char::is_number, &mut token_string);
+        if token_string == "0" {
+            if let Some('x') | Some('X') = self.iter.peek() {
+                return self.parse_hex_literal(token_string, location)
+            }
+        }
+        let mut data = TokenData::IntLiteral;
         // First part of number done. Is it a decimal?
         if self.iter.peek().unwrap_or(' ') == '.' {
-            // This is a case where tokenization cannot continue.
-            // The tokenizer is being rewritten for #46 and will accommodate this.
-            // https://github.com/snirk-lang/protosnirk/issues/46
+            let has_whole_part = !token_string.is_empty();
             token_string.push(self.iter.next().expect("Checked expect: '.' after peek()"));
-            if !self.iter.peek().unwrap_or(' ').is_number() {
-                panic!("Invalid numeric literal with decimal; this panic will be fixed in #46");
+            if !has_whole_part && !self.iter.peek().unwrap_or(' ').is_number() {
+                return Token::new(token_string, location, TokenData::Symbol)
             }
             // numbers after decimal
             self.take_while(char::is_number, &mut token_string);
+            data = TokenData::FloatLiteral;
         }
         let after_numbers = self.iter.peek().unwrap_or(' ');
         if after_numbers != 'e' && after_numbers != 'E' {
-            return Token::new(
-                Cow::Owned(token_string),
-                location,
-                TokenData::NumberLiteral
-            )
+            return Token::new(Cow::Owned(token_string), location, data)
         }
         token_string.push(self.iter.next().expect("Checked expect: 'e' after peek()"));
         // Need numbers after the E
@@ -406,10 +542,72 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
         return Token::new(
             Cow::Owned(token_string),
             location,
-            TokenData::NumberLiteral
+            TokenData::FloatLiteral
         )
     }
 
+    /// Parse a hexadecimal literal after its leading `0`.
+    ///
+    /// It's a float literal if it has a point or a binary exponent, such as
+    /// `0x1.8p3`, which is `1.5 * 2^3`.
+    ///
+    /// The digits are not validated here: malformed literals such as `0x`
+    /// or `0x1p` are still returned so the parser can report them.
+    fn parse_hex_literal(&mut self, mut token_string: String,
+                         location: Location) -> Token {
+        token_string.push(self.iter.next().expect("Checked expect: 'x' after peek()"));
+        self.take_while(|ch| ch.is_ascii_hexdigit(), &mut token_string);
+        let mut data = TokenData::IntLiteral;
+        if self.iter.peek() == Some('.') {
+            token_string.push(self.iter.next().expect("Checked expect: '.' after peek()"));
+            self.take_while(|ch| ch.is_ascii_hexdigit(), &mut token_string);
+            data = TokenData::FloatLiteral;
+        }
+        if let Some('p') | Some('P') = self.iter.peek() {
+            token_string.push(self.iter.next().expect("Checked expect: 'p' after peek()"));
+            if let Some('+') | Some('-') = self.iter.peek() {
+                token_string.push(self.iter.next().expect("Checked expect: sign after peek()"));
+            }
+            self.take_while(|ch| ch.is_ascii_digit(), &mut token_string);
+            data = TokenData::FloatLiteral;
+        }
+        Token::new(token_string, location, data)
+    }
+
+    /// Parse a character literal, including its quotes.
+    ///
+    /// The contents are not validated here: malformed literals such as `''`
+    /// or `'ab'` are still returned so the parser can report them.
+    fn parse_char_literal(&mut self) -> Token {
+        let mut token_string = String::new();
+        let location = self.iter.location();
+        token_string.push(self.iter.next().expect("Checked expect: '\'' after peek()"));
+        loop {
+            match self.iter.peek() {
+                Some('\'') => {
+                    token_string.push(self.iter.next().expect("Checked expect"));
+                    break
+                },
+                // Unterminated literal.
+                None | Some('\n') | Some('\r') => break,
+                Some('\\') => {
+                    token_string.push(self.iter.next().expect("Checked expect"));
+                    if let Some(escaped) = self.iter.peek() {
+                        if escaped != '\n' && escaped != '\r' {
+                            token_string.push(escaped);
+                            self.iter.next();
+                        }
+                    }
+                },
+                Some(other) => {
+                    token_string.push(other);
+                    self.iter.next();
+                }
+            }
+        }
+        Token::new(token_string, location, TokenData::CharLiteral)
+    }
+
     /// Continue taking characters while a condition is met
     #[inline]
     fn take_while<F: Fn(char) -> bool>(&mut self, func: F, acc: &mut String) {
@@ -431,10 +629,11 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
         let mut parsing_kw = true;
         loop {
             if let Some(peeked) = self.iter.peek() {
-                if peeked.is_number() || peeked == '_' {
+                if peeked.is_number() {
                     parsing_kw = false;
                     acc.push(peeked);
-                } else if peeked.is_letter() {
+                } else if peeked.is_letter() || peeked == '_' {
+                    // Keywords such as `assert_eq` can have underscores.
                     acc.push(peeked);
                 } else {
                     return parsing_kw
@@ -461,3 +660,156 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use lex::{tokens, TokenData, TokenType, TokenizerSymbolRule};
+    use super::IterTokenizer;
+
+    #[test]
+    fn it_tokenizes_custom_symbols() {
+        let mut symbols = tokens::default_symbols();
+        symbols.insert(Cow::Borrowed("<>"), TokenizerSymbolRule::Complete);
+        let mut tokenizer = IterTokenizer::with_symbols("2 <> 3".chars(),
+                                                        symbols);
+        let tokens = (0..4).map(|_| tokenizer.next())
+            .map(|token| (token.text().to_string(), token.data()))
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, vec![
+            ("2".to_string(), TokenData::IntLiteral),
+            ("<>".to_string(), TokenData::Symbol),
+            ("3".to_string(), TokenData::IntLiteral),
+            ("".to_string(), TokenData::EOF)
+        ]);
+    }
+
+    #[test]
+    fn numbers_are_int_or_float_literals() {
+        let mut tokenizer = IterTokenizer::new("5 5.0 5e3 0.5E1".chars());
+        let tokens = (0..4).map(|_| tokenizer.next())
+            .map(|token| (token.text().to_string(), token.data()))
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, vec![
+            ("5".to_string(), TokenData::IntLiteral),
+            ("5.0".to_string(), TokenData::FloatLiteral),
+            ("5e3".to_string(), TokenData::FloatLiteral),
+            ("0.5E1".to_string(), TokenData::FloatLiteral)
+        ]);
+    }
+
+    #[test]
+    fn decimal_points_can_leave_out_either_side() {
+        let mut tokenizer = IterTokenizer::new(".5 5. 5.5 . 5.e2".chars());
+        let tokens = (0..5).map(|_| tokenizer.next())
+            .map(|token| (token.text().to_string(), token.get_type()))
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, vec![
+            (".5".to_string(), TokenType::Literal),
+            ("5.".to_string(), TokenType::Literal),
+            ("5.5".to_string(), TokenType::Literal),
+            (".".to_string(), TokenType::Dot),
+            ("5.e2".to_string(), TokenType::Literal)
+        ]);
+        assert_eq!(tokenizer.next().data(), TokenData::EOF);
+        for float in &[".5", "5.", "5.5", "5.e2"] {
+            assert!(float.parse::<f64>().is_ok(), "{} is not a float", float);
+        }
+    }
+
+    #[test]
+    fn default_symbols_split_unknown_symbols() {
+        let mut tokenizer = IterTokenizer::new("2 <> 3".chars());
+        tokenizer.next();
+        assert_eq!(tokenizer.next().text(), "<");
+        assert_eq!(tokenizer.next().text(), ">");
+    }
+
+    #[test]
+    fn whitespace_only_source_is_eof() {
+        for source in &["", "   ", "  \n\t\n    \r\n"] {
+            let mut tokenizer = IterTokenizer::new(source.chars());
+            assert_eq!(tokenizer.next().data(), TokenData::EOF,
+                       "Source {:?} did not tokenize to EOF", source);
+            assert_eq!(tokenizer.next().data(), TokenData::EOF);
+        }
+    }
+
+    #[test]
+    fn many_blank_lines_do_not_overflow_the_stack() {
+        let mut source = "fn main()\n".to_string();
+        for _ in 0..300_000 {
+            source.push_str("  \r\n\n");
+        }
+        source.push_str("    x\n");
+        let mut tokenizer = IterTokenizer::new(source.chars());
+        let tokens = (0..7).map(|_| tokenizer.next().data())
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, vec![
+            TokenData::Keyword, TokenData::Ident, TokenData::Symbol,
+            TokenData::Symbol, TokenData::BeginBlock, TokenData::Ident,
+            TokenData::EndBlock
+        ]);
+        assert_eq!(tokenizer.next().data(), TokenData::EOF);
+    }
+
+    #[test]
+    fn source_can_be_rebuilt_from_trivia() {
+        let source = "// Leading comment\n\
+                      \n\
+                      fn main()  \r\n\
+                      \x20   let x = 1 //trailing\n\
+                      \x20   /// doc\n\
+                      \n\
+                      \x20   if x == 1\n\
+                      \x20       x\t+ 2\n\
+                      \x20   else\n\
+                      \x20       'a'\n\
+                      fn other() -> float\n\
+                      \x20 3.5e2 ** 2 // end\n  \n";
+        let mut tokenizer = IterTokenizer::new(source.chars()).with_trivia();
+        let mut rebuilt = String::new();
+        loop {
+            let token = tokenizer.next();
+            rebuilt.push_str(token.leading_trivia());
+            rebuilt.push_str(token.text());
+            if token.data() == TokenData::EOF {
+                break
+            }
+        }
+        assert_eq!(rebuilt, source);
+
+        let mut tokenizer = IterTokenizer::new(source.chars());
+        assert_eq!(tokenizer.next().leading_trivia(), "");
+    }
+
+    #[test]
+    fn indentation_tokens_can_have_indent_columns() {
+        let source = "fn main()\n\
+                      \x20 if true\n\
+                      \x20      1\n\
+                      \x20 2\n";
+        let indents = |mut tokenizer: IterTokenizer<_>| {
+            let mut indents = Vec::new();
+            loop {
+                let token = tokenizer.next();
+                match token.data() {
+                    TokenData::BeginBlock | TokenData::EndBlock =>
+                        indents.push((token.data(), token.indent_column())),
+                    TokenData::EOF => return indents,
+                    _ => {}
+                }
+            }
+        };
+        assert_eq!(indents(IterTokenizer::new(source.chars())
+                                .with_indent_columns()), vec![
+            (TokenData::BeginBlock, Some(2)),
+            (TokenData::BeginBlock, Some(7)),
+            (TokenData::EndBlock, Some(2)),
+            (TokenData::EndBlock, Some(0)),
+        ]);
+        assert!(indents(IterTokenizer::new(source.chars())).iter()
+                    .all(|&(_, column)| column.is_none()));
+    }
+}
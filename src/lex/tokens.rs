@@ -65,9 +65,11 @@ macro_rules! declare_tokens {
         impl Token {
             pub fn get_type(&self) -> TokenType {
                 match self.data() {
-                    TokenData::NumberLiteral
+                    TokenData::IntLiteral
+                    | TokenData::FloatLiteral
                     | TokenData::UnitLiteral
-                    | TokenData::BoolLiteral => TokenType::Literal,
+                    | TokenData::BoolLiteral
+                    | TokenData::CharLiteral => TokenType::Literal,
                     TokenData::Ident => TokenType::Ident,
                     TokenData::BeginBlock => TokenType::BeginBlock,
                     TokenData::EndBlock => TokenType::EndBlock,
@@ -118,6 +120,7 @@ declare_tokens! {
         PlusEquals: "+="; Complete,
         MinusEquals: "-="; Complete,
         StarEquals: "*="; Complete,
+        DoubleStar: "**"; Complete,
         SlashEquals: "/="; Complete,
         PercentEquals: "%="; Complete,
         LessThanEquals: "<="; Complete,
@@ -125,12 +128,18 @@ declare_tokens! {
 
         LeftParen: "("; Complete,
         RightParen: ")"; Complete,
+        LeftBrace: "{"; Complete,
+        RightBrace: "}"; Complete,
+        LeftBracket: "["; Complete,
+        RightBracket: "]"; Complete,
         // https://github.com/immington-industries/protosnirk/issues/64
         GitMergeBegin: "<<<<<<<"; Complete,
         InlineArrow: "=>"; Complete,
         Arrow: "->"; Complete,
         Comma: ","; Complete,
         Colon: ":"; Complete,
+        Semicolon: ";"; Complete,
+        Dot: "."; Complete,
     }
     symparts {
         "//"; CompletePrefix, // Comments hack, allows // and /// to be parsed.
@@ -144,10 +153,13 @@ declare_tokens! {
         Let: "let",
         Mut: "mut",
         Return: "return",
+        Assert: "assert",
+        AssertEq: "assert_eq",
         Do: "do",
         If: "if",
         Else: "else",
         Fn: "fn",
+        Extern: "extern",
         Typedef: "typedef",
     }
     tynames {
@@ -0,0 +1,53 @@
+//! Lint for `do` blocks without any statements.
+
+use ast::DoBlock;
+use ast::visit::AnalysisVisitor;
+use check::CheckerError;
+
+/// Finds `do` blocks with no statements, such as `do {}`.
+///
+/// An indented block always has a statement, so only braced blocks can be
+/// empty.
+#[derive(Debug, Default)]
+pub struct EmptyBlockChecker {
+    lints: Vec<CheckerError>
+}
+
+impl AnalysisVisitor for EmptyBlockChecker {
+    type Output = Vec<CheckerError>;
+
+    fn finish(self) -> Vec<CheckerError> {
+        self.lints
+    }
+
+    fn visit_do_block(&mut self, do_block: &DoBlock) {
+        if do_block.block().stmts().is_empty() {
+            trace!("Found empty do block at {}", do_block.span().start());
+            self.lints.push(CheckerError::new(vec![do_block.span()],
+                "do block is empty"));
+        }
+        self.visit_block(do_block.block());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ast::visit::AnalysisVisitor;
+    use pipeline::check_source;
+    use super::EmptyBlockChecker;
+
+    #[test]
+    fn empty_do_block_is_linted() {
+        let checked = check_source("fn main()\n\
+                                   \x20   let x = 1\n\
+                                   \x20   do {}\n\
+                                   \x20   do\n\
+                                   \x20       let y = x\n\
+                                   \x20   do { let z = x }\n");
+        let lints = EmptyBlockChecker::default().analyze(checked.unit());
+        assert_eq!(lints.len(), 1, "Unexpected lints {:?}", lints);
+        assert_eq!(lints[0].text(), "do block is empty");
+        assert_eq!(lints[0].offender().map(|span| span.start().line()),
+                   Some(2));
+    }
+}
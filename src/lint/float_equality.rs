@@ -0,0 +1,127 @@
+//! Lint for comparing floats with `==` or `!=`.
+
+use ast::{Assert, BinaryOperation, Expression, LiteralValue};
+use ast::visit::AnalysisVisitor;
+use check::{CheckerError, TypeMapping};
+use identify::ConcreteType;
+
+/// Finds `==` and `!=` comparisons of `float`s, such as `0.1 + 0.2 == 0.3`.
+///
+/// Rounding makes exact float comparisons unreliable, so comparing the
+/// difference with an epsilon is suggested instead. Operand types come from
+/// the type mapping, so this must be run on a checked unit.
+#[derive(Debug)]
+pub struct FloatEqualityChecker<'a> {
+    types: &'a TypeMapping,
+    lints: Vec<CheckerError>
+}
+
+impl<'a> FloatEqualityChecker<'a> {
+    pub fn new(types: &'a TypeMapping) -> FloatEqualityChecker<'a> {
+        FloatEqualityChecker { types, lints: Vec::new() }
+    }
+
+    /// Whether the expression gives a `float`.
+    fn is_float(&self, expr: &Expression) -> bool {
+        match *expr {
+            Expression::Literal(ref literal) => match *literal.value() {
+                LiteralValue::Float(_) => true,
+                _ => false
+            },
+            Expression::VariableRef(ref ident) =>
+                match self.types.get(&ident.id()) {
+                    Some(concrete) => is_float_type(concrete),
+                    None => false
+                },
+            Expression::BinaryOp(ref bin_op) =>
+                !bin_op.operator().is_comparison(),
+            Expression::UnaryOp(ref un_op) => self.is_float(un_op.inner()),
+            Expression::IfExpression(ref if_expr) =>
+                self.is_float(if_expr.true_expr()),
+            Expression::FnCall(ref fn_call) => match self.types.get(&fn_call.id()) {
+                Some(ConcreteType::Function(fn_ty)) =>
+                    is_float_type(fn_ty.return_ty()),
+                _ => false
+            },
+            Expression::Assignment(_) => false
+        }
+    }
+}
+
+fn is_float_type(concrete: &ConcreteType) -> bool {
+    match *concrete {
+        ConcreteType::Named(ref named) => named.name() == "float",
+        ConcreteType::Function(_) => false
+    }
+}
+
+impl<'a> AnalysisVisitor for FloatEqualityChecker<'a> {
+    type Output = Vec<CheckerError>;
+
+    fn finish(self) -> Vec<CheckerError> {
+        self.lints
+    }
+
+    fn visit_assert_stmt(&mut self, assert: &Assert) {
+        // `assert_eq` is meant to compare exactly, so only its operands are
+        // checked.
+        match assert.equality_operands() {
+            Some((left, right)) => {
+                self.visit_expression(left);
+                self.visit_expression(right);
+            },
+            None => self.visit_expression(assert.condition())
+        }
+    }
+
+    fn visit_binary_op(&mut self, bin_op: &BinaryOperation) {
+        // Both sides have the same type, so checking the left is enough.
+        if bin_op.operator().is_comparison() && !bin_op.operator().is_ordering()
+            && self.is_float(bin_op.left()) {
+            trace!("Found float equality at {}", bin_op.span().start());
+            self.lints.push(CheckerError::new(vec![bin_op.span()],
+                    "Floats are compared for exact equality")
+                .with_note("Consider checking whether their difference is \
+                            within an epsilon"));
+        }
+        self.visit_expression(bin_op.left());
+        self.visit_expression(bin_op.right());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ast::visit::AnalysisVisitor;
+    use check::CheckerError;
+    use pipeline::check_source;
+    use super::FloatEqualityChecker;
+
+    fn float_equality_lints(source: &str) -> Vec<CheckerError> {
+        let checked = check_source(source);
+        FloatEqualityChecker::new(checked.type_map()).analyze(checked.unit())
+    }
+
+    #[test]
+    fn float_equality_is_linted() {
+        let lints = float_equality_lints("fn half(x: float) -> float => x / 2\n\
+                                          fn main()\n\
+                                          \x20   let x = 1.0 == 2.0\n\
+                                          \x20   let y = half(x: 1) != -0.5\n");
+        let lines = lints.iter()
+            .map(|lint| {
+                assert_eq!(lint.text(), "Floats are compared for exact equality");
+                lint.offender().map(|span| span.start().line())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn bool_equality_and_float_ordering_are_not_linted() {
+        assert_eq!(float_equality_lints("fn main()\n\
+                                         \x20   let x = 1.0 < 2.0\n\
+                                         \x20   let y = x == true\n\
+                                         \x20   let z = (1 <= 2) != y\n"),
+                   []);
+    }
+}
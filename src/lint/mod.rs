@@ -22,7 +22,32 @@
 //!    ^ `foo` is declared but not used
 //!     true
 //! ```
+//!
+//! ### Lints
+//!
+//! #### Self assignment
+//! ```text
+//! let mut x = 0
+//! x = x
+//! ^ `x` is assigned to itself
+//! ```
+//! #### Float equality
+//! ```text
+//! if 0.1 + 0.2 == 0.3
+//!    ^ floats are compared for exact equality
+//! ```
+//! #### Empty do block
+//! ```text
+//! do {}
+//! ^ do block is empty
+//! ```
 
 //mod usage_checker;
+mod self_assignment;
+mod float_equality;
+mod empty_block;
 
 //pub use self::usage_checker::UsageChecker;
+pub use self::self_assignment::SelfAssignmentChecker;
+pub use self::float_equality::FloatEqualityChecker;
+pub use self::empty_block::EmptyBlockChecker;
@@ -0,0 +1,70 @@
+//! Lint for assignments which don't change their variable.
+
+use ast::{Assignment, Expression};
+use ast::visit::AnalysisVisitor;
+use check::CheckerError;
+
+/// Finds assignments of a variable to itself, such as `x = x`.
+///
+/// Variables are compared by `ScopedId`, so this must be run on an
+/// identified unit. Only a bare variable on the right is flagged: `x = x + 0`
+/// is also a no-op, but is left alone.
+#[derive(Debug, Default)]
+pub struct SelfAssignmentChecker {
+    lints: Vec<CheckerError>
+}
+
+impl AnalysisVisitor for SelfAssignmentChecker {
+    type Output = Vec<CheckerError>;
+
+    fn finish(self) -> Vec<CheckerError> {
+        self.lints
+    }
+
+    fn visit_assignment(&mut self, assign: &Assignment) {
+        if let Expression::VariableRef(ref rvalue) = *assign.rvalue() {
+            let lvalue_id = assign.lvalue().id();
+            if !lvalue_id.is_default() && lvalue_id == rvalue.id() {
+                trace!("Found self-assignment of {}", rvalue.name());
+                self.lints.push(CheckerError::new(vec![assign.span()],
+                    format!("Variable {} is assigned to itself",
+                            rvalue.name())));
+            }
+        }
+        self.visit_expression(assign.rvalue());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ast::visit::AnalysisVisitor;
+    use pipeline::check_source;
+    use super::SelfAssignmentChecker;
+
+    #[test]
+    fn self_assignment_is_linted() {
+        let checked = check_source("fn main()\n\
+                                   \x20   let mut x = 1\n\
+                                   \x20   let y = 2\n\
+                                   \x20   x = y\n\
+                                   \x20   do\n\
+                                   \x20       x = x\n");
+        let lints = SelfAssignmentChecker::default().analyze(checked.unit());
+        assert_eq!(lints.len(), 1, "Unexpected lints {:?}", lints);
+        assert_eq!(lints[0].text(), "Variable x is assigned to itself");
+        assert_eq!(lints[0].offender().map(|span| span.start().line()),
+                   Some(5));
+        assert_eq!(checked.lints(), &lints[..]);
+    }
+
+    #[test]
+    fn assigning_other_variable_is_not_linted() {
+        let checked = check_source("fn main()\n\
+                                   \x20   let mut x = 1\n\
+                                   \x20   let y = 2\n\
+                                   \x20   x = y\n\
+                                   \x20   x = x + 0\n");
+        assert_eq!(SelfAssignmentChecker::default().analyze(checked.unit()),
+                   []);
+    }
+}
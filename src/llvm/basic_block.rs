@@ -30,7 +30,8 @@ impl<'ctx> BasicBlock<'ctx> {
         }
     }
 
-    pub fn get_parent(&self) -> Option<Value<'ctx>> {
+    /// Gets the function this block is in.
+    pub fn parent(&self) -> Option<Value<'ctx>> {
         let value_ref = unsafe {
             LLVMGetBasicBlockParent(self.ptr())
         };
@@ -42,7 +43,9 @@ impl<'ctx> BasicBlock<'ctx> {
         }
     }
 
-    pub fn get_terminator(&self) -> Option<Value<'ctx>> {
+    /// Gets the instruction ending this block, such as a `br` or `ret`, if
+    /// it has one yet.
+    pub fn terminator(&self) -> Option<Value<'ctx>> {
         let value_ref = unsafe {
             LLVMGetBasicBlockTerminator(self.ptr())
         };
@@ -54,3 +57,30 @@ impl<'ctx> BasicBlock<'ctx> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use llvm::{Builder, Context, Type};
+
+    #[test]
+    fn only_returning_block_has_terminator() {
+        let context = Context::new();
+        let module = context.new_module("test");
+        let fn_type = Type::function(&Type::void(&context), vec![], false);
+        let function = module.add_function("f", &fn_type);
+        let empty = context.append_basic_block(&function, "empty");
+        let returns = context.append_basic_block(&function, "returns");
+        let builder = Builder::new(&context);
+        builder.position_at_end(&returns);
+        builder.build_ret_void();
+
+        assert!(empty.terminator().is_none());
+        let terminator = returns.terminator()
+            .expect("Returning block had no terminator");
+        assert_eq!(terminator.print_to_string().trim(), "ret void");
+        for block in &[empty, returns] {
+            let parent = block.parent().expect("Block had no parent");
+            assert_eq!(parent.get_name(), "f");
+        }
+    }
+}
@@ -6,12 +6,31 @@ use std::iter::IntoIterator;
 
 use libc::{c_char, c_uint};
 
-use llvm::{BasicBlock, Context, Value, Type};
+use llvm::{BasicBlock, Context, Module, Value, Type};
 
 use llvm_sys::*;
 use llvm_sys::prelude::*;
 use llvm_sys::core::*;
 
+// Added in LLVM 10, after the version of `llvm_sys` we use, so it's only
+// declared with the `freeze` feature.
+#[cfg(feature = "freeze")]
+extern "C" {
+    fn LLVMBuildFreeze(builder: LLVMBuilderRef,
+                       val: LLVMValueRef,
+                       name: *const c_char) -> LLVMValueRef;
+}
+
+/// A step into an aggregate when building a GEP with
+/// `Builder::build_gep_in_bounds`.
+#[derive(Clone)]
+pub enum GepStep<'ctx> {
+    /// Index of a struct field.
+    Field(u32),
+    /// Index of an array element, which may be computed at runtime.
+    Element(Value<'ctx>),
+}
+
 pub struct Builder<'ctx> {
     ptr: LLVMBuilderRef,
     _lt: ::std::marker::PhantomData<&'ctx ()>
@@ -560,6 +579,28 @@ impl<'ctx> Builder<'ctx> {
         }
     }
 
+    /// Builds an in-bounds GEP into the aggregate `pointer` points to,
+    /// following each step into its fields or elements.
+    ///
+    /// The `i32 0` index which dereferences `pointer` is added for you, so
+    /// `a.b[i]` is one GEP with the steps `[Field(a_b), Element(i)]`.
+    pub fn build_gep_in_bounds(&self,
+                               context: &'ctx Context,
+                               pointer: &Value<'ctx>,
+                               steps: &[GepStep<'ctx>],
+                               name: &str) -> Value<'ctx> {
+        let int32 = Type::int32(context);
+        let mut indices = Vec::with_capacity(steps.len() + 1);
+        indices.push(int32.const_int(0, false));
+        for step in steps {
+            indices.push(match *step {
+                GepStep::Field(ix) => int32.const_int(u64::from(ix), false),
+                GepStep::Element(ref index) => index.clone()
+            });
+        }
+        self.build_in_bounds_gep(pointer, indices, name)
+    }
+
     pub fn build_struct_gep(&self,
                             pointer: &Value<'ctx>,
                             ix: u32,
@@ -777,6 +818,7 @@ impl<'ctx> Builder<'ctx> {
         let args_ptrs = unsafe {
             mem::transmute::<&mut [Value<'ctx>], &mut [LLVMValueRef]>(args_ref)
         };
+        let name = CString::new(name).unwrap();
         unsafe {
             Value::from_ref(LLVMBuildCall(self.ptr(),
                           func.ptr(),
@@ -872,9 +914,125 @@ impl<'ctx> Builder<'ctx> {
         }
     }
 
+    /// Builds a `freeze` of the value, which picks an arbitrary but fixed
+    /// value if it's `undef` or `poison`.
+    ///
+    /// Needs the `freeze` feature, and LLVM 10 or later.
+    #[cfg(feature = "freeze")]
+    pub fn build_freeze(&self, val: &Value<'ctx>, name: &str) -> Value<'ctx> {
+        let name = CString::new(name).unwrap();
+        unsafe {
+            Value::from_ref(LLVMBuildFreeze(self.ptr(),
+                                            val.ptr(),
+                                            name.as_ptr() as *const c_char))
+        }
+    }
+
+    /// Builds a call to the `llvm.assume` intrinsic, telling the optimizer
+    /// that the `i1` condition is always true. The intrinsic is declared in
+    /// the module if it hasn't been used yet.
+    ///
+    /// The behavior is undefined if the condition turns out to be false.
+    pub fn build_assume(&self,
+                        context: &'ctx Context,
+                        module: &Module<'ctx>,
+                        cond: &Value<'ctx>) -> Value<'ctx> {
+        let assume = module.get_function("llvm.assume").unwrap_or_else(|| {
+            let assume_type = Type::function(&Type::void(context),
+                                             vec![Type::int1(context)],
+                                             false);
+            module.add_function("llvm.assume", &assume_type)
+        });
+        self.build_call(&assume, vec![cond.clone()], "")
+    }
+
     pub fn build_atomic_rmw(&self, op: LLVMAtomicRMWBinOp, ptr: &Value<'ctx>, val: &Value<'ctx>, ordering: LLVMAtomicOrdering, single_thread: bool) -> Value<'ctx> {
         unsafe {
             Value::from_ref(LLVMBuildAtomicRMW(self.ptr(), op, ptr.ptr(), val.ptr(), ordering, single_thread as LLVMBool))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use llvm_sys::LLVMRealPredicate;
+
+    use llvm::{Builder, Context, GepStep, Type};
+
+    #[test]
+    fn assume_is_emitted() {
+        let context = Context::new();
+        let module = context.new_module("hints");
+        let double = Type::double(&context);
+        let function_type = Type::function(&double, vec![double.clone()],
+                                           false);
+        let function = module.add_function("test", &function_type);
+        let block = context.append_basic_block(&function, "entry");
+        let builder = Builder::new(&context);
+        builder.position_at_end(&block);
+
+        let param = function.get_params().remove(0);
+        let nonzero = builder.build_fcmp(LLVMRealPredicate::LLVMRealONE,
+            &param, &double.const_real(0f64), "nonzero");
+        builder.build_assume(&context, &module, &nonzero);
+        builder.build_ret(&param);
+
+        let ir = module.print_to_string();
+        for expected in &["call void @llvm.assume(i1 %nonzero)",
+                          "declare void @llvm.assume(i1"] {
+            assert!(ir.contains(expected), "No {}:\n{}", expected, ir);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "freeze")]
+    fn freeze_is_emitted() {
+        let context = Context::new();
+        let module = context.new_module("freeze");
+        let double = Type::double(&context);
+        let function_type = Type::function(&double, vec![double.clone()],
+                                           false);
+        let function = module.add_function("test", &function_type);
+        let block = context.append_basic_block(&function, "entry");
+        let builder = Builder::new(&context);
+        builder.position_at_end(&block);
+
+        let param = function.get_params().remove(0);
+        let frozen = builder.build_freeze(&param, "frozen");
+        builder.build_ret(&frozen);
+
+        let ir = module.print_to_string();
+        assert!(ir.contains("%frozen = freeze double %0"), "No freeze:\n{}", ir);
+    }
+
+    #[test]
+    fn gep_in_bounds_indexes_nested_fields() {
+        let context = Context::new();
+        let module = context.new_module("gep");
+        let function_type = Type::function(&Type::void(&context),
+                                           Vec::new(), false);
+        let function = module.add_function("test", &function_type);
+        let block = context.append_basic_block(&function, "entry");
+        let builder = Builder::new(&context);
+        builder.position_at_end(&block);
+
+        // { double, [4 x i32] }
+        let int32 = Type::int32(&context);
+        let double = Type::double(&context);
+        let pair = Type::structure(&context, &[double, int32.array(4)], false);
+        let alloca = builder.build_alloca(&pair, "pair");
+        let element = Type::int64(&context).const_int(2, false);
+        let gep = builder.build_gep_in_bounds(&context, &alloca,
+            &[GepStep::Field(1), GepStep::Element(element)], "elem");
+
+        assert_eq!(gep.count_operands(), 4);
+        let indices = (1..4)
+            .map(|ix| gep.get_operand(ix).expect("Missing operand")
+                         .print_to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(indices, ["i32 0", "i32 1", "i64 2"]);
+        assert!(gep.print_to_string().contains("getelementptr inbounds"),
+                "Not an in-bounds GEP: {}", gep.print_to_string());
+        assert!(gep.get_operand(4).is_none());
+    }
+}
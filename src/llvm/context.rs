@@ -6,7 +6,7 @@ use libc::{c_char};
 use llvm_sys::core::*;
 use llvm_sys::prelude::*;
 
-use llvm::{Value, Module, BasicBlock, Builder};
+use llvm::{Value, Module, BasicBlock, Builder, Type};
 
 pub struct Context {
     ptr: LLVMContextRef
@@ -50,6 +50,18 @@ impl Context {
         }
     }
 
+    /// Declares a struct type with the given name.
+    ///
+    /// The struct is opaque until its fields are set with
+    /// `Type::struct_set_body`, so it can refer to itself through pointers.
+    pub fn named_struct<'ctx>(&'ctx self, name: &str) -> Type<'ctx> {
+        let name = CString::new(name).unwrap();
+        unsafe {
+            Type::from_ref(LLVMStructCreateNamed(self.ptr(),
+                                                 name.as_ptr() as *const c_char))
+        }
+    }
+
     pub fn new_builder<'ctx>(&'ctx self) -> Builder<'ctx> {
         unsafe {
             Builder::from_ref(
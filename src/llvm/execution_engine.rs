@@ -0,0 +1,133 @@
+//! Bindings to LLVM's MCJIT execution engine.
+
+use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+
+use libc::{c_char, c_void};
+
+use llvm_sys::core::LLVMDisposeMessage;
+use llvm_sys::execution_engine::*;
+use llvm_sys::support::LLVMAddSymbol;
+use llvm_sys::target::{LLVM_InitializeNativeAsmPrinter,
+                       LLVM_InitializeNativeTarget};
+
+use llvm::Module;
+
+/// Makes `address` the definition of the external symbol `name` in code
+/// compiled by an `ExecutionEngine`, such as a fn which a module declares
+/// but doesn't define.
+///
+/// # Safety
+///
+/// Compiled code calls or reads whatever is at `address`, so it must live
+/// as long as that code and match the declaration's type.
+pub unsafe fn add_symbol(name: &str, address: *mut c_void) {
+    let name = CString::new(name).unwrap();
+    // LLVM keeps a copy of the name.
+    LLVMAddSymbol(name.as_ptr(), address);
+}
+
+/// Compiles a module to native code in memory so its fns can be called.
+///
+/// The engine owns the module, and disposes of it when dropped.
+#[derive(Debug)]
+pub struct ExecutionEngine<'ctx> {
+    ptr: LLVMExecutionEngineRef,
+    _lt: PhantomData<&'ctx ()>
+}
+
+impl_llvm_ptr_fmt!(<'ctx> ExecutionEngine);
+
+impl<'ctx> Drop for ExecutionEngine<'ctx> {
+    fn drop(&mut self) {
+        unsafe {
+            LLVMDisposeExecutionEngine(self.ptr())
+        }
+    }
+}
+
+impl<'ctx> ExecutionEngine<'ctx> {
+    llvm_methods! { ExecutionEngine<'ctx> => LLVMExecutionEngineRef }
+
+    /// Creates an MCJIT engine for the module, for the native target.
+    ///
+    /// See `LLVMCreateExecutionEngineForModule`.
+    pub fn for_module(module: Module<'ctx>)
+                      -> Result<ExecutionEngine<'ctx>, String> {
+        let mut engine = ptr::null_mut();
+        let mut error: *mut c_char = ptr::null_mut();
+        let failed = unsafe {
+            LLVMLinkInMCJIT();
+            LLVM_InitializeNativeTarget();
+            LLVM_InitializeNativeAsmPrinter();
+            LLVMCreateExecutionEngineForModule(&mut engine,
+                                               module.ptr(),
+                                               &mut error)
+        };
+        // LLVM takes the module even if the engine can't be created.
+        mem::forget(module);
+        if failed != 0 {
+            unsafe {
+                let message = String::from_utf8_lossy(
+                    CStr::from_ptr(error).to_bytes()).into_owned();
+                LLVMDisposeMessage(error);
+                Err(message)
+            }
+        }
+        else {
+            Ok(unsafe { ExecutionEngine::from_ref(engine) })
+        }
+    }
+
+    /// Gets the address of the compiled fn `name`, compiling the module if
+    /// it hasn't been yet.
+    ///
+    /// See `LLVMGetFunctionAddress`.
+    pub fn function_address(&self, name: &str) -> Option<u64> {
+        let name = CString::new(name).unwrap();
+        let address = unsafe {
+            LLVMGetFunctionAddress(self.ptr(), name.as_ptr())
+        };
+        if address == 0 {
+            None
+        }
+        else {
+            Some(address)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use llvm::{Builder, Context, Type};
+    use super::ExecutionEngine;
+
+    #[test]
+    fn compiled_fns_can_be_called() {
+        let context = Context::new();
+        let module = context.new_module("jit");
+        let double = Type::double(&context);
+        let fn_type = Type::function(&double, vec![double.clone()], false);
+        let function = module.add_function("twice", &fn_type);
+        let block = context.append_basic_block(&function, "entry");
+        let builder = Builder::new(&context);
+        builder.position_at_end(&block);
+        let param = function.get_params().remove(0);
+        let doubled = builder.build_fadd(&param, &param, "doubled");
+        builder.build_ret(&doubled);
+
+        let engine = ExecutionEngine::for_module(module)
+            .expect("Could not create execution engine");
+        assert_eq!(engine.function_address("missing"), None);
+        let address = engine.function_address("twice")
+            .expect("Could not compile twice");
+        let twice: extern "C" fn(f64) -> f64 = unsafe {
+            mem::transmute(address as usize)
+        };
+        assert_eq!(twice(1.5), 3.0);
+    }
+}
@@ -1,6 +1,7 @@
 //! Bindings to LLVM module objects
 
 use std::ffi::{CStr, CString};
+use std::iter;
 use std::marker::PhantomData;
 
 use libc::c_char;
@@ -13,7 +14,10 @@ use llvm_sys::target::{LLVMSetModuleDataLayout};
 use llvm::{Type, Value, TargetData};
 
 /// Handle to an LLVM Module. Owned by an LLVM Context.
-#[derive(Debug, Clone)]
+///
+/// Cloning a module copies it with `clone_module`, as each handle disposes
+/// of its module when dropped.
+#[derive(Debug)]
 pub struct Module<'ctx> {
     ptr: LLVMModuleRef,
     _lt: PhantomData<&'ctx ()>
@@ -21,6 +25,12 @@ pub struct Module<'ctx> {
 
 impl_llvm_ptr_fmt!(<'ctx> Module);
 
+impl<'ctx> Clone for Module<'ctx> {
+    fn clone(&self) -> Module<'ctx> {
+        self.clone_module()
+    }
+}
+
 impl<'ctx> Drop for Module<'ctx> {
     fn drop(&mut self) {
         unsafe {
@@ -43,6 +53,16 @@ impl<'ctx> Module<'ctx> {
         }
     }
 
+    /// Creates a copy of this module in the same context, such as to
+    /// compare its IR before and after running passes on one of them.
+    ///
+    /// See `LLVMCloneModule`.
+    pub fn clone_module(&self) -> Module<'ctx> {
+        unsafe {
+            Module::from_ref(LLVMCloneModule(self.ptr()))
+        }
+    }
+
     pub fn print_to_string(&self) -> String {
         unsafe {
             let buf = LLVMPrintModuleToString(self.ptr());
@@ -79,6 +99,19 @@ impl<'ctx> Module<'ctx> {
         }
     }
 
+    /// Iterates over the functions in the module, in the order they were
+    /// added.
+    ///
+    /// See `LLVMGetFirstFunction` and `LLVMGetNextFunction`.
+    pub fn functions(&self) -> impl Iterator<Item=Value<'ctx>> {
+        let first = unsafe { LLVMGetFirstFunction(self.ptr()) };
+        iter::successors(Some(first).filter(|first| !first.is_null()),
+                         |&function| {
+                let next = unsafe { LLVMGetNextFunction(function) };
+                Some(next).filter(|next| !next.is_null())
+            })
+            .map(|function| unsafe { Value::from_ref(function) })
+    }
 
     pub fn get_type_by_name(&self, name: &str) -> Option<Type<'ctx>> {
         let c_name = CString::new(name).unwrap();
@@ -106,6 +139,41 @@ impl<'ctx> Module<'ctx> {
         }
     }
 
+    /// Gets the target triple of the module.
+    ///
+    /// See `LLVMGetTarget`.
+    pub fn target_triple(&self) -> String {
+        unsafe {
+            let triple = CStr::from_ptr(LLVMGetTarget(self.ptr()));
+            triple.to_string_lossy().into_owned()
+        }
+    }
+
+    /// Sets the data layout of the module from its string representation.
+    ///
+    /// See `LLVMSetDataLayout`.
+    pub fn set_data_layout_str(&self, data_layout: &str) {
+        let c_layout = CString::new(data_layout).unwrap();
+        unsafe {
+            LLVMSetDataLayout(self.ptr(), c_layout.as_ptr());
+        }
+    }
+
+    /// Gets the string representation of the module's data layout.
+    ///
+    /// See `LLVMGetDataLayoutStr`.
+    pub fn data_layout_str(&self) -> String {
+        unsafe {
+            let layout = CStr::from_ptr(LLVMGetDataLayoutStr(self.ptr()));
+            layout.to_string_lossy().into_owned()
+        }
+    }
+
+    /// Gets a copy of the module's data layout, for querying type sizes.
+    pub fn target_data(&self) -> TargetData {
+        TargetData::from_target_layout(&self.data_layout_str())
+    }
+
     pub fn verify(&self,
                   action: LLVMVerifierFailureAction) -> Result<(), String> {
         let mut error = 0 as *mut c_char;
@@ -122,3 +190,76 @@ impl<'ctx> Module<'ctx> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use llvm::{Context, Module, Type};
+
+    #[test]
+    fn it_reads_back_target_triple_and_data_layout() {
+        let context = Context::new();
+        let module = context.new_module("test");
+        module.set_target_triple("x86_64-unknown-linux-gnu");
+        assert_eq!(module.target_triple(), "x86_64-unknown-linux-gnu");
+        module.set_data_layout_str("e-m:e-i64:64-f80:128-n8:16:32:64-S128");
+        assert_eq!(module.data_layout_str(),
+                   "e-m:e-i64:64-f80:128-n8:16:32:64-S128");
+    }
+
+    #[test]
+    fn named_struct_appears_by_name() {
+        let context = Context::new();
+        let module = context.new_module("test");
+        let point = context.named_struct("Point");
+        assert!(point.is_opaque_struct());
+        let double = Type::double(&context);
+        point.struct_set_body(&[double.clone(), double], false);
+        assert!(!point.is_opaque_struct());
+
+        let fn_type = Type::function(&Type::void(&context),
+                                     vec![point], false);
+        module.add_function("takes_point", &fn_type);
+        let ir = module.print_to_string();
+        assert!(ir.contains("%Point = type { double, double }"),
+                "Point type not declared by name:\n{}", ir);
+        assert!(ir.contains("declare void @takes_point(%Point)"),
+                "Point type not used by name:\n{}", ir);
+    }
+
+    #[test]
+    fn functions_are_iterated_in_order() {
+        let context = Context::new();
+        let module = context.new_module("test");
+        assert_eq!(module.functions().count(), 0);
+
+        let fn_type = Type::function(&Type::void(&context),
+                                     Vec::new(), false);
+        module.add_function("first", &fn_type);
+        module.add_function("second", &fn_type);
+        let names = module.functions()
+            .map(|function| function.get_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn cloned_modules_are_independent() {
+        let context = Context::new();
+        let module = context.new_module("test");
+        let fn_type = Type::function(&Type::void(&context),
+                                     Vec::new(), false);
+        module.add_function("shared", &fn_type);
+
+        let clone = module.clone_module();
+        assert_eq!(clone.print_to_string(), module.print_to_string());
+        clone.add_function("cloned_only", &fn_type);
+        let names = |module: &Module| module.functions()
+            .map(|function| function.get_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names(&module), vec!["shared"]);
+        assert_eq!(names(&clone), vec!["shared", "cloned_only"]);
+
+        drop(module);
+        assert!(clone.print_to_string().contains("declare void @shared()"));
+    }
+}
@@ -7,6 +7,8 @@ use llvm_sys::core::LLVMDisposeMessage;
 use llvm_sys::target::*;
 use llvm_sys::target_machine::*;
 
+use llvm::Type;
+
 pub fn initialize_native_target() -> bool {
     unsafe {
         if LLVM_InitializeNativeTarget() == 1 {
@@ -177,6 +179,26 @@ impl TargetData {
         let machine = try!(TargetMachine::native(opt_level, reloc_mode, code_model));
         Ok(TargetData::from_machine(&machine))
     }
+
+    /// Number of bytes a value of the type takes up in memory, including
+    /// padding for alignment (as in an array of them).
+    ///
+    /// See `LLVMABISizeOfType`. The type must be sized: not `void` or a
+    /// function type.
+    pub fn abi_size_of(&self, ty: &Type) -> u64 {
+        unsafe {
+            LLVMABISizeOfType(self.ptr(), ty.ptr()) as u64
+        }
+    }
+
+    /// Size of a pointer in the default address space, in bytes.
+    ///
+    /// See `LLVMPointerSize`.
+    pub fn pointer_size(&self) -> u64 {
+        unsafe {
+            LLVMPointerSize(self.ptr()) as u64
+        }
+    }
 }
 
 pub struct TargetMachine {
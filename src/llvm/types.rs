@@ -141,16 +141,63 @@ impl<'ctx> Type<'ctx> {
 
     // From Core / Types / Structure Types
 
+    pub fn structure<'a, I>(ctx: &'ctx Context, fields: I, packed: bool)
+                            -> Type<'ctx>
+    where I: IntoIterator<Item=&'a Type<'ctx>>, 'ctx: 'a {
+        let mut field_ptrs = fields.into_iter()
+            .map(|field| field.ptr())
+            .collect::<Vec<_>>();
+        unsafe {
+            Type::from_ref(LLVMStructTypeInContext(ctx.ptr(),
+                field_ptrs.as_mut_ptr(),
+                field_ptrs.len() as c_uint,
+                packed as LLVMBool))
+        }
+    }
+
+    /// Sets the fields of a struct declared with `Context::named_struct`.
+    pub fn struct_set_body<'a, I>(&self, fields: I, packed: bool)
+    where I: IntoIterator<Item=&'a Type<'ctx>>, 'ctx: 'a {
+        let mut field_ptrs = fields.into_iter()
+            .map(|field| field.ptr())
+            .collect::<Vec<_>>();
+        unsafe {
+            LLVMStructSetBody(self.ptr(),
+                field_ptrs.as_mut_ptr(),
+                field_ptrs.len() as c_uint,
+                packed as LLVMBool);
+        }
+    }
+
+    /// Whether this is a struct type whose body has not been set.
+    pub fn is_opaque_struct(&self) -> bool {
+        unsafe {
+            LLVMIsOpaqueStruct(self.ptr()) != 0
+        }
+    }
+
     // From Core / Types / Sequential Types
 
+    pub fn array(&self, count: u32) -> Type<'ctx> {
+        unsafe {
+            Type::from_ref(LLVMArrayType(self.ptr(), count as c_uint))
+        }
+    }
+
+    pub fn pointer(&self, address_space: u32) -> Type<'ctx> {
+        unsafe {
+            Type::from_ref(LLVMPointerType(self.ptr(), address_space as c_uint))
+        }
+    }
+
     // From Core / Types / Integer Types
     context_ctors! {
         pub fn int1 <'ctx> = LLVMInt1TypeInContext;
-        pub fn int8 <'ctx> = LLVMInt1TypeInContext;
-        pub fn int16 <'ctx> = LLVMInt1TypeInContext;
-        pub fn int32 <'ctx> = LLVMInt1TypeInContext;
-        pub fn int64 <'ctx> = LLVMInt1TypeInContext;
-        pub fn int128 <'ctx> = LLVMInt1TypeInContext;
+        pub fn int8 <'ctx> = LLVMInt8TypeInContext;
+        pub fn int16 <'ctx> = LLVMInt16TypeInContext;
+        pub fn int32 <'ctx> = LLVMInt32TypeInContext;
+        pub fn int64 <'ctx> = LLVMInt64TypeInContext;
+        pub fn int128 <'ctx> = LLVMInt128TypeInContext;
     }
 
     pub fn int(ctx: &'ctx Context, num_bits: u32) -> Type<'ctx> {
@@ -171,9 +218,13 @@ impl<'ctx> Type<'ctx> {
 
     // From Core / Values / Constants
     llvm_passthrough! {
+        /// The zero value of this type, such as `0`, `null` or
+        /// `zeroinitializer`.
         pub fn const_null() -> Value<'ctx> => LLVMConstNull;
         pub fn const_all_ones() -> Value<'ctx> => LLVMConstAllOnes;
-        pub fn get_undef() -> Value<'ctx> => LLVMGetUndef;
+        /// An `undef` value of this type, for slots which aren't
+        /// initialized yet.
+        pub fn const_undef() -> Value<'ctx> => LLVMGetUndef;
         pub fn const_ptr_null() -> Value<'ctx> => LLVMConstPointerNull;
     }
 
@@ -1,5 +1,6 @@
 //! Bindings to LLVM value objects
 
+use std::cmp;
 use std::ffi::{CString, CStr};
 use std::mem;
 
@@ -7,6 +8,7 @@ use libc::{size_t, c_uint};
 
 use llvm_sys::core::*;
 use llvm_sys::prelude::*;
+use llvm_sys::LLVMLinkage;
 use llvm_sys::analysis::{LLVMVerifierFailureAction, LLVMVerifyFunction};
 
 use llvm::BasicBlock;
@@ -28,7 +30,41 @@ impl<'ctx> Value<'ctx> {
     llvm_methods! { Value<'ctx> => LLVMValueRef }
 
     llvm_passthrough! {
-        pub fn get_type() -> Type<'ctx> => LLVMTypeOf;
+        /// Get the LLVM type of this value.
+        pub fn type_of() -> Type<'ctx> => LLVMTypeOf;
+    }
+
+    /// Whether this value is a constant.
+    pub fn is_constant(&self) -> bool {
+        unsafe {
+            LLVMIsConstant(self.ptr()) != 0
+        }
+    }
+
+    /// Whether this value is a function.
+    pub fn is_function(&self) -> bool {
+        unsafe {
+            !LLVMIsAFunction(self.ptr()).is_null()
+        }
+    }
+
+    /// Whether this value is `undef`.
+    pub fn is_undef(&self) -> bool {
+        unsafe {
+            LLVMIsUndef(self.ptr()) != 0
+        }
+    }
+
+    /// The value of this constant integer, zero-extended, if it is one.
+    pub fn const_int_value(&self) -> Option<u64> {
+        unsafe {
+            if LLVMIsAConstantInt(self.ptr()).is_null() {
+                None
+            }
+            else {
+                Some(LLVMConstIntGetZExtValue(self.ptr()))
+            }
+        }
     }
 
     pub fn is_null_value(&self) -> bool {
@@ -57,6 +93,18 @@ impl<'ctx> Value<'ctx> {
         }
     }
 
+    /// Gets the name of this value, which is empty if it's unnamed.
+    ///
+    /// See `LLVMGetValueName2`.
+    pub fn get_name(&self) -> String {
+        let mut len: size_t = 0;
+        unsafe {
+            let name = LLVMGetValueName2(self.ptr(), &mut len);
+            let bytes = ::std::slice::from_raw_parts(name as *const u8, len);
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+    }
+
     pub fn set_name(&self, name: &str) {
         let c_name = CString::new(name).unwrap();
         unsafe {
@@ -64,6 +112,13 @@ impl<'ctx> Value<'ctx> {
         }
     }
 
+    /// Sets how this global value is linked, such as private to its module.
+    pub fn set_linkage(&self, linkage: LLVMLinkage) {
+        unsafe {
+            LLVMSetLinkage(self.ptr(), linkage);
+        }
+    }
+
     pub fn verify(&self, action: LLVMVerifierFailureAction) -> bool {
         unsafe {
             LLVMVerifyFunction(self.ptr(), action) == 0
@@ -86,10 +141,33 @@ impl<'ctx> Value<'ctx> {
         }
     }
 
+    // methods on User
+
+    pub fn count_operands(&self) -> u32 {
+        unsafe {
+            LLVMGetNumOperands(self.ptr()) as u32
+        }
+    }
+
+    pub fn get_operand(&self, index: u32) -> Option<Value<'ctx>> {
+        if index >= self.count_operands() {
+            return None
+        }
+        unsafe {
+            Some(Value::from_ref(LLVMGetOperand(self.ptr(), index as c_uint)))
+        }
+    }
+
     // From Core / BasicBlock
 
     // methods on PhiNode
 
+    /// Adds incoming values to a phi node, each from the block at the same
+    /// position in `blocks`.
+    ///
+    /// Every value needs a block, so a mismatched count is a codegen bug.
+    /// It's asserted in debug builds. In release it's logged and only the
+    /// values which have a block are added.
     pub fn add_incoming<V, B>(&self, values: V, blocks: B)
     where V: IntoIterator<Item=Value<'ctx>>,
           B: IntoIterator<Item=BasicBlock<'ctx>> {
@@ -108,14 +186,84 @@ impl<'ctx> Value<'ctx> {
             mem::transmute::<&mut [BasicBlock<'ctx>], &mut [LLVMBasicBlockRef]>(blocks_ref)
         };
 
-        debug_assert_eq!(blocks_count, values_count);
+        if blocks_count != values_count {
+            warn!("Phi node given {} incoming values from {} blocks",
+                  values_count, blocks_count);
+        }
+        debug_assert_eq!(values_count, blocks_count,
+                         "Mismatched incoming values and blocks of phi node");
+        debug_assert!(unsafe { !LLVMIsAPHINode(self.ptr()).is_null() },
+                      "Adding incoming values to non-phi value");
 
+        // LLVM reads `count` of each, so don't pass the longer count.
+        let count = cmp::min(values_count, blocks_count);
         unsafe {
             LLVMAddIncoming(self.ptr(),
                             values_ptrs.as_mut_ptr(),
                             blocks_ptrs.as_mut_ptr(),
-                            values_count);
+                            count);
         }
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use llvm_sys::LLVMTypeKind;
+
+    use llvm::{Builder, Context, Type};
+
+    #[test]
+    fn const_int_is_constant() {
+        let context = Context::new();
+        let int_type = Type::int(&context, 32);
+        let value = int_type.const_int(5u64, false);
+        assert!(value.is_constant());
+        assert!(!value.is_function());
+        assert_eq!(value.type_of().get_kind(),
+                   LLVMTypeKind::LLVMIntegerTypeKind);
+        assert_eq!(value.const_int_value(), Some(5));
+    }
+
+    #[test]
+    fn null_int_is_constant_zero() {
+        let context = Context::new();
+        let int_type = Type::int(&context, 32);
+        let value = int_type.const_null();
+        assert!(value.is_constant());
+        assert!(!value.is_undef());
+        assert_eq!(value.const_int_value(), Some(0));
+    }
+
+    #[test]
+    fn undef_struct_is_constant_undef() {
+        let context = Context::new();
+        let fields = [Type::int(&context, 32), Type::double(&context)];
+        let struct_type = Type::structure(&context, &fields, false);
+        let value = struct_type.const_undef();
+        assert!(value.is_constant());
+        assert!(value.is_undef());
+        assert_eq!(value.const_int_value(), None);
+        assert_eq!(value.print_to_string(), "{ i32, double } undef");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Mismatched incoming values and blocks")]
+    fn phi_incoming_counts_must_match() {
+        let context = Context::new();
+        let module = context.new_module("phi");
+        let double = Type::double(&context);
+        let function_type = Type::function(&double, Vec::new(), false);
+        let function = module.add_function("test", &function_type);
+        let entry = context.append_basic_block(&function, "entry");
+        let other = context.append_basic_block(&function, "other");
+        let builder = Builder::new(&context);
+        builder.position_at_end(&other);
+
+        let phi = builder.build_phi(&double, "phi");
+        phi.add_incoming(vec![double.const_real(0f64),
+                              double.const_real(1f64)],
+                         vec![entry]);
+    }
+}
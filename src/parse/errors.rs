@@ -1,6 +1,8 @@
 //! Error handling in parsers
 
-use lex::{CowStr, Token, TokenType};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use lex::{CowStr, Location, Token, TokenType};
 use ast::{Expression};
 
 /// Result given from main and expression parsers
@@ -24,8 +26,35 @@ pub enum ParseError {
         text: CowStr,
         token_type: TokenType
     },
-    EOF,
-    LazyString(String)
+    /// A line was indented further than the block it is in, but the
+    /// statement before it does not begin a new block.
+    UnexpectedIndent(Token),
+    /// Expressions were nested more deeply than the parser allows, starting
+    /// at the given token.
+    NestingTooDeep(Token),
+    /// The condition of the given `if` token was not followed by a block
+    /// (or `=>` for an inline if).
+    ExpectedBlockAfterIf(Token),
+    /// The input, or the block being parsed, ended before the construct
+    /// being parsed was complete.
+    EOF {
+        /// Location of the last token consumed before the end.
+        location: Location,
+        /// If a delimiter was left open, the token which would have closed
+        /// it and the location of the token which opened it.
+        unclosed: Option<(TokenType, Location)>
+    },
+    LazyString(CowStr)
+}
+
+impl ParseError {
+    /// Error for a token which was parsed as an operator, but isn't one.
+    pub fn unknown_operator(token: &Token) -> ParseError {
+        ParseError::UnknownOperator {
+            text: token.text_owned().into(),
+            token_type: token.get_type()
+        }
+    }
 }
 
 /// Information of what the parser was expecting to get
@@ -39,3 +68,54 @@ pub enum ExpectedNextType {
     TypeExpression,
     SpecificToken(CowStr),
 }
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::ParseError::*;
+        match *self {
+            ExpectedToken { ref expected, ref got, ref token } =>
+                write!(f, "Expected {:?}, but found {:?} at {}",
+                       expected, got, token.start()),
+            ExpectedExpression { ref expected, ref got } =>
+                write!(f, "Expected {}, but found {} at {}",
+                       expected, got.description(), got.span()),
+            ExpectedLValue(ref got) =>
+                write!(f, "Expected {}, but found {} at {}",
+                       ExpectedNextType::Lvalue, got.description(), got.span()),
+            ExpectedRValue(ref got) =>
+                write!(f, "Expected {}, but found {} at {}",
+                       ExpectedNextType::Rvalue, got.description(), got.span()),
+            UnknownOperator { ref text, .. } =>
+                write!(f, "Unknown operator {}", text),
+            UnexpectedIndent(ref token) =>
+                write!(f, "Unexpected indentation at {}", token.start()),
+            NestingTooDeep(ref token) =>
+                write!(f, "Expression nested too deeply at {}", token.start()),
+            ExpectedBlockAfterIf(ref token) =>
+                write!(f, "Expected a block or `=>` after the condition of \
+                           the `if` at {}", token.start()),
+            EOF { ref location, unclosed: None } =>
+                write!(f, "Unexpected end of input after {}", location),
+            EOF { ref location, unclosed: Some((ref closing, ref opened_at)) } =>
+                write!(f, "Unexpected end of input after {}: expected {:?} to \
+                           close the delimiter opened at {}",
+                       location, closing, opened_at),
+            LazyString(ref text) => f.write_str(text)
+        }
+    }
+}
+
+impl Display for ExpectedNextType {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::ExpectedNextType::*;
+        match *self {
+            AnyStatement => f.write_str("a statement"),
+            AnyExpression => f.write_str("an expression"),
+            AnyItem => f.write_str("an item"),
+            Lvalue => f.write_str("a variable name"),
+            Rvalue => f.write_str("a value"),
+            TypeExpression => f.write_str("a type"),
+            SpecificToken(ref text) => write!(f, "`{}`", text)
+        }
+    }
+}
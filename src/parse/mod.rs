@@ -10,5 +10,8 @@ mod parser;
 
 pub mod parsers;
 
+#[cfg(test)]
+mod tests;
+
 pub use self::errors::{ParseError, ParseResult, ExpectedNextType};
-pub use self::parser::{Parser, IndentationRule};
+pub use self::parser::{Parser, IndentationRule, DEFAULT_MAX_EXPRESSION_DEPTH};
@@ -3,14 +3,17 @@
 //! The parser is a configurable object which parses a stream of tokens into a
 //! source tree.
 
-use std::borrow::Cow;
 use std::collections::VecDeque;
+use std::mem;
 
-use lex::{CowStr, Token, TokenType, Span, Tokenizer};
+use lex::{CowStr, Token, TokenType, Location, Span, Tokenizer};
 use parse::ParseError;
 use ast::*;
 use parse::parsers::*;
 
+/// Default limit on how deeply expressions may be nested.
+pub const DEFAULT_MAX_EXPRESSION_DEPTH: usize = 256;
+
 /// Parser object which parses things
 pub struct Parser<T: Tokenizer> {
     /// Tokenizer which supplies tokens
@@ -19,6 +22,29 @@ pub struct Parser<T: Tokenizer> {
     lookahead: VecDeque<Token>,
     /// Allows the parser to skip over unneeded indentation
     indent_rules: Vec<IndentationRule>,
+    /// Number of `expression` calls currently being parsed
+    expression_depth: usize,
+    /// Limit on `expression_depth`, so nested expressions can't overflow
+    /// the stack
+    max_expression_depth: usize,
+    /// Location of the last consumed token which wasn't indentation or EOF
+    last_location: Location,
+    /// Operators referenced as fns, i.e. `(+)`, and where they were first
+    /// referenced. Their fns are declared in the parsed unit.
+    operator_fns: Vec<(BinaryOperator, Location)>,
+    /// Whether indentation inside brackets is skipped, so that bracketed
+    /// expressions can span lines
+    ignore_bracketed_indentation: bool,
+    /// Number of brackets opened by the tokens read so far
+    bracket_depth: usize,
+    /// Indentation skipped inside brackets which hasn't been undone yet.
+    /// The `EndBlock`s undoing it are skipped, even after the brackets close.
+    skipped_indentation: usize,
+    /// Whether a statement can be followed by a `;`
+    allow_semicolons: bool,
+    /// Whether an indented line starting with an operator continues the
+    /// expression before it. Not the case after a block's condition.
+    allow_continuation_lines: bool,
 }
 
 impl<T: Tokenizer> Parser<T> {
@@ -52,8 +78,44 @@ impl<T: Tokenizer> Parser<T> {
     /// Consumes the next token from the tokenizer.
     pub fn consume(&mut self) -> Token {
         self.look_ahead(1usize);
-        self.lookahead.pop_back()
-            .expect("Unable to queue token via lookahead for consume")
+        let token = self.lookahead.pop_front()
+            .expect("Unable to queue token via lookahead for consume");
+        match token.get_type() {
+            TokenType::BeginBlock | TokenType::EndBlock | TokenType::EOF => {},
+            _ => self.last_location = token.start()
+        }
+        token
+    }
+
+    /// Whether the input, or the current block, has ended.
+    pub fn at_end(&mut self) -> bool {
+        let next_type = self.next_type();
+        next_type == TokenType::EndBlock || next_type == TokenType::EOF
+    }
+
+    /// Gets a `ParseError::EOF` for the input ending at the current token.
+    pub fn eof_error(&self) -> ParseError {
+        ParseError::EOF { location: self.last_location, unclosed: None }
+    }
+
+    /// Gets a `ParseError::EOF` for the input ending before the delimiter
+    /// opened by `opening` was closed by a `closing` token.
+    pub fn unclosed_error(&self, closing: TokenType, opening: &Token)
+                          -> ParseError {
+        ParseError::EOF {
+            location: self.last_location,
+            unclosed: Some((closing, opening.start()))
+        }
+    }
+
+    /// Consumes the `closing` token of a delimiter opened by `opening`, such
+    /// as the `)` of a `(`.
+    pub fn consume_closing(&mut self, closing: TokenType, opening: &Token)
+                           -> Result<Token, ParseError> {
+        if self.at_end() {
+            return Err(self.unclosed_error(closing, opening))
+        }
+        self.consume_type(closing)
     }
 
     /// Consume the next token, returning whether the given rule has been
@@ -88,6 +150,9 @@ impl<T: Tokenizer> Parser<T> {
         debug_assert!(count != 0, "Cannot look ahead 0");
         while count > self.lookahead.len() {
             let next = self.tokenizer.next();
+            if self.skips_bracketed_indentation(&next) {
+                continue
+            }
             if let Some(indent_rule) = self.indent_rules.last().cloned() {
                 match indent_rule {
                     // Ignore indentation until match found
@@ -125,6 +190,49 @@ impl<T: Tokenizer> Parser<T> {
         &self.lookahead[count - 1]
     }
 
+    /// Tracks the brackets opened by a token read from the tokenizer, and
+    /// whether it is indentation inside them, which should be skipped.
+    ///
+    /// Lines inside brackets can be indented further than the line the
+    /// brackets were opened on, but not less: that `EndBlock` isn't skipped,
+    /// so an unclosed bracket is reported at the end of its block.
+    ///
+    /// Parens and square brackets are only counted if bracketed indentation
+    /// is ignored. The braces of a braced block always are, as the braces
+    /// rather than indentation delimit the block.
+    fn skips_bracketed_indentation(&mut self, token: &Token) -> bool {
+        match token.get_type() {
+            TokenType::LeftParen | TokenType::LeftBracket
+                    if self.ignore_bracketed_indentation => {
+                self.bracket_depth += 1;
+                false
+            },
+            TokenType::LeftBrace => {
+                self.bracket_depth += 1;
+                false
+            },
+            TokenType::RightParen | TokenType::RightBracket
+                    if self.ignore_bracketed_indentation
+                        && self.bracket_depth > 0 => {
+                self.bracket_depth -= 1;
+                false
+            },
+            TokenType::RightBrace if self.bracket_depth > 0 => {
+                self.bracket_depth -= 1;
+                false
+            },
+            TokenType::BeginBlock if self.bracket_depth > 0 => {
+                self.skipped_indentation += 1;
+                true
+            },
+            TokenType::EndBlock if self.skipped_indentation > 0 => {
+                self.skipped_indentation -= 1;
+                true
+            },
+            _ => false
+        }
+    }
+
     /// Attempts to match the next token from the tokenizer with the given type.
     pub fn consume_type(&mut self, expected_type: TokenType) -> Result<Token, ParseError> {
         trace!("Consuming type {:?}", expected_type);
@@ -207,11 +315,20 @@ impl<T: Tokenizer> Parser<T> {
                 let consumed = self.consume();
                 NamedTypeParser { }.parse(self, consumed)
             },
+            // `()` is named like the implicit return type of a fn.
+            TokenType::LeftParen => {
+                trace!("Parsing unit type expr");
+                let left_paren = self.consume();
+                try!(self.consume_closing(TokenType::RightParen, &left_paren));
+                Ok(TypeExpression::Named(NamedTypeExpression::new(
+                    Identifier::new(Token::new_ident("()", left_paren.start())))))
+            },
+            TokenType::EOF | TokenType::EndBlock => Err(self.eof_error()),
             _other => {
                 trace!("Invalid token for type expr");
                 // TODO this is also a bad error
                 return Err(ParseError::LazyString(format!(
-                    "Unexpected token {:?} for type expression", next_type)))
+                    "Unexpected token {:?} for type expression", next_type).into()))
             }
         }
     }
@@ -219,21 +336,64 @@ impl<T: Tokenizer> Parser<T> {
     /// Parses any expression with the given precedence.
     ///
     /// This parser will push a `NegateDeindent` rule to the rule stack.
+    ///
+    /// Returns `ParseError::NestingTooDeep` if expressions are nested more
+    /// than the parser's maximum expression depth.
     pub fn expression(&mut self, precedence: Precedence) -> Result<Expression, ParseError> {
-        let (_indented, mut token) = self.consume_indented(IndentationRule::NegateDeindent);
+        if self.expression_depth >= self.max_expression_depth {
+            debug!("Expression nested more than {} deep",
+                self.max_expression_depth);
+            return Err(ParseError::NestingTooDeep(self.peek().clone()))
+        }
+        self.expression_depth += 1;
+        let result = self.nested_expression(precedence);
+        self.expression_depth -= 1;
+        result
+    }
+
+    /// Parses an expression, once we know we aren't nested too deep.
+    fn nested_expression(&mut self, precedence: Precedence) -> Result<Expression, ParseError> {
+        let (_indented, token) = self.consume_indented(IndentationRule::NegateDeindent);
         trace!("Parsing expression(precedence={:?}) with {}", precedence, token);
         if _indented { trace!("Parsing indented expression"); }
-        let token_type = token.get_type();
+        let left = try!(self.prefix_expression(token));
+        trace!("Parsed left expression: {:?}", left);
+        self.infix_expressions(precedence, left)
+    }
+
+    /// Parses the condition of a block, such as an `if` block's.
+    ///
+    /// The indented line after the condition starts the block, so it isn't
+    /// a continuation of the condition even if it starts with `-` or `+`.
+    pub fn block_condition(&mut self) -> Result<Expression, ParseError> {
+        let allowed = mem::replace(&mut self.allow_continuation_lines, false);
+        let result = self.expression(Precedence::Min);
+        self.allow_continuation_lines = allowed;
+        result
+    }
+
+    /// Parses the expression started by a prefix token.
+    ///
+    /// The prefix and infix parsers are kept in separate functions so that
+    /// deeply nested prefix expressions only use the stack space of the
+    /// prefix parsers.
+    fn prefix_expression(&mut self, token: Token) -> Result<Expression, ParseError> {
         use self::TokenType::*;
-        let mut left = try!(match token_type {
-            EOF => Err(ParseError::EOF),
+        match token.get_type() {
+            EOF => Err(self.eof_error()),
 
-            EndBlock => Err(ParseError::EOF),
+            EndBlock => Err(self.eof_error()),
 
             If => IfExpressionParser { }.parse(self, token),
 
             Minus | Plus => UnaryOpExprSymbol { }.parse(self, token),
 
+            LeftParen if self.next_type() == RightParen =>
+                UnitLiteralParser { }.parse(self, token),
+
+            LeftParen if self.at_operator_ref() =>
+                OperatorRefParser { }.parse(self, token),
+
             LeftParen => ParensParser { }.parse(self, token),
 
             Ident => IdentifierParser { }.parse(self, token),
@@ -242,21 +402,26 @@ impl<T: Tokenizer> Parser<T> {
 
             _ => {
                 trace!("Could not find parser");
-                return Err(ParseError::LazyString(format!("Unexpected token {:?}", token)))
+                Err(ParseError::LazyString(format!("Unexpected token {:?}", token).into()))
             }
-        });
-        trace!("Parsed left expression: {:?}", left);
+        }
+    }
+
+    /// Parses any infix operators binding tighter than `precedence` which
+    /// follow the `left` expression.
+    fn infix_expressions(&mut self, precedence: Precedence, mut left: Expression)
+                         -> Result<Expression, ParseError> {
+        use self::TokenType::*;
         while precedence < self.current_precedence() {
             trace!("Consuming a token to determine if there's an infix");
             // We allow indentation before any infix operator in expression!
-            let (_infix_indented, new_token) = self.consume_indented(IndentationRule::NegateDeindent);
-            trace!("Consumed {:?}, indentation: {}", new_token, _infix_indented);
-            token = new_token;
+            let (_infix_indented, token) = self.consume_indented(IndentationRule::NegateDeindent);
+            trace!("Consumed {:?}, indentation: {}", token, _infix_indented);
             let token_type = token.get_type();
             left = try!(match token_type {
                 Equals => AssignmentParser { }.parse(self, left, token),
 
-                Plus | Minus | Star | Slash | Percent =>
+                Plus | Minus | Star | Slash | Percent | DoubleStar =>
                     BinOpExprSymbol { }.parse(self, left, token),
 
                 LeftParen => FnCallParser { }.parse(self, left, token),
@@ -300,6 +465,14 @@ impl<T: Tokenizer> Parser<T> {
                 let token = self.consume();
                 ReturnParser { }.parse(self, token)
             },
+            Assert => {
+                let token = self.consume();
+                AssertParser { }.parse(self, token)
+            },
+            AssertEq => {
+                let token = self.consume();
+                AssertEqParser { }.parse(self, token)
+            },
             Do => {
                 let token = self.consume();
                 DoBlockParser { }.parse(self, token)
@@ -308,6 +481,13 @@ impl<T: Tokenizer> Parser<T> {
                 let token = self.consume();
                 IfBlockParser { }.parse(self, token)
             },
+            // Blocks are begun by the statements and items which own them,
+            // so a statement can't start with an indent.
+            BeginBlock => {
+                let token = self.consume();
+                trace!("Found unexpected indent at {:?}", token.start());
+                Err(ParseError::UnexpectedIndent(token))
+            },
             _ => {
                 trace!("Using expr parser for statement");
                 self.expression(Precedence::Min)
@@ -316,6 +496,61 @@ impl<T: Tokenizer> Parser<T> {
         }
     }
 
+    /// Whether the next token is a `;` ending a statement, if they're
+    /// allowed. It must be on the same line as the end of the statement.
+    pub fn at_semicolon(&mut self) -> bool {
+        if !self.allow_semicolons {
+            return false
+        }
+        let line = self.last_location.line();
+        let next = self.peek();
+        next.get_type() == TokenType::Semicolon && next.start().line() == line
+    }
+
+    /// Whether the next token starts a block: either a `BeginBlock` or a
+    /// `{`.
+    pub fn at_block_start(&mut self) -> bool {
+        let next_type = self.next_type();
+        next_type == TokenType::BeginBlock || next_type == TokenType::LeftBrace
+    }
+
+    /// Consumes the token starting a block, then parses the indented or
+    /// braced block.
+    pub fn start_block(&mut self) -> Result<Block, ParseError> {
+        let token = self.consume();
+        match token.get_type() {
+            TokenType::BeginBlock => self.block(),
+            TokenType::LeftBrace => self.braced_block(&token),
+            other => Err(ParseError::ExpectedToken {
+                expected: TokenType::BeginBlock,
+                got: other,
+                token
+            })
+        }
+    }
+
+    /// Parse a block of code in braces, which gives the same `Block` as
+    /// the indented block of its statements.
+    ///
+    /// Newlines inside the braces are ignored, so statements can be
+    /// separated by `;`s on one line. Assumes the `{` has been consumed.
+    fn braced_block(&mut self, left_brace: &Token) -> Result<Block, ParseError> {
+        let start = self.peek().start();
+        let mut found = Vec::new();
+        while self.next_type() != TokenType::RightBrace {
+            if self.at_end() {
+                return Err(self.unclosed_error(TokenType::RightBrace,
+                                               left_brace))
+            }
+            found.push(try!(self.statement()));
+            if self.next_type() == TokenType::Semicolon {
+                self.consume();
+            }
+        }
+        self.consume();
+        Ok(Block::new(start, found))
+    }
+
     /// Parse a block of code.
     ///
     /// Block parsing assumes the `BeginBlock` token has already been consumed.
@@ -333,6 +568,9 @@ impl<T: Tokenizer> Parser<T> {
             }
             let next_stmt = try!(self.statement());
             found.push(next_stmt);
+            if self.at_semicolon() {
+                self.consume();
+            }
         }
         return Ok(Block::new(start, found))
     }
@@ -346,12 +584,17 @@ impl<T: Tokenizer> Parser<T> {
                 trace!("Parsing a fn");
                 FnDeclarationParser { }.parse(self, token)
             },
+            TokenType::Extern => {
+                trace!("Parsing an extern fn");
+                ExternFnDeclarationParser { }.parse(self, token)
+            },
             TokenType::Typedef => {
                 trace!("Parsing a typedef");
                 TypedefParser { }.parse(self, token)
             },
             _ => {
-                Err(ParseError::LazyString(format!("Unexpected item token {:?}", token_type)))
+                Err(ParseError::LazyString(
+                    format!("Unexpected item token {:?}", token_type).into()))
             }
         }
     }
@@ -373,40 +616,40 @@ impl<T: Tokenizer> Parser<T> {
     }
 
     /// Gets the binary operator used for the given token.
-    pub fn binary_operator(&self,
-                           token_type: TokenType)
+    pub fn binary_operator(&self, token: &Token)
                            -> Result<BinaryOperator, ParseError> {
+        Self::binary_operator_of(token.get_type())
+            .ok_or_else(|| ParseError::unknown_operator(token))
+    }
+
+    /// Gets the binary operator used for tokens of the given type, if any.
+    fn binary_operator_of(token_type: TokenType) -> Option<BinaryOperator> {
         use lex::TokenType::*;
         match token_type {
-            Plus => Ok(BinaryOperator::Addition),
-            Minus => Ok(BinaryOperator::Subtraction),
-            Star => Ok(BinaryOperator::Multiplication),
-            Slash => Ok(BinaryOperator::Division),
-            Percent => Ok(BinaryOperator::Modulus),
-            DoubleEquals => Ok(BinaryOperator::Equality),
-            NotEquals => Ok(BinaryOperator::NonEquality),
-            LeftAngle => Ok(BinaryOperator::LessThan),
-            RightAngle => Ok(BinaryOperator::GreaterThan),
-            LessThanEquals => Ok(BinaryOperator::LessThanEquals),
-            GreaterThanEquals => Ok(BinaryOperator::GreaterThanEquals),
-            _ => Err(ParseError::UnknownOperator {
-                    text: Cow::from(format!("{:?}", token_type)),
-                    token_type
-                })
+            Plus => Some(BinaryOperator::Addition),
+            Minus => Some(BinaryOperator::Subtraction),
+            Star => Some(BinaryOperator::Multiplication),
+            Slash => Some(BinaryOperator::Division),
+            Percent => Some(BinaryOperator::Modulus),
+            DoubleStar => Some(BinaryOperator::Power),
+            DoubleEquals => Some(BinaryOperator::Equality),
+            NotEquals => Some(BinaryOperator::NonEquality),
+            LeftAngle => Some(BinaryOperator::LessThan),
+            RightAngle => Some(BinaryOperator::GreaterThan),
+            LessThanEquals => Some(BinaryOperator::LessThanEquals),
+            GreaterThanEquals => Some(BinaryOperator::GreaterThanEquals),
+            _ => None
         }
     }
 
-    pub fn unary_operator(&self,
-                          token_type: TokenType)
+    /// Gets the unary operator used for the given token.
+    pub fn unary_operator(&self, token: &Token)
                           -> Result<UnaryOperator, ParseError> {
         use lex::TokenType::*;
-        match token_type {
+        match token.get_type() {
             Minus => Ok(UnaryOperator::Negation),
             Plus => Ok(UnaryOperator::Addition),
-            _ => Err(ParseError::UnknownOperator {
-                    text: Cow::from(format!("{:?}", token_type)),
-                    token_type
-                })
+            _ => Err(ParseError::unknown_operator(token))
         }
     }
 
@@ -416,9 +659,77 @@ impl<T: Tokenizer> Parser<T> {
             tokenizer: tokenizer,
             lookahead: VecDeque::new(),
             indent_rules: Vec::new(),
+            expression_depth: 0,
+            max_expression_depth: DEFAULT_MAX_EXPRESSION_DEPTH,
+            last_location: Location::default(),
+            operator_fns: Vec::new(),
+            ignore_bracketed_indentation: false,
+            bracket_depth: 0,
+            skipped_indentation: 0,
+            allow_semicolons: false,
+            allow_continuation_lines: true,
         }
     }
 
+    /// Sets how deeply expressions may be nested before the parser gives
+    /// up with `ParseError::NestingTooDeep`.
+    ///
+    /// Defaults to `DEFAULT_MAX_EXPRESSION_DEPTH`.
+    pub fn set_max_expression_depth(&mut self, max_depth: usize) {
+        self.max_expression_depth = max_depth;
+    }
+
+    /// Sets whether line breaks inside parens and square brackets are
+    /// ignored, so that bracketed expressions can span lines without any
+    /// indentation rules. Newlines outside of brackets still separate
+    /// statements. Braced blocks always ignore indentation.
+    ///
+    /// Defaults to `false`.
+    pub fn set_ignore_bracketed_indentation(&mut self, ignore: bool) {
+        self.ignore_bracketed_indentation = ignore;
+    }
+
+    /// Sets whether each statement can be followed by a `;`, which is
+    /// ignored. Newlines still separate statements, so a `;` can't start a
+    /// statement or be doubled up.
+    ///
+    /// Defaults to `false`.
+    pub fn set_allow_semicolons(&mut self, allow: bool) {
+        self.allow_semicolons = allow;
+    }
+
+    /// Reset this parser to parse from a new tokenizer.
+    ///
+    /// The lookahead and indentation rule buffers are cleared but keep their
+    /// allocations, so a parser can be reused to parse many sources cheaply.
+    pub fn reset(&mut self, tokenizer: T) {
+        self.tokenizer = tokenizer;
+        self.lookahead.clear();
+        self.indent_rules.clear();
+        self.expression_depth = 0;
+        self.last_location = Location::default();
+        self.operator_fns.clear();
+        self.bracket_depth = 0;
+        self.skipped_indentation = 0;
+    }
+
+    /// Records that an operator's fn was referenced, so that it will be
+    /// declared in the unit.
+    pub fn reference_operator_fn(&mut self, operator: BinaryOperator,
+                                 location: Location) {
+        if !self.operator_fns.iter().any(|&(used, _)| used == operator) {
+            self.operator_fns.push((operator, location));
+        }
+    }
+
+    /// Whether the tokens after a `(` are an operator and `)`, referencing
+    /// the operator as a fn.
+    fn at_operator_ref(&mut self) -> bool {
+        let next_type = self.next_type();
+        Self::binary_operator_of(next_type).is_some()
+            && self.look_ahead(2).get_type() == TokenType::RightParen
+    }
+
     /// Parse a program and verify it for errors
     pub fn parse_unit(&mut self) -> Result<Unit, ParseError> {
         let start = self.peek().start();
@@ -430,13 +741,35 @@ impl<T: Tokenizer> Parser<T> {
         }
         let end = self.peek().end();
         trace!("Parsed {} items", items.len());
-        let unit = Unit::new(Span::from(start ..= end), items);
+        // Operator fns are declared first, so they're compiled before use.
+        let mut unit_items = self.operator_fns.drain(..)
+            .map(|(operator, location)| {
+                Item::BlockFnDeclaration(operator_fn_decl(operator, location))
+            })
+            .collect::<Vec<_>>();
+        unit_items.extend(items);
+        let unit = Unit::new(Span::from(start ..= end), unit_items);
         Ok(unit)
     }
 
     /// Get the current precedence
+    ///
+    /// An indented line starting with a binary operator continues the
+    /// expression before it, so the precedence is that of the operator:
+    ///
+    /// ```text
+    /// let sum = x
+    ///     + y
+    /// ```
     fn current_precedence(&mut self) -> Precedence {
-        Precedence::for_token(self.next_type(), false)
+        let next_type = self.next_type();
+        if next_type == TokenType::BeginBlock && self.allow_continuation_lines {
+            let after_indent = self.look_ahead(2).get_type();
+            if Self::binary_operator_of(after_indent).is_some() {
+                return Precedence::for_token(after_indent, false)
+            }
+        }
+        Precedence::for_token(next_type, false)
     }
 }
 
@@ -3,7 +3,7 @@
 use lex::{Token, Tokenizer, TokenType};
 use ast::*;
 use parse::{Parser, ParseResult};
-use parse::parsers::{InfixParser, Precedence};
+use parse::parsers::{InfixParser, Precedence, Associativity};
 
 /// Parses an assignment expresion.
 ///
@@ -20,8 +20,16 @@ impl<T: Tokenizer> InfixParser<Expression, T> for AssignmentParser {
         debug_assert!(_token.get_type() == TokenType::Equals,
             "Assign parser called with non-assign token {:?}", _token);
         let ident = try!(left.expect_identifier());
-        let right_expr = try!(parser.expression(Precedence::Assign));
+        let precedence = Precedence::Assign.for_right_side(
+            InfixParser::<Expression, T>::associativity(self, TokenType::Equals));
+        let right_expr = try!(parser.expression(precedence));
         let right = try!(right_expr.expect_value());
         Ok(Expression::Assignment(Assignment::new(ident, Box::new(right))))
     }
+
+    /// `x = y = 1` is `x = (y = 1)`, which is then rejected because an
+    /// assignment is not a value.
+    fn associativity(&self, _token_type: TokenType) -> Associativity {
+        Associativity::Right
+    }
 }
@@ -12,6 +12,16 @@ use parse::parsers::{InfixParser, Precedence};
 /// foo(bar    :     otherFnCall(),     baz    )
 ///    >^ident ^take ^expr        ^take ^ident ^take
 /// ```
+///
+/// Like the params of a fn declaration, the args can span lines, with an
+/// optional trailing comma:
+///
+/// ```text
+/// foo(
+///     bar: 1,
+///     baz: 2,
+/// )
+/// ```
 #[derive(Debug)]
 pub struct FnCallParser { }
 impl<T: Tokenizer> InfixParser<Expression, T> for FnCallParser {
@@ -25,14 +35,17 @@ impl<T: Tokenizer> InfixParser<Expression, T> for FnCallParser {
 
         let mut call_args = Vec::new();
         let mut arg_name = true;
-        loop {
+        let end = loop {
             if parser.next_type() == TokenType::RightParen {
-                parser.consume();
                 trace!("Function call complete");
-                break
+                break parser.consume().end()
+            }
+            if parser.at_end() {
+                return Err(parser.unclosed_error(TokenType::RightParen, &token))
             }
             if arg_name {
                 trace!("Parsing an argument");
+                parser.apply_indentation(IndentationRule::NegateDeindent);
                 let arg = try!(parser.expression(Precedence::Min));
                 if let Expression::VariableRef(ident) = arg {
                     if parser.next_type() == TokenType::Colon {
@@ -50,10 +63,9 @@ impl<T: Tokenizer> InfixParser<Expression, T> for FnCallParser {
                     }
                 }
                 else {
-                    try!(parser.consume_type_indented(TokenType::RightParen,
-                                                      IndentationRule::NegateDeindent));
-                    trace!("Function call complete");
-                    break
+                    // https://github.com/immington-industries/protosnirk/issues/45
+                    return Err(ParseError::LazyString(
+                        "Non-named params not supported right now".into()))
                 }
                 arg_name = false;
             }
@@ -62,8 +74,7 @@ impl<T: Tokenizer> InfixParser<Expression, T> for FnCallParser {
                                                   IndentationRule::NegateDeindent));
                 arg_name = true;
             }
-        }
-        let end = parser.peek().end();
+        };
         let call = FnCall::new(Span::from(start ..= end), lvalue, call_args);
         Ok(Expression::FnCall(call))
     }
@@ -2,7 +2,7 @@
 
 use lex::{Token, Tokenizer, TokenType};
 use ast::*;
-use parse::{Parser, ParseResult};
+use parse::{Parser, ParseError, ParseResult};
 use parse::parsers::{PrefixParser, Precedence};
 
 /// Parses block and inline forms of prefix expr/block `if`.
@@ -27,6 +27,9 @@ impl<T: Tokenizer> PrefixParser<Expression, T> for IfExpressionParser {
         let start = token.start();
         let condition = try!(parser.expression(Precedence::Min));
         trace!("Parsed if conditional");
+        if parser.at_end() {
+            return Err(ParseError::ExpectedBlockAfterIf(token))
+        }
         try!(parser.consume_type(TokenType::InlineArrow));
         trace!("Consumed inline arrow token");
         let true_expr = try!(parser.expression(Precedence::Min));
@@ -17,18 +17,17 @@ impl<T: Tokenizer> PrefixParser<Expression, T> for LiteralParser {
     fn parse(&self, _parser: &mut Parser<T>, token: Token)
              -> ParseResult<Expression> {
         match token.data() {
-            TokenData::NumberLiteral => {
-                match token.text().parse::<f64>() {
-                    Ok(val) =>
+            // There's no int type yet, so int literals are floats too.
+            TokenData::IntLiteral | TokenData::FloatLiteral => {
+                match float_value(token.text()) {
+                    Some(val) =>
                         Ok(Expression::Literal(
                             Literal::new_float(token, val))),
-                    Err(_) =>
-                        // This is an internal error: tokenizer should've bailed
-                        Err(ParseError::ExpectedToken {
-                            expected: TokenType::Literal,
-                            got: token.get_type(),
-                            token: token
-                        })
+                    // The tokenizer leaves malformed hex literals, such as
+                    // `0x` or `0x1p`, for the parser to report.
+                    None => Err(ParseError::LazyString(format!(
+                        "Invalid number literal {} at {}",
+                        token.text(), token.start()).into()))
                 }
             },
             TokenData::BoolLiteral => {
@@ -47,6 +46,15 @@ impl<T: Tokenizer> PrefixParser<Expression, T> for LiteralParser {
                     })
                 }
             },
+            TokenData::CharLiteral => {
+                match unescape_char(token.text()) {
+                    Some(value) =>
+                        Ok(Expression::Literal(Literal::new_char(token, value))),
+                    None => Err(ParseError::LazyString(format!(
+                        "Invalid char literal {} at {}",
+                        token.text(), token.start()).into()))
+                }
+            },
             TokenData::UnitLiteral => {
                 Ok(Expression::Literal(Literal::new_unit(token)))
             },
@@ -59,3 +67,70 @@ impl<T: Tokenizer> PrefixParser<Expression, T> for LiteralParser {
         }
     }
 }
+
+/// Gets the value of a numeric literal's text.
+///
+/// Besides decimal numbers, this includes hexadecimal numbers such as `0xff`
+/// or `0x1.8p3`, and `inf` and `nan`.
+fn float_value(text: &str) -> Option<f64> {
+    if text.starts_with("0x") || text.starts_with("0X") {
+        hex_float_value(&text[2..])
+    }
+    else {
+        text.parse::<f64>().ok()
+    }
+}
+
+/// Gets the value of the digits of a hexadecimal literal after its `0x`.
+///
+/// The number may have a point, and be followed by a `p` and the power of
+/// two it is multiplied by. Returns `None` if there are no digits, or the
+/// `p` has no power.
+fn hex_float_value(digits: &str) -> Option<f64> {
+    let (mantissa, exponent) = match digits.find(['p', 'P']) {
+        Some(ix) => (&digits[..ix], digits[ix + 1..].parse::<i32>().ok()?),
+        None => (digits, 0)
+    };
+    let (whole, fraction) = match mantissa.find('.') {
+        Some(ix) => (&mantissa[..ix], &mantissa[ix + 1..]),
+        None => (mantissa, "")
+    };
+    if whole.is_empty() && fraction.is_empty() {
+        return None
+    }
+    let mut value = 0f64;
+    for ch in whole.chars().chain(fraction.chars()) {
+        value = value * 16f64 + ch.to_digit(16)? as f64;
+    }
+    // Each digit after the point is another 4 bits of fraction.
+    Some(value * 2f64.powi(exponent - 4 * fraction.len() as i32))
+}
+
+/// Gets the value of a quoted char literal's text, such as `'a'` or `'\n'`.
+///
+/// Returns `None` if the literal is unterminated, empty, has more than one
+/// character, or uses an unknown escape.
+fn unescape_char(text: &str) -> Option<char> {
+    if text.len() < 3 || !text.starts_with('\'') || !text.ends_with('\'') {
+        return None
+    }
+    let mut chars = text[1 .. text.len() - 1].chars();
+    let value = match chars.next() {
+        Some('\\') => match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('\'') => '\'',
+            Some('"') => '"',
+            _ => return None
+        },
+        Some(other) => other,
+        None => return None
+    };
+    if chars.next().is_some() {
+        return None
+    }
+    Some(value)
+}
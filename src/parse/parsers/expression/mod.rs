@@ -5,19 +5,21 @@ mod assignment;
 mod assign_op;
 mod if_expr;
 mod fn_call;
+mod operator_ref;
 
 pub use self::literal::LiteralParser;
 pub use self::identifier::IdentifierParser;
-pub use self::parens::ParensParser;
+pub use self::parens::{ParensParser, UnitLiteralParser};
 pub use self::assignment::AssignmentParser;
 pub use self::assign_op::AssignOpParser;
 pub use self::if_expr::IfExpressionParser;
 pub use self::fn_call::FnCallParser;
+pub use self::operator_ref::{OperatorRefParser, operator_fn_decl};
 
-use lex::{Token, Tokenizer};
+use lex::{Token, Tokenizer, TokenType};
 use parse::{Parser, ParseResult};
 use ast::*;
-use parse::parsers::{Precedence, InfixParser, PrefixParser};
+use parse::parsers::{Precedence, Associativity, InfixParser, PrefixParser};
 
 /// A parser which parses symbols used for binary operators.
 ///
@@ -29,12 +31,19 @@ impl<T: Tokenizer> InfixParser<Expression, T> for BinOpExprSymbol {
     /// Parses a binary operator expression.
     fn parse(&self, parser: &mut Parser<T>,
              left: Expression, token: Token) -> ParseResult<Expression> {
-        let precedence = Precedence::for_token(token.get_type(), false);
+        let associativity = InfixParser::<Expression, T>::associativity(
+            self, token.get_type());
+        let precedence = Precedence::for_token(token.get_type(), false)
+            .for_right_side(associativity);
         let right: Expression = try!(parser.expression(precedence));
-        let bin_operator = try!(parser.binary_operator(token.get_type()));
+        let bin_operator = try!(parser.binary_operator(&token));
         Ok(Expression::BinaryOp(
             BinaryOperation::new(bin_operator, Box::new(left), Box::new(right))))
     }
+
+    fn associativity(&self, token_type: TokenType) -> Associativity {
+        Associativity::for_token(token_type)
+    }
 }
 
 /// Unary operator parser.
@@ -50,7 +59,7 @@ impl<T: Tokenizer> PrefixParser<Expression, T> for UnaryOpExprSymbol {
         let precedence = Precedence::for_token(token.get_type(), true);
         let right_expr = try!(parser.expression(precedence));
         let right_value = try!(right_expr.expect_value());
-        let operator = try!(parser.unary_operator(token.get_type()));
+        let operator = try!(parser.unary_operator(&token));
         Ok(Expression::UnaryOp(UnaryOperation::new(start, operator, Box::new(right_value))))
     }
 }
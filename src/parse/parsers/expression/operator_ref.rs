@@ -0,0 +1,70 @@
+//! Parser for operators referenced as fns, i.e. `(+)`.
+
+use lex::{Location, Token, Tokenizer, TokenType};
+use parse::{Parser, ParseResult, ParseError};
+use ast::*;
+use parse::parsers::PrefixParser;
+
+/// Parses a binary operator in parens into a reference to the operator's fn.
+///
+/// The fn is declared in the unit by `operator_fn_decl`.
+///
+/// # Examples
+/// ```text
+/// (    +    )
+/// ^skip^op  ^skip
+/// ```
+#[derive(Debug)]
+pub struct OperatorRefParser { }
+impl<T: Tokenizer> PrefixParser<Expression, T> for OperatorRefParser {
+    fn parse(&self, parser: &mut Parser<T>, token: Token) -> ParseResult<Expression> {
+        debug_assert!(token.get_type() == TokenType::LeftParen,
+                      "Operator ref parser called with non-left-paren {:?}", token);
+        let op_token = parser.consume();
+        let operator = try!(parser.binary_operator(&op_token));
+        try!(parser.consume_closing(TokenType::RightParen, &token));
+        let fn_name = match operator.fn_name() {
+            Some(fn_name) => fn_name,
+            None => {
+                return Err(ParseError::LazyString(format!(
+                    "Operator {} at {} can't be referenced as a fn, as it \
+                     works on more than one type", op_token.text(),
+                     op_token.start()).into()))
+            }
+        };
+        parser.reference_operator_fn(operator, token.start());
+        Ok(Expression::VariableRef(Identifier::new(
+            Token::new_ident(fn_name, token.start()))))
+    }
+}
+
+/// Creates the declaration of the fn for an operator referenced in a unit.
+///
+/// The fn takes `left` and `right` operands:
+///
+/// ```text
+/// fn (+)(left: float, right: float) -> float => left + right
+/// ```
+pub fn operator_fn_decl(operator: BinaryOperator, start: Location)
+                        -> BlockFnDeclaration {
+    let fn_name = operator.fn_name()
+        .expect("Declaring fn of an operator without one");
+    let ident = |name: &'static str| Identifier::new(
+        Token::new_ident(name, start));
+    let type_expr = |name: &'static str| TypeExpression::Named(
+        NamedTypeExpression::new(ident(name)));
+    let return_ty = if operator.is_ordering() { "bool" } else { "float" };
+
+    let body = Expression::BinaryOp(BinaryOperation::new(operator,
+        Box::new(Expression::VariableRef(ident("left"))),
+        Box::new(Expression::VariableRef(ident("right")))));
+    let block = Block::new(start, vec![Statement::Expression(body)]);
+    BlockFnDeclaration::new(start,
+                            ident(fn_name),
+                            vec![(ident("left"), type_expr("float")),
+                                 (ident("right"), type_expr("float"))],
+                            vec![None, None],
+                            type_expr(return_ty),
+                            true,
+                            block)
+}
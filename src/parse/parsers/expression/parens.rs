@@ -3,7 +3,7 @@
 // This parser will be one of the first to be heavily
 // overloaded (tuple parsing vs expression recedence in expr prefix).
 
-use lex::{Token, Tokenizer, TokenType};
+use lex::{Token, TokenData, Tokenizer, TokenType};
 use parse::{Parser, ParseResult};
 use ast::*;
 use parse::parsers::{PrefixParser, Precedence};
@@ -18,12 +18,31 @@ use parse::parsers::{PrefixParser, Precedence};
 #[derive(Debug)]
 pub struct ParensParser { }
 impl<T: Tokenizer> PrefixParser<Expression, T> for ParensParser {
-    fn parse(&self, parser: &mut Parser<T>, _token: Token) -> ParseResult<Expression> {
-        debug_assert!(_token.get_type() == TokenType::LeftParen,
-                      "Parens parser called with non-left-paren {:?}", _token);
+    fn parse(&self, parser: &mut Parser<T>, token: Token) -> ParseResult<Expression> {
+        debug_assert!(token.get_type() == TokenType::LeftParen,
+                      "Parens parser called with non-left-paren {:?}", token);
         let inner_expr = try!(parser.expression(Precedence::Min));
         let inner = try!(inner_expr.expect_value());
-        try!(parser.consume_type(TokenType::RightParen));
+        try!(parser.consume_closing(TokenType::RightParen, &token));
         Ok(inner)
     }
 }
+
+/// Parses the unit literal `()`
+///
+/// # Examples
+/// ```text
+/// (    )
+/// ^ (skip)
+/// ```
+#[derive(Debug)]
+pub struct UnitLiteralParser { }
+impl<T: Tokenizer> PrefixParser<Expression, T> for UnitLiteralParser {
+    fn parse(&self, parser: &mut Parser<T>, token: Token) -> ParseResult<Expression> {
+        debug_assert!(token.get_type() == TokenType::LeftParen,
+                      "Unit literal parser called with non-left-paren {:?}", token);
+        try!(parser.consume_closing(TokenType::RightParen, &token));
+        let unit = Token::new("()", token.start(), TokenData::UnitLiteral);
+        Ok(Expression::Literal(Literal::new_unit(unit)))
+    }
+}
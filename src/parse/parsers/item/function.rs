@@ -2,8 +2,8 @@
 
 use lex::{Token, Tokenizer, TokenType};
 use ast::*;
-use parse::{Parser, ParseResult, IndentationRule};
-use parse::parsers::PrefixParser;
+use parse::{Parser, ParseResult, ParseError, IndentationRule};
+use parse::parsers::{PrefixParser, Precedence};
 
 /// Parses a function declaration.
 ///
@@ -15,7 +15,20 @@ use parse::parsers::PrefixParser;
 ///     stmt*
 ///
 /// fn foo (bar, baz, \+ bliz) -> int \- \+ stmt* \-
+///
+/// fn foo(bar: float, baz: float = 1)
+///
+/// fn foo(
+///     bar: float,
+///     baz: float,
+/// ) -> float
+///
+/// fn foo(bar: float) -> float => bar * 2
 /// ```
+///
+/// Params may continue on indented lines. Indentation inside the parens
+/// negates the next deindent, so the tokenizer's `EndBlock` for the params
+/// doesn't end the function before its block.
 #[derive(Debug, PartialEq, Clone)]
 pub struct FnDeclarationParser { }
 impl<T: Tokenizer> PrefixParser<Item, T> for FnDeclarationParser {
@@ -25,57 +38,136 @@ impl<T: Tokenizer> PrefixParser<Item, T> for FnDeclarationParser {
         let start = token.start();
         let name = try!(parser.lvalue());
 
-        // Args
+        let (params, defaults) = try!(parse_params(parser));
+        let (return_ty, explicit) = try!(parse_return_type(parser, &name));
 
-        // TODO Eventually params should be a separate parser?
-        // altough the fn signature type parser would be a little different
-        // from the first-class-fn type parser.
+        let block = if parser.next_type() == TokenType::InlineArrow {
+            try!(parse_arrow_body(parser, &name))
+        }
+        else {
+            // This is gonna require a comment in the place of Python's `pass`.
+            try!(parser.start_block())
+        };
+        Ok(Item::BlockFnDeclaration(BlockFnDeclaration::new(
+            start, name, params, defaults, return_ty, explicit, block
+        )))
+    }
+}
 
-        // left paren cannot be indented
-        try!(parser.consume_type(TokenType::LeftParen));
-        // S1 -> ")", done | name, S2
-        // S2 -> ",", S1 | ")", done
-        let mut params = Vec::new();
-        let mut param_name = true;
-        loop {
-            if parser.next_type() == TokenType::RightParen {
-                parser.consume(); // right paren
-                break
-            }
-            // name
-            if param_name {
-                parser.apply_indentation(IndentationRule::NegateDeindent);
-                let name = try!(parser.lvalue());
-                try!(parser.consume_type(TokenType::Colon));
-                let type_ = try!(parser.type_expr());
-                params.push((name, type_));
-                param_name = false;
-            }
-            // comma
-            else {
-                try!(parser.consume_type_indented(TokenType::Comma,
-                                                  IndentationRule::NegateDeindent));
-                param_name = true;
-            }
+/// Parses an external function declaration, which has no body.
+///
+/// # Examples
+/// ```txt
+/// extern fn sqrt(x: float) -> float
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExternFnDeclarationParser { }
+impl<T: Tokenizer> PrefixParser<Item, T> for ExternFnDeclarationParser {
+    fn parse(&self, parser: &mut Parser<T>, token: Token) -> ParseResult<Item> {
+        debug_assert!(token.get_type() == TokenType::Extern,
+            "Unexpected token {:?} to extern fn parser", token);
+        let start = token.start();
+        try!(parser.consume_type(TokenType::Fn));
+        let name = try!(parser.lvalue());
+
+        let (params, defaults) = try!(parse_params(parser));
+        if defaults.iter().any(Option::is_some) {
+            return Err(ParseError::LazyString(format!(
+                "Params of extern fn {} cannot have default values",
+                name.name()).into()))
         }
+        let (return_ty, _explicit) = try!(parse_return_type(parser, &name));
+
+        Ok(Item::ExternFnDeclaration(ExternFnDeclaration::new(
+            start, name, params, return_ty
+        )))
+    }
+}
+
+/// The params of a function and their default values.
+type FnParams = (Vec<(Identifier, TypeExpression)>, Vec<Option<Expression>>);
 
-        // Explicitly differentiating between omitted return type for block fns
-        // This is gonna be `None` for inline fns
-        let (return_ty, explicit) = if parser.next_type() == TokenType::Arrow {
-            parser.consume();
-            (try!(parser.type_expr()), true)
+/// Parses the parenthesized params of a function and their default values.
+fn parse_params<T: Tokenizer>(parser: &mut Parser<T>) -> ParseResult<FnParams> {
+    // TODO Eventually params should be a separate parser?
+    // altough the fn signature type parser would be a little different
+    // from the first-class-fn type parser.
+
+    // left paren cannot be indented
+    let left_paren = try!(parser.consume_type(TokenType::LeftParen));
+    // S1 -> ")", done | name, S2
+    // S2 -> ",", S1 | ")", done
+    let mut params = Vec::new();
+    let mut defaults = Vec::new();
+    let mut param_name = true;
+    loop {
+        if parser.next_type() == TokenType::RightParen {
+            parser.consume(); // right paren
+            break
+        }
+        if parser.at_end() {
+            return Err(parser.unclosed_error(TokenType::RightParen, &left_paren))
+        }
+        // name
+        if param_name {
+            parser.apply_indentation(IndentationRule::NegateDeindent);
+            let name = try!(parser.lvalue());
+            try!(parser.consume_type(TokenType::Colon));
+            let type_ = try!(parser.type_expr());
+            // `= <expr>` gives the param a default value
+            let default = if parser.next_type() == TokenType::Equals {
+                parser.consume();
+                Some(try!(parser.expression(Precedence::Min)))
+            }
+            else {
+                None
+            };
+            params.push((name, type_));
+            defaults.push(default);
+            param_name = false;
         }
+        // comma
         else {
-            (TypeExpression::Named(NamedTypeExpression::new(Identifier::new(
-                Token::new_ident("()",
-                        name.token().start().clone())))), false)
-        };
+            try!(parser.consume_type_indented(TokenType::Comma,
+                                              IndentationRule::NegateDeindent));
+            param_name = true;
+        }
+    }
+    Ok((params, defaults))
+}
 
-        // This is gonna require a comment in the place of Python's `pass`.
-        try!(parser.consume_type(TokenType::BeginBlock));
-        let block = try!(parser.block());
-        Ok(Item::BlockFnDeclaration(BlockFnDeclaration::new(
-            start, name, params, return_ty, explicit, block
-        )))
+/// Parses the `=> expr` body of a function into a block of that expression.
+///
+/// The expression must follow the arrow on the same line: an arrow body is
+/// never empty.
+fn parse_arrow_body<T: Tokenizer>(parser: &mut Parser<T>, name: &Identifier)
+        -> ParseResult<Block> {
+    let arrow = parser.consume();
+    debug_assert!(arrow.get_type() == TokenType::InlineArrow,
+        "Unexpected token {:?} starting arrow body", arrow);
+    if parser.at_end() || parser.next_type() == TokenType::BeginBlock
+        || parser.peek().start().line() != arrow.start().line() {
+        return Err(ParseError::LazyString(format!(
+            "Expected an expression after `=>` in fn {} at {}",
+            name.name(), arrow.start()).into()))
+    }
+    let body = try!(parser.expression(Precedence::Min));
+    Ok(Block::new(body.span().start(), vec![Statement::Expression(body)]))
+}
+
+/// Parses the `-> type` of a function, returning whether it was given
+/// explicitly. Functions without one return `()`.
+fn parse_return_type<T: Tokenizer>(parser: &mut Parser<T>, name: &Identifier)
+        -> ParseResult<(TypeExpression, bool)> {
+    // Explicitly differentiating between omitted return type for block fns
+    // This is gonna be `None` for inline fns
+    if parser.next_type() == TokenType::Arrow {
+        parser.consume();
+        Ok((try!(parser.type_expr()), true))
+    }
+    else {
+        Ok((TypeExpression::Named(NamedTypeExpression::new(Identifier::new(
+            Token::new_ident("()",
+                    name.token().start().clone())))), false))
     }
 }
@@ -1,5 +1,5 @@
 mod function;
 mod typedef;
 
-pub use self::function::FnDeclarationParser;
+pub use self::function::{FnDeclarationParser, ExternFnDeclarationParser};
 pub use self::typedef::TypedefParser;
@@ -8,9 +8,9 @@ pub use self::expression::*;
 pub use self::statement::*;
 pub use self::item::*;
 pub use self::types::*;
-pub use self::precedence::Precedence;
+pub use self::precedence::{Precedence, Associativity};
 
-use lex::{Token, Tokenizer};
+use lex::{Token, Tokenizer, TokenType};
 use parse::{Parser, ParseResult};
 
 // # Note
@@ -28,6 +28,14 @@ pub trait PrefixParser<E, T: Tokenizer> {
 /// Generic parser trait used to parse AST nodes of type E in the infix position.
 pub trait InfixParser<E, T: Tokenizer> {
     fn parse(&self, parser: &mut Parser<T>, left: E, token: Token) -> ParseResult<E>;
+
+    /// How the given operator groups with operators of the same precedence.
+    ///
+    /// Infix parsers should parse their right side at
+    /// `precedence.for_right_side(associativity)`.
+    fn associativity(&self, _token_type: TokenType) -> Associativity {
+        Associativity::Left
+    }
 }
 
 // TODO This can't be implemented until we have a way of knowing when to stop calling
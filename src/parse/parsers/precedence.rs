@@ -22,6 +22,9 @@ pub enum Precedence {
     Modulo,
     /// Negate or positive operator
     NumericPrefix,
+    /// The `**` operator, which binds tighter than negation: `-2 ** 2` is
+    /// `-(2 ** 2)`
+    Power,
     /// The `not` keyword
     NotKeyword,
     /// Parens binder, used for both prefix and infix fns
@@ -50,8 +53,58 @@ impl Precedence {
             },
             Star | Slash => Precedence::MulDiv,
             Percent => Precedence::Modulo,
+            DoubleStar => Precedence::Power,
             LeftParen => Precedence::Paren,
             _ => Precedence::Min
         }
     }
+
+    /// The precedence to parse the right side of an infix operator at.
+    ///
+    /// Left-associative operators stop at the next operator of the same
+    /// precedence, while right-associative ones continue past it.
+    pub fn for_right_side(self, associativity: Associativity) -> Precedence {
+        match associativity {
+            Associativity::Left => self,
+            Associativity::Right => self.lower()
+        }
+    }
+
+    /// The precedence one step below this one.
+    pub fn lower(self) -> Precedence {
+        use self::Precedence::*;
+        match self {
+            Min | Return => Min,
+            Assign => Return,
+            Equality => Assign,
+            EqualityCompare => Equality,
+            AddSub => EqualityCompare,
+            MulDiv => AddSub,
+            Modulo => MulDiv,
+            NumericPrefix => Modulo,
+            Power => NumericPrefix,
+            NotKeyword => Power,
+            Paren => NotKeyword,
+            Max => Paren
+        }
+    }
+}
+
+/// How operators of the same precedence are grouped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Associativity {
+    /// `a - b - c` is `(a - b) - c`
+    Left,
+    /// `a ** b ** c` is `a ** (b ** c)`
+    Right
+}
+
+impl Associativity {
+    /// Source of truth for associativity of infix operators
+    pub fn for_token(token_type: TokenType) -> Associativity {
+        match token_type {
+            TokenType::DoubleStar => Associativity::Right,
+            _ => Associativity::Left
+        }
+    }
 }
@@ -0,0 +1,52 @@
+//! Assert statement parser
+
+use lex::{tokens, Token, Tokenizer, TokenType};
+use ast::*;
+use parse::{Parser, ParseResult};
+use parse::parsers::{PrefixParser, Precedence};
+
+/// Parses assert statements
+///
+/// # Examples
+/// ```text
+/// assert x == 1
+///   ^    ->condition:expression
+/// ```
+#[derive(Debug)]
+pub struct AssertParser { }
+impl<T: Tokenizer> PrefixParser<Statement, T> for AssertParser {
+    fn parse(&self, parser: &mut Parser<T>, token: Token) -> ParseResult<Statement> {
+        debug_assert!(token.text() == tokens::Assert,
+                      "Assert parser called with non-assert {:?}", token);
+        let start = token.start();
+        let condition_expr = try!(parser.expression(Precedence::Return));
+        let condition = try!(condition_expr.expect_value());
+        Ok(Statement::Assert(Assert::new(start, Box::new(condition))))
+    }
+}
+
+/// Parses `assert_eq` statements
+///
+/// # Examples
+/// ```text
+/// assert_eq   (   x + 1   ,   2   )
+///   ^       (skip) ->left (skip) ->right (skip)
+/// ```
+#[derive(Debug)]
+pub struct AssertEqParser { }
+impl<T: Tokenizer> PrefixParser<Statement, T> for AssertEqParser {
+    fn parse(&self, parser: &mut Parser<T>, token: Token) -> ParseResult<Statement> {
+        debug_assert!(token.text() == tokens::AssertEq,
+                      "Assert eq parser called with non-assert_eq {:?}", token);
+        let start = token.start();
+        let paren = try!(parser.consume_type(TokenType::LeftParen));
+        let left_expr = try!(parser.expression(Precedence::Min));
+        let left = try!(left_expr.expect_value());
+        try!(parser.consume_type(TokenType::Comma));
+        let right_expr = try!(parser.expression(Precedence::Min));
+        let right = try!(right_expr.expect_value());
+        let closing = try!(parser.consume_closing(TokenType::RightParen, &paren));
+        Ok(Statement::Assert(Assert::new_equality(
+            start, Box::new(left), Box::new(right), closing.start())))
+    }
+}
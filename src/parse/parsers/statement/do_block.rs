@@ -1,6 +1,6 @@
 //! Block literal `do` statement.
 
-use lex::{Token, Tokenizer, TokenType};
+use lex::{Token, Tokenizer};
 use ast::*;
 use parse::{Parser, ParseResult};
 use parse::parsers::PrefixParser;
@@ -23,9 +23,8 @@ impl<T: Tokenizer> PrefixParser<Statement, T> for DoBlockParser {
         debug_assert!(token.text() == "do",
                       "Invalid token {:?} in DoBlockParser", token);
         let start = token.start();
-        if parser.next_type() == TokenType::BeginBlock {
-            parser.consume();
-            let block = try!(parser.block());
+        if parser.at_block_start() {
+            let block = try!(parser.start_block());
             Ok(Statement::DoBlock(DoBlock::new(start, Box::new(block))))
         }
         else { // Allow for inline form `do <expr>`
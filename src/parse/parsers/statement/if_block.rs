@@ -26,7 +26,7 @@ impl<T: Tokenizer> PrefixParser<Statement, T> for IfBlockParser {
             "Invalid token {:?} in IfBlockParser", token);
         trace!("Parsing conditional of if statement");
         let block_start = token.start();
-        let condition = try!(parser.expression(Precedence::Min));
+        let condition = try!(parser.block_condition());
         trace!("Parsed conditional");
         if parser.peek().get_type() == TokenType::InlineArrow {
             trace!("Next char is =>, doing infix expr");
@@ -36,7 +36,7 @@ impl<T: Tokenizer> PrefixParser<Statement, T> for IfBlockParser {
             try!(parser.consume_type(TokenType::Else));
             if parser.next_type() == TokenType::If {
                 let error = "Cannot have an `else if` via inline if expression";
-                return Err(ParseError::LazyString(error.to_string()))
+                return Err(ParseError::LazyString(error.into()))
             }
             let else_expr = try!(parser.expression(Precedence::Min));
             trace!("Parsed infix if false expr");
@@ -47,8 +47,10 @@ impl<T: Tokenizer> PrefixParser<Statement, T> for IfBlockParser {
             return Ok(Statement::Expression(Expression::IfExpression(if_expr)))
         }
         trace!("Parsing if block");
-        try!(parser.consume_type(TokenType::BeginBlock));
-        let true_block = try!(parser.block());
+        if !parser.at_block_start() {
+            return Err(ParseError::ExpectedBlockAfterIf(token))
+        }
+        let true_block = try!(parser.start_block());
         let first_conditional = Conditional::new(block_start, condition, true_block);
         let mut conditionals = vec![first_conditional];
         loop {
@@ -61,34 +63,38 @@ impl<T: Tokenizer> PrefixParser<Statement, T> for IfBlockParser {
             let cond_start = else_token.start();
             trace!("Got an else token {:?}", else_token);
             // we have else \+ ... so we have an else block
-            if parser.next_type() == TokenType::BeginBlock {
+            if parser.at_block_start() {
                 trace!("Found an empty else, parsing else block");
-                parser.consume();
-                let else_block = try!(parser.block());
+                let else_block = try!(parser.start_block());
                 return Ok(Statement::IfBlock(
                     IfBlock::new(block_start, conditionals, Some(else_block))
                 ))
             }
             // we have else if ... so we have an else if expr
             else if parser.next_type() == TokenType::If {
-                let _if_token = parser.consume();
-                let else_if_condition = try!(parser.expression(Precedence::Min));
+                let if_token = parser.consume();
+                let else_if_condition = try!(parser.block_condition());
                 if parser.next_type() == TokenType::InlineArrow {
                     let error = "Cannot have an inline `else if` via if block";
-                    return Err(ParseError::LazyString(error.to_string()))
+                    return Err(ParseError::LazyString(error.into()))
                 }
                 // Peel off begin block of else if
-                try!(parser.consume_type(TokenType::BeginBlock));
-                let else_if_block = try!(parser.block());
+                if !parser.at_block_start() {
+                    return Err(ParseError::ExpectedBlockAfterIf(if_token))
+                }
+                let else_if_block = try!(parser.start_block());
                 let else_if_conditional = Conditional::new(cond_start,
                                                            else_if_condition,
                                                            else_if_block);
                 conditionals.push(else_if_conditional);
             }
+            else if parser.at_end() {
+                return Err(parser.eof_error())
+            }
             else {
                 return Err(ParseError::LazyString(format!(
                     "Got unexpected token {:?} after an else", parser.peek()
-                )));
+                ).into()));
             }
         }
     }
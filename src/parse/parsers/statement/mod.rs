@@ -1,9 +1,11 @@
 mod do_block;
 mod return_stmt;
+mod assert_stmt;
 mod if_block;
 mod declaration;
 
 pub use self::do_block::DoBlockParser;
 pub use self::return_stmt::ReturnParser;
+pub use self::assert_stmt::{AssertParser, AssertEqParser};
 pub use self::if_block::IfBlockParser;
 pub use self::declaration::DeclarationParser;
@@ -25,7 +25,8 @@ impl<T: Tokenizer> PrefixParser<Statement, T> for ReturnParser {
         if parser.peek_is_newline(&token) {
             return Ok(Statement::Return(Return::new(start, None)))
         }
-        else if parser.peek().get_type() == TokenType::EOF {
+        else if parser.peek().get_type() == TokenType::EOF
+                || parser.at_semicolon() {
             return Ok(Statement::Return(Return::new(start, None)))
         }
         let inner_expr = try!(parser.expression(Precedence::Return));
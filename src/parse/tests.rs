@@ -0,0 +1,730 @@
+//! Tests for parsing source into an AST.
+
+use lex::{IterTokenizer, Location, Token, TokenType};
+use ast::Unit;
+use ast::{Expression, Item, LiteralValue, Statement};
+use parse::{Parser, ParseError, ExpectedNextType};
+
+fn parse(source: &str) -> Result<Unit, ParseError> {
+    Parser::new(IterTokenizer::new(source.chars())).parse_unit()
+}
+
+/// Parses an expression as the body of a fn, and shows how its operators
+/// were grouped, i.e. `1 + 2 * 3` is `(1 + (2 * 3))`.
+fn grouping_of(expr_source: &str) -> String {
+    grouping_parsed_by(|source| {
+        parse(source).expect("Could not parse expression")
+    }, expr_source)
+}
+
+/// Parses with bracketed indentation ignored.
+fn parse_bracketed(source: &str) -> Unit {
+    let mut parser = Parser::new(IterTokenizer::new(source.chars()));
+    parser.set_ignore_bracketed_indentation(true);
+    parser.parse_unit().expect("Could not parse with brackets ignored")
+}
+
+/// Shows the grouping of an expression parsed by `parse`.
+fn grouping_parsed_by<F>(parse: F, expr_source: &str) -> String
+        where F: Fn(&str) -> Unit {
+    fn grouping(expr: &Expression) -> String {
+        match *expr {
+            Expression::BinaryOp(ref bin_op) =>
+                format!("({} {:?} {})", grouping(bin_op.left()),
+                        bin_op.operator(), grouping(bin_op.right())),
+            Expression::UnaryOp(ref un_op) =>
+                format!("({:?} {})", un_op.operator(), grouping(un_op.inner())),
+            Expression::Literal(ref literal) => literal.text().to_string(),
+            Expression::VariableRef(ref var_ref) => var_ref.name().to_string(),
+            ref other => panic!("Unexpected expression {:?}", other)
+        }
+    }
+    let unit = parse(&format!("fn main()\n    {}\n", expr_source));
+    match unit.items()[0] {
+        Item::BlockFnDeclaration(ref block_fn) =>
+            match block_fn.block().stmts().last() {
+                Some(&Statement::Expression(ref expr)) => grouping(expr),
+                other => panic!("Expected an expression, got {:?}", other)
+            },
+        ref other => panic!("Expected a fn declaration, got {:?}", other)
+    }
+}
+
+#[test]
+fn over_indented_statement_is_unexpected_indent() {
+    let source = "fn main()\n    let x = 1\n        let y = 2\n";
+    match parse(source) {
+        Err(ParseError::UnexpectedIndent(token)) => {
+            assert_eq!(token.get_type(), TokenType::BeginBlock);
+            assert_eq!(token.start().line(), 2);
+        },
+        other => panic!("Expected an unexpected indent error, got {:?}", other)
+    }
+}
+
+#[test]
+fn stray_indents_after_any_statement_are_unexpected() {
+    let cases = [
+        ("fn main()\n    let x = 1\n        x\n", (2, 8)),
+        ("fn main() -> float\n    return 1\n        2\n", (2, 8)),
+        ("fn main()\n    let mut x = 1\n    x = 2\n        x = 3\n", (3, 8)),
+        ("fn main()\n    if true\n        let x = 1\n            let y = 2\n",
+         (3, 12)),
+        ("fn main()\n    do\n        let x = 1\n    let y = 2\n            \
+          let z = 3\n", (4, 12)),
+    ];
+    for &(source, (line, column)) in &cases {
+        match parse(source) {
+            Err(ParseError::UnexpectedIndent(token)) =>
+                assert_eq!((token.start().line(), token.start().column()),
+                           (line, column), "Wrong indent for {:?}", source),
+            other => panic!("Expected an unexpected indent in {:?}, got {:?}",
+                            source, other)
+        }
+    }
+    // Unlike a stray indent, an indented line starting with an operator
+    // continues the expression before it.
+    assert!(parse("fn main()\n    let x = 1\n    x\n        - 1\n").is_ok());
+}
+
+#[test]
+fn assignment_as_value_names_expected_and_found() {
+    let source = "fn main()\n    let x = 0\n    let y = -(x = 1)\n";
+    let error = parse(source).expect_err("Parsed assignment as a value");
+    match error {
+        ParseError::ExpectedExpression {
+            expected: ExpectedNextType::Rvalue,
+            got: Expression::Assignment(_)
+        } => {},
+        ref other => panic!("Expected an rvalue error, got {:?}", other)
+    }
+    assert!(error.to_string()
+                 .starts_with("Expected a value, but found an assignment"),
+            "Undescriptive error message: {}", error);
+}
+
+#[test]
+fn reset_parser_parses_same_as_new_parser() {
+    let source = "fn foo(x: float) -> float\n    let y = x * 2\n    y + 1\n";
+    let expected = parse(source).expect("Could not parse source");
+
+    // Leave the parser in the middle of a unit with lookahead.
+    let mut parser = Parser::new(IterTokenizer::new("fn bar(".chars()));
+    parser.peek();
+    assert!(parser.parse_unit().is_err());
+
+    for _ in 0..3 {
+        parser.reset(IterTokenizer::new(source.chars()));
+        let reparsed = parser.parse_unit().expect("Could not reparse source");
+        assert_eq!(reparsed, expected);
+    }
+}
+
+#[test]
+fn deeply_nested_parens_are_too_deep() {
+    let depth = 10_000;
+    let source = format!("fn main()\n    let x = {}1{}\n",
+                         "(".repeat(depth), ")".repeat(depth));
+    match parse(&source) {
+        Err(ParseError::NestingTooDeep(token)) => {
+            assert_eq!(token.get_type(), TokenType::LeftParen);
+        },
+        other => panic!("Expected a nesting error, got {:?}", other)
+    }
+}
+
+#[test]
+fn max_expression_depth_is_configurable() {
+    let source = "fn main()\n    let x = -(-(-1))\n";
+    assert!(parse(source).is_ok());
+
+    let mut parser = Parser::new(IterTokenizer::new(source.chars()));
+    parser.set_max_expression_depth(4);
+    match parser.parse_unit() {
+        Err(ParseError::NestingTooDeep(_)) => {},
+        other => panic!("Expected a nesting error, got {:?}", other)
+    }
+}
+
+#[test]
+fn fn_params_can_span_lines() {
+    let aligned = "fn sum(a: float,\n       b: float,\n       c: float) -> float\n\
+                   \x20   a + b + c\n";
+    let trailing_comma = "fn sum(\n    a: float,\n    b: float,\n    c: float,\n\
+                          ) -> float\n    a + b + c\n";
+    for source in &[aligned, trailing_comma] {
+        let unit = parse(source).expect("Could not parse multi-line params");
+        match unit.items()[0] {
+            Item::BlockFnDeclaration(ref block_fn) => {
+                let names = block_fn.params().iter()
+                    .map(|(name, _)| name.name())
+                    .collect::<Vec<_>>();
+                assert_eq!(names, ["a", "b", "c"]);
+            },
+            ref other => panic!("Expected a fn declaration, got {:?}", other)
+        }
+    }
+}
+
+#[test]
+fn block_value_accounts_for_returns() {
+    let source = "fn value()\n    let x = 1\n    x\n\
+                  \n\
+                  fn returns()\n    return 1\n\
+                  \n\
+                  fn branches_return(x: bool)\n    if x\n        return 1\n\
+                  \x20   else\n        return 2\n\
+                  \n\
+                  fn falls_through(x: bool)\n    if x\n        return 1\n\
+                  \x20   let y = 2\n";
+    let unit = parse(source).expect("Could not parse fns");
+    let blocks = unit.items().iter().map(|item| match *item {
+        Item::BlockFnDeclaration(ref block_fn) => block_fn.block(),
+        ref other => panic!("Expected a fn declaration, got {:?}", other)
+    }).collect::<Vec<_>>();
+
+    let has_value = blocks.iter()
+        .map(|block| block.has_value())
+        .collect::<Vec<_>>();
+    let diverges = blocks.iter()
+        .map(|block| block.diverges())
+        .collect::<Vec<_>>();
+    assert_eq!(has_value, [true, true, true, false]);
+    assert_eq!(diverges, [false, true, true, false]);
+}
+
+#[test]
+fn empty_sources_parse_to_empty_units() {
+    for source in &["", "   \n\n  \t\n", "// just a comment\n\n// and another"] {
+        let unit = parse(source).expect("Could not parse empty source");
+        assert!(unit.items().is_empty(),
+                "Empty source {:?} had items: {:?}", source, unit.items());
+    }
+}
+
+#[test]
+fn power_is_right_associative() {
+    assert_eq!(grouping_of("2 ** 3 ** 2"), "(2 Power (3 Power 2))");
+    assert_eq!(grouping_of("2 * 3 ** 2"), "(2 Multiplication (3 Power 2))");
+    assert_eq!(grouping_of("-2 ** 2"), "(Negation (2 Power 2))");
+}
+
+#[test]
+fn left_associative_operators_group_left() {
+    assert_eq!(grouping_of("1 - 2 - 3"), "((1 Subtraction 2) Subtraction 3)");
+    assert_eq!(grouping_of("8 / 4 * 2"), "((8 Division 4) Multiplication 2)");
+    assert_eq!(grouping_of("1 - 2 + 3 * 4"),
+               "((1 Subtraction 2) Addition (3 Multiplication 4))");
+}
+
+#[test]
+fn right_associative_operators_group_right() {
+    assert_eq!(grouping_of("a ** b ** c ** d"),
+               "(a Power (b Power (c Power d)))");
+    // `x = (y = 1)`, where the inner assignment can't be used as a value.
+    let source = "fn main()\n    let mut x = 0\n    let mut y = 0\n    x = y = 1\n";
+    match parse(source) {
+        Err(ParseError::ExpectedExpression {
+            expected: ExpectedNextType::Rvalue,
+            got: Expression::Assignment(_)
+        }) => {},
+        other => panic!("Expected chained assignment to be an rvalue error, \
+                         got {:?}", other)
+    }
+}
+
+#[test]
+fn unclosed_paren_is_eof_at_opening_paren() {
+    let sources = ["fn main()\n    let x = (1 + 2\n",
+                   "fn main()\n    let x = (1 + 2\n\nfn other()\n    let y = 1\n",
+                   "fn main()\n    foo(x: 1\n",
+                   "fn main(x: float"];
+    let expected_locations = [(1, 12), (1, 12), (1, 7), (0, 7)];
+    for (source, &(line, column)) in sources.iter().zip(&expected_locations) {
+        match parse(source) {
+            Err(ParseError::EOF {
+                unclosed: Some((TokenType::RightParen, opened_at)), ..
+            }) => {
+                assert_eq!((opened_at.line(), opened_at.column()), (line, column),
+                           "Wrong opening paren location in {:?}", source);
+            },
+            other => panic!("Expected an unclosed paren error for {:?}, got {:?}",
+                            source, other)
+        }
+    }
+    let error = parse(sources[0]).expect_err("Parsed unclosed paren");
+    assert_eq!(error.to_string(),
+               "Unexpected end of input after line 1, column 17: expected \
+                RightParen to close the delimiter opened at line 1, column 12");
+}
+
+#[test]
+fn input_ending_mid_construct_is_an_error() {
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    // (source, whether the error should be `ParseError::EOF`)
+    let cases = [
+        // Mid expression
+        ("fn main()\n    let x = 1 *", true),
+        ("fn main()\n    let x =", true),
+        ("fn main()\n    let y = if x => 1 else", true),
+        // Mid block
+        ("fn main()\n    do\n", true),
+        ("fn main()\n    let", false),
+        // Mid param list
+        ("fn main(a: float,", true),
+        ("fn main(a:", true),
+        ("fn main() ->", true),
+        ("extern fn", false),
+        // Mid if
+        ("fn main()\n    if x", false),
+        ("fn main()\n    if x\n        y\n    else", true),
+        ("fn main()\n    if x\n        y\n    else if", true),
+        // Mid call
+        ("fn main()\n    foo(x: 1,", true),
+        ("typedef A =", true),
+    ];
+    for &(source, expect_eof) in &cases {
+        // Parse on another thread so a parser stuck in a loop fails the test
+        // instead of hanging it.
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || sender.send(parse(source)));
+        let result = receiver.recv_timeout(Duration::from_secs(5))
+            .unwrap_or_else(|_| panic!("Parser hung or panicked on {:?}", source));
+        match result {
+            Err(ParseError::EOF { .. }) => {},
+            Err(ref error) if !expect_eof => {
+                assert!(!error.to_string().is_empty());
+            },
+            other => panic!("Expected an EOF error for {:?}, got {:?}",
+                            source, other)
+        }
+    }
+}
+
+#[test]
+fn if_without_block_is_an_error_at_the_if() {
+    let cases = [
+        ("fn main()\n    if x", (1, 4)),
+        ("fn main()\n    if x\n    let y = 1\n", (1, 4)),
+        ("fn main()\n    if x\n        y\n    else if y", (3, 9)),
+        ("fn main()\n    let y = if x", (1, 12)),
+    ];
+    for &(source, (line, column)) in &cases {
+        match parse(source) {
+            Err(ParseError::ExpectedBlockAfterIf(ref token)) => {
+                assert_eq!(token.get_type(), TokenType::If);
+                assert_eq!((token.start().line(), token.start().column()),
+                           (line, column), "Wrong `if` for {:?}", source);
+            },
+            other => panic!("Expected a missing block error for {:?}, got {:?}",
+                            source, other)
+        }
+    }
+}
+
+#[test]
+fn call_args_are_normalized_in_order() {
+    fn call_args(call_source: &str) -> Vec<(Option<String>, &'static str)> {
+        let unit = parse(&format!("fn main()\n    {}\n", call_source))
+            .expect("Could not parse call");
+        match unit.items()[0] {
+            Item::BlockFnDeclaration(ref block_fn) =>
+                match block_fn.block().stmts()[0] {
+                    Statement::Expression(Expression::FnCall(ref call)) =>
+                        call.arg_exprs().iter()
+                            .map(|&(name, expr)| {
+                                (name.map(str::to_string), expr.description())
+                            })
+                            .collect(),
+                    ref other => panic!("Expected a call, got {:?}", other)
+                },
+            ref other => panic!("Expected a fn declaration, got {:?}", other)
+        }
+    }
+    assert_eq!(call_args("foo()"), vec![]);
+    assert_eq!(call_args("foo(y: 1 + 2, x: bar(z: 3))"), vec![
+        (Some("y".to_string()), "a binary operation"),
+        (Some("x".to_string()), "a function call"),
+    ]);
+
+    // Unnamed arguments aren't supported yet, rather than being dropped.
+    for source in &["foo(x)", "foo(1 + 2)"] {
+        assert!(parse(&format!("fn main()\n    {}\n", source)).is_err(),
+                "Parsed unnamed argument in {:?}", source);
+    }
+}
+
+#[test]
+fn call_args_can_span_lines() {
+    let aligned = "fn main()\n    foo(a: 1,\n        b: bar(c: 2))\n    x\n";
+    let trailing_comma = "fn main()\n    foo(\n        a: 1,\n        \
+                          b: bar(\n            c: 2,\n        ),\n    )\n    x\n";
+    for source in &[aligned, trailing_comma] {
+        let unit = parse(source).expect("Could not parse multi-line call");
+        match unit.items()[0] {
+            Item::BlockFnDeclaration(ref block_fn) => {
+                let stmts = block_fn.block().stmts();
+                assert_eq!(stmts.len(), 2, "Call swallowed the next stmt");
+                match stmts[0] {
+                    Statement::Expression(Expression::FnCall(ref call)) => {
+                        let args = call.arg_exprs().iter()
+                            .map(|&(name, expr)| (name, expr.description()))
+                            .collect::<Vec<_>>();
+                        assert_eq!(args, vec![
+                            (Some("a"), "a literal"),
+                            (Some("b"), "a function call"),
+                        ]);
+                    },
+                    ref other => panic!("Expected a call, got {:?}", other)
+                }
+            },
+            ref other => panic!("Expected a fn declaration, got {:?}", other)
+        }
+    }
+}
+
+#[test]
+fn line_breaks_inside_parens_are_ignored() {
+    let grouping_of = |source| grouping_parsed_by(parse_bracketed, source);
+    assert_eq!(grouping_of("(1 +\n        2) * 3"),
+               "((1 Addition 2) Multiplication 3)");
+    assert_eq!(grouping_of("(1\n        + 2) * 3"),
+               "((1 Addition 2) Multiplication 3)");
+    assert_eq!(grouping_of("(1 +\n        (2\n            * 3)\n        ) - 4"),
+               "((1 Addition (2 Multiplication 3)) Subtraction 4)");
+
+    // Newlines outside of the parens still end statements.
+    let source = "fn main()\n    let x = (1\n            + 2)\n    let y = x\n\
+                  \x20   let z = (x -\n        y)\n    z\n";
+    let unit = parse_bracketed(source);
+    match unit.items()[0] {
+        Item::BlockFnDeclaration(ref block_fn) => {
+            let stmts = block_fn.block().stmts().iter()
+                .map(|stmt| stmt.span().start().line())
+                .collect::<Vec<_>>();
+            assert_eq!(stmts, [1, 3, 4, 6]);
+        },
+        ref other => panic!("Expected a fn declaration, got {:?}", other)
+    }
+
+    // Lines starting with an operator continue the expression anyway, so
+    // close the parens on the next line instead.
+    let source = "fn main()\n    let x = (1 + 2\n        ) * 3\n";
+    parse_bracketed(source);
+    assert!(parse(source).is_err(),
+            "Parsed multi-line parens with their indentation");
+}
+
+#[test]
+fn line_breaks_inside_square_brackets_are_ignored() {
+    use lex::TokenType::*;
+    // Nothing parses `[...]` yet, so this checks the tokens the parser reads.
+    fn token_types(source: &str, ignore: bool) -> Vec<TokenType> {
+        let mut parser = Parser::new(IterTokenizer::new(source.chars()));
+        parser.set_ignore_bracketed_indentation(ignore);
+        let mut types = Vec::new();
+        while parser.next_type() != EOF {
+            types.push(parser.consume().get_type());
+        }
+        types
+    }
+    let source = "fn main()
+    let x = [a,
+        b,
+            (c
+    )]
+    x
+";
+    assert_eq!(token_types(source, true), vec![
+        Fn, Ident, LeftParen, RightParen, BeginBlock,
+        Let, Ident, Equals,
+        LeftBracket, Ident, Comma, Ident, Comma, LeftParen, Ident, RightParen,
+        RightBracket,
+        Ident, EndBlock
+    ]);
+    assert!(token_types(source, false).contains(&BeginBlock),
+            "Skipped the indentation inside brackets by default");
+}
+
+#[test]
+fn trailing_semicolons_are_ignored_when_allowed() {
+    fn parse_with_semicolons(source: &str) -> Result<Unit, ParseError> {
+        let mut parser = Parser::new(IterTokenizer::new(source.chars()));
+        parser.set_allow_semicolons(true);
+        parser.parse_unit()
+    }
+    let plain = "fn f(x: float) -> float\n\
+                 \x20   let mut y = x + 1\n\
+                 \x20   y = y + 2\n\
+                 \x20   if y > 3\n\
+                 \x20       return y\n\
+                 \x20   assert y < 4\n\
+                 \x20   y\n\
+                 fn g()\n\
+                 \x20   return\n";
+    let with_semicolons = "fn f(x: float) -> float\n\
+                           \x20   let mut y = x + 1;\n\
+                           \x20   y = y + 2;\n\
+                           \x20   if y > 3\n\
+                           \x20       return y;\n\
+                           \x20   assert y < 4;\n\
+                           \x20   y;\n\
+                           fn g()\n\
+                           \x20   return;\n";
+    // The semicolons move the character indices of later tokens, so the
+    // ASTs are compared by their lines and columns.
+    let expected = format!("{:?}", parse(plain)
+        .expect("Could not parse without semicolons").items());
+    let parsed = parse_with_semicolons(with_semicolons)
+        .expect("Could not parse with semicolons");
+    assert_eq!(format!("{:?}", parsed.items()), expected);
+    let parsed = parse_with_semicolons(plain)
+        .expect("Could not parse without semicolons");
+    assert_eq!(format!("{:?}", parsed.items()), expected);
+
+    assert!(parse(with_semicolons).is_err(),
+            "Parsed semicolons without allowing them");
+    for source in &["fn main()\n    let x = 1;;\n",
+                    "fn main()\n    ;let x = 1\n",
+                    "fn main()\n    let x = 1\n    ;\n"] {
+        assert!(parse_with_semicolons(source).is_err(),
+                "Parsed misplaced semicolon in {:?}", source);
+    }
+}
+
+#[test]
+fn arrow_fn_body_is_a_block_of_one_expression() {
+    let unit = parse("fn none() => ()\nfn double(x: float) -> float => x * 2\n")
+        .expect("Could not parse arrow fns");
+    let bodies = unit.items().iter()
+        .map(|item| match *item {
+            Item::BlockFnDeclaration(ref block_fn) =>
+                block_fn.block().stmts().iter()
+                    .map(|stmt| match *stmt {
+                        Statement::Expression(ref expr) => expr.description(),
+                        ref other => panic!("Expected an expression, got {:?}",
+                                            other)
+                    })
+                    .collect::<Vec<_>>(),
+            ref other => panic!("Expected a fn declaration, got {:?}", other)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(bodies, vec![vec!["a literal"], vec!["a binary operation"]]);
+
+    for source in &["fn none() =>\n", "fn none() =>\nfn main()\n    none()\n",
+                    "fn none() =>\n    ()\n"] {
+        assert!(parse(source).is_err(), "Parsed empty arrow body in {:?}", source);
+    }
+}
+
+#[test]
+fn operator_refs_declare_operator_fns_once() {
+    let unit = parse("fn main()\n    let a = (+)\n    let b = (<=)\n    \
+                      let c = (+)\n    let d = (-1)\n")
+        .expect("Could not parse operator refs");
+    let names = unit.items().iter()
+        .map(|item| match *item {
+            Item::BlockFnDeclaration(ref block_fn) => block_fn.name(),
+            ref other => panic!("Expected a fn declaration, got {:?}", other)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["(+)", "(<=)", "main"]);
+
+    assert!(parse("fn main()\n    let eq = (==)\n").is_err(),
+            "Referenced `==` as a fn");
+}
+
+/// Asserts the grouping of each expression in a table of
+/// `(source, grouping)` pairs, listing every expression which was grouped
+/// differently.
+fn assert_groupings(table: &[(&str, &str)]) {
+    let mismatches = table.iter()
+        .map(|&(source, expected)| (source, expected, grouping_of(source)))
+        .filter(|&(_, expected, ref found)| expected != found)
+        .map(|(source, expected, found)| {
+            format!("`{}`: expected {}, got {}", source, expected, found)
+        })
+        .collect::<Vec<_>>();
+    assert!(mismatches.is_empty(),
+            "Expressions were grouped wrongly:\n{}", mismatches.join("\n"));
+}
+
+#[test]
+fn operator_precedence_table() {
+    assert_groupings(&[
+        // Arithmetic
+        ("1 + 2 * 3", "(1 Addition (2 Multiplication 3))"),
+        ("1 * 2 + 3", "((1 Multiplication 2) Addition 3)"),
+        ("1 - 2 / 3", "(1 Subtraction (2 Division 3))"),
+        ("a * b % c", "(a Multiplication (b Modulus c))"),
+        ("a % b * c", "((a Modulus b) Multiplication c)"),
+        ("-a % b", "((Negation a) Modulus b)"),
+        ("-a * b", "((Negation a) Multiplication b)"),
+        ("a * b ** c", "(a Multiplication (b Power c))"),
+        ("a - -b", "(a Subtraction (Negation b))"),
+        // Comparison
+        ("a + 1 < b * 2", "((a Addition 1) LessThan (b Multiplication 2))"),
+        ("a >= b - 1", "(a GreaterThanEquals (b Subtraction 1))"),
+        ("a ** 2 > b % 3", "((a Power 2) GreaterThan (b Modulus 3))"),
+        ("a <= -b", "(a LessThanEquals (Negation b))"),
+        // Equality
+        ("a < b == c > d", "((a LessThan b) Equality (c GreaterThan d))"),
+        ("a == b + 1", "(a Equality (b Addition 1))"),
+        ("a != b * c", "(a NonEquality (b Multiplication c))"),
+        ("a == b != c", "((a Equality b) NonEquality c)"),
+        ("a + b == c - d", "((a Addition b) Equality (c Subtraction d))"),
+    ]);
+}
+
+#[test]
+fn braced_blocks_parse_like_indented_blocks() {
+    /// Debug output of the fn's block, without the locations of its tokens.
+    fn block_of(source: &str) -> String {
+        let unit = parse(source).expect("Could not parse block");
+        let block = match unit.items()[0] {
+            Item::BlockFnDeclaration(ref block_fn) =>
+                format!("{:?}", block_fn.block()),
+            ref other => panic!("Expected a fn declaration, got {:?}", other)
+        };
+        let mut stripped = String::new();
+        for (ix, part) in block.split("(line: ").enumerate() {
+            if ix == 0 {
+                stripped.push_str(part);
+            }
+            else {
+                let end = part.find(')').expect("Unclosed location");
+                stripped.push_str(&part[end + 1..]);
+            }
+        }
+        stripped
+    }
+    let indented = block_of("fn f(x: float) -> float\n\
+                             \x20   let mut y = x + 1\n\
+                             \x20   if y > 3\n\
+                             \x20       y = 3\n\
+                             \x20   else\n\
+                             \x20       do\n\
+                             \x20           y = y - 1\n\
+                             \x20   y\n");
+    let braced = block_of("fn f(x: float) -> float {\n\
+                           \x20   let mut y = x + 1\n\
+                           \x20   if y > 3 {\n\
+                           \x20       y = 3\n\
+                           \x20   } else {\n\
+                           \x20       do {\n\
+                           \x20           y = y - 1\n\
+                           \x20       }\n\
+                           \x20   }\n\
+                           \x20   y\n\
+                           }\n");
+    assert_eq!(braced, indented);
+    let inline = block_of("fn f(x: float) -> float { let mut y = x + 1; \
+                           if y > 3 { y = 3 } else { do { y = y - 1 } }; y }\n");
+    assert_eq!(inline, indented);
+
+    match parse("fn main() {\n    let x = 1\n") {
+        Err(ParseError::EOF { unclosed: Some((TokenType::RightBrace, _)), .. }) => {},
+        other => panic!("Expected an unclosed brace, got {:?}", other)
+    }
+}
+
+#[test]
+fn operator_led_lines_continue_the_expression() {
+    assert_eq!(grouping_of("a\n        + b\n        + c * d\n        - e"),
+               "(((a Addition b) Addition (c Multiplication d)) Subtraction e)");
+    assert_eq!(grouping_of("a * b\n        + c\n            * d"),
+               "((a Multiplication b) Addition (c Multiplication d))");
+
+    let source = "fn main()\n\
+                  \x20   let sum = 1\n\
+                  \x20       + 2\n\
+                  \x20       + 3\n\
+                  \x20   if sum > 2\n\
+                  \x20       -sum\n\
+                  \x20   sum\n";
+    let unit = parse(source).expect("Could not parse continuation lines");
+    let stmts = match unit.items()[0] {
+        Item::BlockFnDeclaration(ref block_fn) => block_fn.block().stmts(),
+        ref other => panic!("Expected a fn declaration, got {:?}", other)
+    };
+    assert_eq!(stmts.len(), 3, "{:?}", stmts);
+    match stmts[1] {
+        Statement::IfBlock(ref if_block) =>
+            assert_eq!(if_block.conditionals()[0].block().stmts().len(), 1),
+        ref other => panic!("Expected an if block, got {:?}", other)
+    }
+}
+
+#[test]
+fn special_and_hex_floats_have_their_values() {
+    let source = "fn main()\n\
+                  \x20   let a = inf\n\
+                  \x20   let b = 0x1.8p3\n\
+                  \x20   let c = 0X.8P-1\n\
+                  \x20   let d = 0xff\n\
+                  \x20   let e = nan\n";
+    let unit = parse(source).expect("Could not parse special floats");
+    let values = match unit.items()[0] {
+        Item::BlockFnDeclaration(ref block_fn) =>
+            block_fn.block().stmts().iter().map(|stmt| match *stmt {
+                Statement::Declaration(ref decl) => match *decl.value() {
+                    Expression::Literal(ref literal) => match *literal.value() {
+                        LiteralValue::Float(value) => value,
+                        ref other => panic!("Expected a float, got {:?}", other)
+                    },
+                    ref other => panic!("Expected a literal, got {:?}", other)
+                },
+                ref other => panic!("Expected a declaration, got {:?}", other)
+            }).collect::<Vec<_>>(),
+        ref other => panic!("Expected a fn declaration, got {:?}", other)
+    };
+    let bits = values[..4].iter().map(|value| value.to_bits())
+        .collect::<Vec<_>>();
+    assert_eq!(bits, vec![0x7FF0_0000_0000_0000, 12f64.to_bits(),
+                          0.25f64.to_bits(), 255f64.to_bits()]);
+    assert!(values[4].is_nan());
+}
+
+#[test]
+fn malformed_hex_literals_are_parse_errors() {
+    for literal in &["0x", "0x1p", "0xffp", "0x.p-", "0x1.p+"] {
+        let source = format!("fn main()\n    let x = {}\n", literal);
+        match parse(&source) {
+            Err(ParseError::LazyString(text)) =>
+                assert_eq!(text, format!("Invalid number literal {} at \
+                                          line 1, column 12", literal)),
+            other => panic!("Expected an invalid literal error for {}, got {:?}",
+                            literal, other)
+        }
+    }
+}
+
+#[test]
+fn inf_and_nan_cannot_be_variable_names() {
+    // They're float literals, like `true` and `false` are bool literals.
+    for name in &["inf", "nan"] {
+        let source = format!("fn main()\n    let {} = 2\n", name);
+        match parse(&source) {
+            Err(ParseError::ExpectedToken { expected, got, ref token }) => {
+                assert_eq!((expected, got), (TokenType::Ident, TokenType::Literal));
+                assert_eq!(token.text(), *name);
+            },
+            other => panic!("Expected {} to not be a name, got {:?}", name, other)
+        }
+    }
+}
+
+#[test]
+fn unknown_operator_errors_show_the_token_text() {
+    let parser = Parser::new(IterTokenizer::new("".chars()));
+    let token = Token::new_ident("mod", Location::default());
+    match parser.binary_operator(&token) {
+        Err(error @ ParseError::UnknownOperator { .. }) =>
+            assert_eq!(error.to_string(), "Unknown operator mod"),
+        other => panic!("Expected an unknown operator error, got {:?}", other)
+    }
+}
+
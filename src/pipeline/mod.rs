@@ -2,11 +2,14 @@
 
 use lex::IterTokenizer;
 use parse::{Parser, ParseError};
-use ast::{Unit, visit::UnitVisitor};
+use ast::{Unit, visit::{UnitVisitor, AnalysisVisitor}};
 use identify::{
     NameScopeBuilder, TypeScopeBuilder, ASTIdentifier, ASTTypeChecker, TypeGraph};
-use check::{ErrorCollector, TypeConcretifier, TypeMapping};
-use compile::{ModuleCompiler, SimpleModuleProvider};
+use check::{CheckerError, DefaultIdChecker, ErrorCollector,
+            LocalReferenceChecker, TypeConcretifier, TypeMapping};
+use lint::{SelfAssignmentChecker, FloatEqualityChecker, EmptyBlockChecker};
+use compile::{ModuleCompiler, ModuleProvider, SimpleModuleProvider,
+              FloatComparisons, DEFAULT_PANIC_HANDLER};
 use llvm::{Context, Builder};
 
 use std::collections::HashMap;
@@ -14,9 +17,12 @@ use std::fs::File;
 use std::path::Path;
 use std::str::Chars;
 use std::io::{self, Read};
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 pub enum CompilationError {
+    /// The source could not be parsed.
+    ParseError(ParseError),
     IdentificationError {
         unit: Unit,
         name_builder: NameScopeBuilder,
@@ -31,31 +37,175 @@ pub enum CompilationError {
     }
 }
 
+impl From<ParseError> for CompilationError {
+    fn from(error: ParseError) -> CompilationError {
+        CompilationError::ParseError(error)
+    }
+}
+
+/// Runs the whole pipeline on `source`, giving the IR of the compiled
+/// module or the first pass's errors.
+///
+/// The unit is compiled without optimizations or a prelude, in a `Context`
+/// which is dropped once its IR has been printed. Use a `Runner` and
+/// `CompileRunner` to configure the passes or keep the module.
+pub fn compile_source_to_ir(source: &str, name: &str)
+                            -> Result<String, CompilationError> {
+    let identify = try!(Runner::from_string(source, name.to_string()).parse());
+    let checked = try!(try!(identify.identify()).check());
+    let context = Context::new();
+    let provider = CompileRunner::new(&context).compile(checked, false);
+    Ok(provider.module().print_to_string())
+}
+
+/// Parses, identifies and checks test source, panicking if any pass fails.
+#[cfg(test)]
+pub fn check_source(source: &str) -> CheckedUnit {
+    check_runner(Runner::from_string(source, "test".into()))
+}
+
+/// Checks the source of a `Runner` which was set up by a test, panicking if
+/// any pass fails.
+#[cfg(test)]
+pub fn check_runner(runner: Runner) -> CheckedUnit {
+    runner
+        .parse().expect("Could not parse test source")
+        .identify().expect("Could not identify test source")
+        .check().expect("Could not check test source")
+}
+
+/// Functions available to every unit compiled with a prelude.
+///
+/// They're only declared: embedders need to link in their definitions.
+pub const PRELUDE: &str = "\
+extern fn print(value: float)
+
+extern fn sqrt(x: float) -> float
+
+extern fn floor(x: float) -> float
+";
+
+/// How long each pass of the pipeline took.
+///
+/// Recorded when the pipeline is started with `Runner::with_timings`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PassTimings {
+    /// Parsing the source into a `Unit`.
+    pub parse: Duration,
+    /// Giving the unit's names and types `ScopedId`s.
+    pub identify: Duration,
+    /// Building the type inference graph.
+    pub typecheck: Duration,
+    /// Inferring a concrete type for each expression.
+    pub concretify: Duration,
+    /// Compiling the unit into an LLVM module.
+    pub compile: Duration
+}
+
+impl PassTimings {
+    /// Gets the name and duration of each pass, in pipeline order.
+    pub fn passes(&self) -> [(&'static str, Duration); 5] {
+        [("parse", self.parse),
+         ("identify", self.identify),
+         ("typecheck", self.typecheck),
+         ("concretify", self.concretify),
+         ("compile", self.compile)]
+    }
+
+    /// Total time taken by all of the passes.
+    pub fn total(&self) -> Duration {
+        self.passes().iter().map(|&(_, duration)| duration).sum()
+    }
+}
+
 #[derive(Debug)]
 pub struct Runner<'input> {
     iter: IterTokenizer<Chars<'input>>,
-    name: String
+    name: String,
+    prelude: bool,
+    timings: Option<PassTimings>
 }
 
 impl<'input> Runner<'input> {
     pub fn from_string(text: &'input str, name: String) -> Runner<'input> {
         Runner {
             iter: IterTokenizer::new(text.chars()),
-            name
+            name,
+            prelude: false,
+            timings: None
         }
     }
+
+    /// Makes the fns in the `PRELUDE` callable from the unit without
+    /// declaring them.
+    pub fn with_prelude(mut self) -> Runner<'input> {
+        self.prelude = true;
+        self
+    }
+
+    /// Records how long each pass takes, which can be read from the
+    /// `CheckedUnit` and `CompileRunner`.
+    pub fn with_timings(mut self) -> Runner<'input> {
+        self.timings = Some(PassTimings::default());
+        self
+    }
+
     pub fn from_file<P: AsRef<Path>>(path: P, buffer: &'input mut String)
                                      -> io::Result<Runner<'input>> {
         let name = path.as_ref().to_string_lossy().into();
-        let mut file = try!(File::open(path));
-        try!(file.read_to_string(buffer));
+        let file = try!(File::open(path));
+        Runner::from_reader(file, name, buffer)
+    }
+
+    /// Reads all of the source from `reader` into `buffer`, such as from
+    /// stdin or an in-memory reader.
+    ///
+    /// Fails if the reader does, or if the source isn't valid UTF-8.
+    pub fn from_reader<R: Read>(mut reader: R,
+                                name: String,
+                                buffer: &'input mut String)
+                                -> io::Result<Runner<'input>> {
+        try!(reader.read_to_string(buffer));
         Ok(Runner::from_string(buffer, name))
     }
 
     pub fn parse(self) -> Result<IdentifyRunner, ParseError> {
+        let start = Instant::now();
         let mut parser = Parser::new(self.iter);
-        let unit = try!(parser.parse_unit());
-        Ok(IdentifyRunner::new(unit, self.name))
+        let mut unit = try!(parser.parse_unit());
+        let mut clashes = Vec::new();
+        if self.prelude {
+            let prelude = Parser::new(IterTokenizer::new(PRELUDE.chars()))
+                .parse_unit()
+                .expect("Could not parse the prelude");
+            clashes = unit.merge(prelude);
+        }
+        let mut timings = self.timings;
+        if let Some(ref mut timings) = timings {
+            timings.parse = start.elapsed();
+        }
+        let mut runner = IdentifyRunner::new(unit, self.name, timings);
+        // Clashes are reported with the errors found while identifying the
+        // unit, as redeclaring an item is an identify error.
+        for (declared, prelude_declared) in clashes {
+            runner.errors.add_error(CheckerError::new(
+                vec![declared.span()],
+                format!("{} is declared by the prelude, so it can't be \
+                         declared in the unit", declared.name()))
+                .with_note_at(prelude_declared.span(),
+                              "It is declared here in the prelude"));
+        }
+        Ok(runner)
+    }
+
+    /// Parses the source and prints its AST, without running any other
+    /// passes.
+    ///
+    /// The AST is printed with its (pretty) `Debug` format, so IDs have not
+    /// been set.
+    pub fn emit_ast(self) -> Result<String, ParseError> {
+        let runner = try!(self.parse());
+        Ok(format!("{:#?}", runner.unit))
     }
 }
 
@@ -66,27 +216,67 @@ pub struct IdentifyRunner {
     errors: ErrorCollector,
     name_builder: NameScopeBuilder,
     type_builder: TypeScopeBuilder,
-    graph: TypeGraph
+    graph: TypeGraph,
+    /// Whether the builders are from a checked unit whose fns are in `unit`.
+    reuses_items: bool,
+    timings: Option<PassTimings>
 }
 
 impl IdentifyRunner {
-    fn new(unit: Unit, name: String) -> IdentifyRunner {
+    fn new(unit: Unit, name: String, timings: Option<PassTimings>)
+           -> IdentifyRunner {
         IdentifyRunner {
-            unit, name,
+            unit, name, timings,
             errors: ErrorCollector::new(),
             name_builder: NameScopeBuilder::new(),
             type_builder: TypeScopeBuilder::with_primitives(),
-            graph: TypeGraph::with_primitives()
+            graph: TypeGraph::with_primitives(),
+            reuses_items: false
+        }
+    }
+
+    /// Creates a runner to re-check fns whose bodies were edited, using the
+    /// builders of their `CheckedUnit` instead of identifying its items
+    /// again.
+    ///
+    /// `unit` only needs to hold the edited fns, which can call the checked
+    /// unit's other fns. Their signatures can't be changed.
+    pub fn with_checked_items(unit: Unit,
+                              name: String,
+                              name_builder: NameScopeBuilder,
+                              type_builder: TypeScopeBuilder)
+                              -> IdentifyRunner {
+        IdentifyRunner {
+            reuses_items: true,
+            name_builder,
+            type_builder,
+            .. IdentifyRunner::new(unit, name, None)
         }
     }
 
     pub fn identify(mut self) -> Result<CheckRunner, CompilationError> {
-        ASTIdentifier::new(&mut self.name_builder,
-                           &mut self.type_builder,
-                           &mut self.errors)
-            .visit_unit(&self.unit);
+        let start = Instant::now();
+        {
+            let mut identifier = ASTIdentifier::new(&mut self.name_builder,
+                                                    &mut self.type_builder,
+                                                    &mut self.errors);
+            if self.reuses_items {
+                identifier.visit_edited_unit(&self.unit);
+            }
+            else {
+                identifier.visit_unit(&self.unit);
+            }
+        }
+        // Units with identify errors can have default ids anywhere, so
+        // they're only checked if identification succeeded.
+        if self.errors.errors().is_empty() {
+            for error in DefaultIdChecker::default().analyze(&self.unit) {
+                self.errors.add_error(error);
+            }
+        }
         if !self.errors.errors().is_empty() {
             error!("IdentifyRunner: failed ASTIdentifer");
+            self.errors.sort_by_location();
             return Err(CompilationError::IdentificationError {
                 unit: self.unit,
                 name_builder: self.name_builder,
@@ -94,12 +284,26 @@ impl IdentifyRunner {
                 errors: self.errors
             })
         }
+        for lint in SelfAssignmentChecker::default().analyze(&self.unit) {
+            self.errors.add_lint(lint);
+        }
+        for lint in EmptyBlockChecker::default().analyze(&self.unit) {
+            self.errors.add_lint(lint);
+        }
+        if let Some(ref mut timings) = self.timings {
+            timings.identify = start.elapsed();
+        }
+        let start = Instant::now();
         ASTTypeChecker::new(&mut self.type_builder,
             &mut self.graph,
             &mut self.errors)
             .visit_unit(&self.unit);
+        if let Some(ref mut timings) = self.timings {
+            timings.typecheck = start.elapsed();
+        }
         if !self.errors.errors().is_empty() {
             error!("IdentifyRunner: failed ASTTypeChecker");
+            self.errors.sort_by_location();
             Err(CompilationError::CheckingError {
                 unit: self.unit,
                 type_builder: self.type_builder,
@@ -120,7 +324,8 @@ pub struct CheckRunner {
     errors: ErrorCollector,
     name_builder: NameScopeBuilder,
     type_builder: TypeScopeBuilder,
-    graph: TypeGraph
+    graph: TypeGraph,
+    timings: Option<PassTimings>
 }
 
 impl CheckRunner {
@@ -131,11 +336,22 @@ impl CheckRunner {
             errors: runner.errors,
             name_builder: runner.name_builder,
             type_builder: runner.type_builder,
-            graph: runner.graph
+            graph: runner.graph,
+            timings: runner.timings
         }
     }
 
+    pub fn unit(&self) -> &Unit {
+        &self.unit
+    }
+
+    /// The type inferences made about the identified unit.
+    pub fn type_graph(&self) -> &TypeGraph {
+        &self.graph
+    }
+
     pub fn check(mut self) -> Result<CheckedUnit, CompilationError> {
+        let start = Instant::now();
         let results = {
             let mut tc = TypeConcretifier::new(&self.type_builder,
                                                &mut self.errors,
@@ -143,8 +359,15 @@ impl CheckRunner {
             tc.visit_unit(&self.unit);
             tc.into_results()
         };
+        for error in LocalReferenceChecker::new(&results).analyze(&self.unit) {
+            self.errors.add_error(error);
+        }
+        if let Some(ref mut timings) = self.timings {
+            timings.concretify = start.elapsed();
+        }
         if !self.errors.errors().is_empty() {
             error!("CheckRunner: failed to type concretify");
+            self.errors.sort_by_location();
             Err(CompilationError::CheckingError {
                 unit: self.unit,
                 type_builder: self.type_builder,
@@ -153,7 +376,11 @@ impl CheckRunner {
             })
         }
         else {
-            Ok(CheckedUnit::new(self.unit, self.name, results))
+            for lint in FloatEqualityChecker::new(&results).analyze(&self.unit) {
+                self.errors.add_lint(lint);
+            }
+            self.errors.sort_by_location();
+            Ok(CheckedUnit::new(self, results))
         }
     }
 }
@@ -162,34 +389,111 @@ impl CheckRunner {
 pub struct CheckedUnit {
     unit: Unit,
     name: String,
-    map: TypeMapping
+    map: TypeMapping,
+    /// Warnings and lints found while checking the unit.
+    errors: ErrorCollector,
+    name_builder: NameScopeBuilder,
+    type_builder: TypeScopeBuilder,
+    timings: Option<PassTimings>
 }
 impl CheckedUnit {
-    fn new(unit: Unit, name: String, map: TypeMapping) -> CheckedUnit {
-        CheckedUnit { unit, name, map }
+    fn new(runner: CheckRunner, map: TypeMapping) -> CheckedUnit {
+        CheckedUnit {
+            unit: runner.unit,
+            name: runner.name,
+            map,
+            errors: runner.errors,
+            name_builder: runner.name_builder,
+            type_builder: runner.type_builder,
+            timings: runner.timings
+        }
     }
 
     pub fn unit(&self) -> &Unit {
         &self.unit
     }
 
+    /// The names of the unit's items, which can be reused to re-check an
+    /// edited fn with `IdentifyRunner::with_checked_items`.
+    pub fn name_builder(&self) -> &NameScopeBuilder {
+        &self.name_builder
+    }
+
+    /// The types of the unit's items, which can be reused to re-check an
+    /// edited fn with `IdentifyRunner::with_checked_items`.
+    pub fn type_builder(&self) -> &TypeScopeBuilder {
+        &self.type_builder
+    }
+
     pub fn type_map(&self) -> &TypeMapping {
         &self.map
     }
+
+    /// Lints found in the unit, in source order.
+    pub fn lints(&self) -> &[CheckerError] {
+        self.errors.lints()
+    }
+
+    /// How long the passes up to checking took, if they were being timed.
+    pub fn timings(&self) -> Option<&PassTimings> {
+        self.timings.as_ref()
+    }
 }
 
 pub struct CompileRunner<'ctx> {
-    context: &'ctx Context
+    context: &'ctx Context,
+    panic_handler: String,
+    float_comparisons: FloatComparisons,
+    /// Timings of the last unit compiled, if it was being timed.
+    timings: Option<PassTimings>
 }
 impl<'ctx> CompileRunner<'ctx> {
     pub fn new(context: &'ctx Context) -> CompileRunner<'ctx> {
-        CompileRunner { context }
+        CompileRunner {
+            context,
+            panic_handler: DEFAULT_PANIC_HANDLER.to_string(),
+            float_comparisons: FloatComparisons::default(),
+            timings: None
+        }
+    }
+
+    /// Sets the name of the runtime function called when an `assert` fails.
+    ///
+    /// See `ModuleCompiler::set_panic_handler`.
+    pub fn set_panic_handler<S: Into<String>>(&mut self, name: S) {
+        self.panic_handler = name.into();
+    }
+
+    /// Sets how float comparisons treat NaN.
+    ///
+    /// See `FloatComparisons`.
+    pub fn set_float_comparisons(&mut self, comparisons: FloatComparisons) {
+        self.float_comparisons = comparisons;
+    }
+
+    /// How long each pass took for the last unit compiled, if it was
+    /// started with `Runner::with_timings`.
+    pub fn timings(&self) -> Option<&PassTimings> {
+        self.timings.as_ref()
     }
 
     pub fn compile(&mut self, unit: CheckedUnit, optimizations: bool)
                    -> SimpleModuleProvider<'ctx> {
+        self.compile_with_progress(unit, optimizations, |_name, _ir| { })
+    }
+
+    /// Compiles the unit, calling `progress` with the name and IR of each
+    /// function as soon as it has been compiled.
+    pub fn compile_with_progress<F>(&mut self,
+                                    unit: CheckedUnit,
+                                    optimizations: bool,
+                                    mut progress: F)
+                                    -> SimpleModuleProvider<'ctx>
+                                    where F: FnMut(&str, String) {
+        let start = Instant::now();
+        self.timings = unit.timings;
         let module = self.context.new_module(&unit.name);
-        {
+        let provider = {
             let builder = Builder::new(&self.context);
             let mut ir_code = Vec::new();
             let mut scopes = HashMap::new();
@@ -203,11 +507,291 @@ impl<'ctx> CompileRunner<'ctx> {
                     &mut ir_code,
                     &mut scopes,
                     optimizations);
+                compiler.on_fn_compiled(&mut progress);
+                compiler.set_panic_handler(self.panic_handler.clone());
+                compiler.set_float_comparisons(self.float_comparisons);
                 compiler.visit_unit(&unit.unit);
 
                 let (provider, _types) = compiler.decompose();
                 provider
             }
+        };
+        if let Some(ref mut timings) = self.timings {
+            timings.compile = start.elapsed();
+        }
+        provider
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use llvm_sys::analysis::LLVMVerifierFailureAction;
+
+    use compile::ModuleProvider;
+    use llvm::Context;
+    use std::io::{Cursor, ErrorKind};
+    use std::time::Duration;
+
+    use lex::IterTokenizer;
+    use parse::Parser;
+    use super::{Runner, IdentifyRunner, CheckRunner, CompileRunner,
+                CompilationError, compile_source_to_ir, check_source,
+                check_runner};
+
+    #[test]
+    fn progress_is_reported_for_each_fn() {
+        let checked = check_source(
+            "fn double(x: float) -> float\n\
+            \x20   x * 2\n\
+            \n\
+            fn main()\n\
+            \x20   let y: float = double(x: 4)\n");
+        let context = Context::new();
+        let mut compiled = Vec::new();
+        CompileRunner::new(&context).compile_with_progress(checked, false,
+            |name, ir| compiled.push((name.to_string(), ir)));
+
+        let names: Vec<&str> = compiled.iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["double", "main"]);
+        assert!(compiled[0].1.contains("define double @double(double %x)"),
+                "Unexpected IR for double:\n{}", compiled[0].1);
+    }
+
+    #[test]
+    fn timed_pipeline_records_each_pass() {
+        let source = "fn double(x: float) -> float\n\
+                      \x20   x * 2\n\
+                      \n\
+                      fn main()\n\
+                      \x20   let y: float = double(x: 4)\n";
+        let checked = check_runner(Runner::from_string(source, "test".into())
+            .with_timings());
+        assert_eq!(checked.timings().map(|timings| timings.compile),
+                   Some(Duration::from_secs(0)));
+        let context = Context::new();
+        let mut compiler = CompileRunner::new(&context);
+        compiler.compile(checked, false);
+        let timings = compiler.timings().expect("Pipeline was not timed");
+        // Fast passes can take no measurable time, so only the passes and
+        // their order are checked.
+        let passes = timings.passes().iter()
+            .map(|&(pass, _)| pass)
+            .collect::<Vec<_>>();
+        assert_eq!(passes,
+                   ["parse", "identify", "typecheck", "concretify", "compile"]);
+        for &(pass, duration) in timings.passes().iter() {
+            assert!(timings.total() >= duration,
+                    "{} pass took longer than the pipeline: {:?}",
+                    pass, timings);
+        }
+
+        assert_eq!(check_source(source).timings(), None);
+    }
+
+    #[test]
+    fn prelude_fns_can_be_called_without_declaring() {
+        let source = "fn main()\n\
+                      \x20   let x: float = sqrt(x: 16)\n\
+                      \x20   print(value: x)\n";
+        assert!(Runner::from_string(source, "test".into())
+                    .parse().expect("Could not parse test source")
+                    .identify().is_err(),
+                "Called prelude fns without a prelude");
+
+        let checked = check_runner(Runner::from_string(source, "test".into())
+            .with_prelude());
+        let context = Context::new();
+        let provider = CompileRunner::new(&context).compile(checked, false);
+        let ir = provider.module().print_to_string();
+        assert_eq!(ir.matches("declare void @print(double").count(), 1,
+                   "print should be declared once:\n{}", ir);
+        assert!(ir.contains("call void @print(double"),
+                "print was not called:\n{}", ir);
+    }
+
+    #[test]
+    fn merged_units_share_their_scopes() {
+        let parse = |source: &str| Parser::new(IterTokenizer::new(source.chars()))
+            .parse_unit().expect("Could not parse test source");
+        let prelude = parse("fn double(x: float) -> float\n    x * 2\n");
+        let mut unit = parse("fn main()\n    let y: float = double(x: 4)\n");
+        assert_eq!(unit.merge(prelude.clone()), vec![]);
+        assert_eq!(unit.items().len(), 2);
+
+        let checked = IdentifyRunner::new(unit, "test".into(), None)
+            .identify().expect("Could not identify merged unit")
+            .check().expect("Could not check merged unit");
+        let context = Context::new();
+        let provider = CompileRunner::new(&context).compile(checked, false);
+        let ir = provider.module().print_to_string();
+        assert!(ir.contains("define double @double(double"),
+                "double was not defined:\n{}", ir);
+        assert!(ir.contains("call double @double(double"),
+                "double was not called:\n{}", ir);
+
+        let mut redefined = parse("fn double(x: float) -> float\n    x + x\n");
+        let clashes = redefined.merge(prelude);
+        assert_eq!(clashes.len(), 1);
+        assert_eq!(clashes[0].0.name(), "double");
+        assert_eq!(clashes[0].1.name(), "double");
+        assert_eq!(redefined.items().len(), 1);
+    }
+
+    #[test]
+    fn redeclared_prelude_fns_are_identify_errors() {
+        let source = "fn main()\n\
+                      \x20   print(value: sqrt(x: 4))\n\
+                      \n\
+                      fn sqrt(x: float) -> float\n\
+                      \x20   x\n";
+        let result = Runner::from_string(source, "test".into())
+            .with_prelude()
+            .parse().expect("Could not parse test source")
+            .identify();
+        let errors = match result {
+            Err(CompilationError::IdentificationError { errors, .. }) => errors,
+            other => panic!("Redeclared a prelude fn: {:?}", other)
+        };
+        // Only the clash is reported: the other prelude fns are still
+        // merged, and `sqrt` refers to the unit's fn.
+        assert_eq!(errors.errors().len(), 1, "{:?}", errors.errors());
+        let error = &errors.errors()[0];
+        assert_eq!(error.text(), "sqrt is declared by the prelude, so it \
+                                  can't be declared in the unit");
+        let offender = error.offender().expect("Error had no span");
+        assert_eq!(offender.start().line(), 3);
+        assert_eq!(error.notes().len(), 1);
+        assert!(error.notes()[0].span().is_some());
+    }
+
+    #[test]
+    fn emit_ast_prints_parsed_fn() {
+        let ast = Runner::from_string("fn answer() -> float\n\
+                                       \x20   let x = 42\n\
+                                       \x20   x\n", "test".into())
+            .emit_ast().expect("Could not parse test source");
+        assert!(ast.contains("BlockFnDeclaration"), "No fn in AST:\n{}", ast);
+        assert!(ast.contains("\"answer\""), "No fn name in AST:\n{}", ast);
+        assert!(ast.contains("Declaration"), "No let in AST:\n{}", ast);
+        assert!(ast.contains("\"42\""), "No literal in AST:\n{}", ast);
+        assert!(ast.contains("VariableRef"), "No x in AST:\n{}", ast);
+    }
+
+    #[test]
+    fn empty_sources_compile_to_empty_modules() {
+        for source in &["", "   \n\n  \t\n", "// just a comment\n\n// and another"] {
+            let checked = check_source(source);
+            let context = Context::new();
+            let provider = CompileRunner::new(&context).compile(checked, false);
+            let module = provider.module();
+            assert!(module.verify(LLVMVerifierFailureAction::LLVMReturnStatusAction)
+                          .is_ok(),
+                    "Module of {:?} did not verify", source);
+            assert!(!module.print_to_string().contains("define"),
+                    "Module of {:?} was not empty", source);
+        }
+    }
+
+    #[test]
+    fn compiled_module_has_the_unit_fns() {
+        let checked = check_source("fn double(x: float) -> float\n\
+                                   \x20   x * 2\n\
+                                   fn main()\n\
+                                   \x20   double(x: 1)\n");
+        let context = Context::new();
+        let provider = CompileRunner::new(&context).compile(checked, false);
+        let names = provider.module().functions()
+            .map(|function| function.get_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["double", "main"]);
+    }
+
+    #[test]
+    fn runner_compiles_from_reader() {
+        let source = b"fn double(x: float) -> float\n    x * 2\n";
+        let mut buffer = String::new();
+        let runner = Runner::from_reader(Cursor::new(&source[..]),
+                                         "cursor".into(), &mut buffer)
+            .expect("Could not read from cursor");
+        let checked = check_runner(runner);
+        let context = Context::new();
+        let provider = CompileRunner::new(&context).compile(checked, false);
+        let ir = provider.module().print_to_string();
+        assert!(ir.contains("define double @double(double %x)"),
+                "double not compiled:\n{}", ir);
+
+        let mut buffer = String::new();
+        let error = Runner::from_reader(Cursor::new(&b"fn \xff()"[..]),
+                                        "cursor".into(), &mut buffer)
+            .expect_err("Read invalid UTF-8");
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn edited_fns_are_checked_against_checked_items() {
+        let main_source = "fn main()\n\
+                           \x20   let y: float = double(x: 3)\n\
+                           \x20   let z = y > 4\n\
+                           \x20   let g = double\n";
+        let source = format!("fn double(x: float) -> float\n\
+                              \x20   x * 2\n{}", main_source);
+        let checked = check_source(&source);
+        let recheck = |source: &str| {
+            let unit = Parser::new(IterTokenizer::new(source.chars()))
+                .parse_unit().expect("Could not parse edited fn");
+            IdentifyRunner::with_checked_items(unit, "test".into(),
+                    checked.name_builder().clone(),
+                    checked.type_builder().clone())
+                .identify()
+                .and_then(CheckRunner::check)
+                .map_err(|err| match err {
+                    CompilationError::IdentificationError { errors, .. }
+                    | CompilationError::CheckingError { errors, .. } => errors,
+                    CompilationError::ParseError(error) =>
+                        panic!("Unexpected parse error {:?}", error)
+                })
+        };
+
+        let rechecked = recheck(main_source).expect("Could not re-check main");
+        let main = rechecked.unit().items()[0].ident();
+        assert_eq!(main.id(), checked.unit().items()[1].ident().id());
+        assert_eq!(rechecked.type_map().len(), 5, "{:?}", rechecked.type_map());
+        for (id, concrete) in rechecked.type_map() {
+            assert_eq!(Some(concrete), checked.type_map().get(id),
+                       "Type of {:?} changed", id);
+        }
+        assert_eq!(rechecked.type_builder(), checked.type_builder());
+
+        match recheck("fn main(x: float)\n    let y = x\n") {
+            Err(errors) => {
+                assert_eq!(errors.errors().len(), 1);
+                assert!(errors.errors()[0].text()
+                        .starts_with("The signature of function main"),
+                        "Unexpected error {}", errors.errors()[0]);
+            },
+            other => panic!("Changed signature was re-checked: {:?}", other)
+        }
+    }
+
+    #[test]
+    fn source_compiles_to_ir_in_one_call() {
+        let ir = compile_source_to_ir("fn double(x: float) -> float\n\
+                                       \x20   x * 2\n", "test")
+            .expect("Could not compile test source");
+        assert!(ir.contains("define double @double(double"),
+                "Fn not in IR:\n{}", ir);
+
+        match compile_source_to_ir("fn main()\n    let = 1\n", "test") {
+            Err(CompilationError::ParseError(_)) => { },
+            other => panic!("Expected a parse error, got {:?}", other)
+        }
+        match compile_source_to_ir("fn main()\n    x\n", "test") {
+            Err(CompilationError::IdentificationError { errors, .. }) =>
+                assert_eq!(errors.errors().len(), 1),
+            other => panic!("Expected an identification error, got {:?}",
+                            other)
         }
     }
 }
@@ -15,7 +15,9 @@ use std::env;
 use std::fs::File;
 use std::io::{Read, Write};
 
-use protosnirk::llvm::{Context};
+use protosnirk::llvm::{Context, ExecutionEngine};
+use protosnirk::llvm::execution_engine::add_symbol;
+use protosnirk::compile::ModuleProvider;
 use protosnirk::pipeline::{Runner, CompileRunner, CompilationError};
 
 fn init_logs() {
@@ -55,6 +57,9 @@ pub enum TestMode {
     ParseOk,
     /// Test must compile and check properly
     CompileOk,
+    /// Test must compile, and its `main()` must run without failing an
+    /// `assert`
+    RunOk,
     /// Test must fail to parse
     ParseFail,
     /// Test must fail checking or compiling
@@ -65,7 +70,7 @@ impl TestMode {
     pub fn is_ok(&self) -> bool {
         use TestMode::*;
         match self {
-            ParseOk | CompileOk => true,
+            ParseOk | CompileOk | RunOk => true,
             ParseFail | CompileFail => false
         }
     }
@@ -96,6 +101,9 @@ impl Test {
             else if name.ends_with("parse-fail") {
                 TestMode::ParseFail
             }
+            else if name.ends_with("run-ok") {
+                TestMode::RunOk
+            }
             else if name.ends_with("-ok") {
                 TestMode::CompileOk
             }
@@ -174,7 +182,9 @@ fn compile_runner(test: Test) -> TestResult {
             if let Ok(print_ast) = env::var("SNIRK_PRINT_AST") {
                 let unit = match errors {
                     CompilationError::IdentificationError { ref unit, .. } => unit,
-                    CompilationError::CheckingError { ref unit, .. } => unit
+                    CompilationError::CheckingError { ref unit, .. } => unit,
+                    CompilationError::ParseError(_) =>
+                        unreachable!("Checked for bad parse result")
                 };
                 if print_ast.to_lowercase() == "full" {
                     info!("AST:\n{:#?}\n", unit);
@@ -212,13 +222,47 @@ fn compile_runner(test: Test) -> TestResult {
 
     info!("Code checked sucessfully.\n");
 
-    {
-        let context = Context::new();
-        let mut compiler = CompileRunner::new(&context);
+    let context = Context::new();
+    let mut compiler = CompileRunner::new(&context);
+    if test.mode() != TestMode::RunOk {
         let _module = compiler.compile(checked, false);
+        return Ok(())
+    }
+
+    compiler.set_panic_handler(TEST_PANIC_HANDLER);
+    let provider = compiler.compile(checked, false);
+    unsafe {
+        add_symbol(TEST_PANIC_HANDLER, test_panic as *mut _);
+        add_symbol("print", test_print as *mut _);
     }
+    let engine = try!(ExecutionEngine::for_module(provider.module().clone())
+        .map_err(|error| format!("Could not JIT {}: {}", test.path(), error)));
+    let main = try!(engine.function_address("main")
+        .ok_or_else(|| format!("Test {} has no main()", test.path())));
+    info!("Running main()\n");
+    let main: extern "C" fn() = unsafe {
+        ::std::mem::transmute(main as usize)
+    };
+    main();
     Ok(())
 }
 
+/// Panic handler for `run-ok` tests, which is called when an `assert` fails.
+const TEST_PANIC_HANDLER: &str = "__protosnirk_test_panic";
+
+/// Reports a failed `assert` in a `run-ok` test.
+///
+/// The handler can't return, or unwind through the compiled code, so the
+/// test process is aborted.
+extern "C" fn test_panic() {
+    eprintln!("An assert failed in a run-ok test");
+    ::std::process::abort();
+}
+
+/// The prelude's `print`, which a failed `assert_eq` uses to show its values.
+extern "C" fn test_print(value: f64) {
+    eprintln!("{}", value);
+}
+
 #[derive(IntegrationTests)]
 struct _Placeholder;
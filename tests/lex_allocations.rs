@@ -0,0 +1,78 @@
+//! Counts the allocations made while tokenizing.
+//!
+//! This is its own test binary so its counting allocator doesn't affect the
+//! other tests.
+
+extern crate protosnirk;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use protosnirk::lex::{IterTokenizer, StrTokenizer, TokenData};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize)
+                      -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Source with `count` identifiers, spread over lines.
+fn identifiers(count: usize) -> String {
+    (0..count)
+        .map(|ix| {
+            let separator = if ix % 8 == 7 { "\n" } else { " " };
+            format!("ident_{}{}", ix, separator)
+        })
+        .collect()
+}
+
+/// Reads identifier tokens until EOF, giving their count and the number of
+/// allocations made.
+fn count_allocations<F: FnMut() -> TokenData>(mut next: F) -> (usize, usize) {
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    let mut idents = 0;
+    loop {
+        match next() {
+            TokenData::Ident => idents += 1,
+            TokenData::EOF => break,
+            other => panic!("Unexpected token {:?}", other)
+        }
+    }
+    (idents, ALLOCATIONS.load(Ordering::SeqCst) - before)
+}
+
+#[test]
+fn borrowed_identifier_tokens_do_not_allocate() {
+    let source = identifiers(10_000);
+
+    let mut owned = IterTokenizer::new(source.chars());
+    let (idents, owned_allocations) =
+        count_allocations(|| owned.next().data());
+    assert_eq!(idents, 10_000);
+    assert!(owned_allocations >= idents,
+            "Owned tokens made {} allocations", owned_allocations);
+
+    let mut borrowed = StrTokenizer::new(&source);
+    let (idents, borrowed_allocations) =
+        count_allocations(|| borrowed.next().data());
+    assert_eq!(idents, 10_000);
+    assert_eq!(borrowed_allocations, 0);
+}